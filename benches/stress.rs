@@ -0,0 +1,23 @@
+//! `cargo bench`: replaying the journal, rolling up the subgoals and drawing
+//! the screen, through `sw --stress`, on sessions of a few sizes, with the
+//! optimized build. Compare the figures before and after a change.
+
+use std::process::Command;
+
+const SIZES: &[&str] = &["50", "200", "1000"];
+
+fn main() {
+    for splits in SIZES {
+        let output = Command::new(env!("CARGO_BIN_EXE_sw"))
+            .args(["--stress", "--splits", splits, "--ticks", "2000"])
+            .output()
+            .expect("running sw --stress");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        print!("{}", String::from_utf8_lossy(&output.stdout));
+        println!();
+    }
+}
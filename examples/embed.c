@@ -0,0 +1,42 @@
+/*
+ * Drives the timer library from C.
+ *
+ *   cargo build --lib
+ *   cc examples/embed.c -Iinclude -Ltarget/debug -lstopwatch -o embed
+ *   LD_LIBRARY_PATH=target/debug ./embed
+ */
+
+#include <stdio.h>
+#include <unistd.h>
+
+#include "stopwatch.h"
+
+int main(void)
+{
+    if (sw_api_version() != SW_API_VERSION) {
+        fprintf(stderr, "library is API version %u, expected %u\n",
+                sw_api_version(), SW_API_VERSION);
+        return 1;
+    }
+
+    SwTimer *timer = sw_timer_new();
+    sw_timer_start(timer, "Write the report");
+    sw_timer_split(timer, "Outline", -1);
+    usleep(100 * 1000);
+    sw_timer_stop(timer);
+    sw_timer_split(timer, "Draft", -1);
+    sw_timer_split(timer, "Introduction", 1);
+    usleep(50 * 1000);
+
+    if (sw_timer_split(timer, "Nowhere", 7) != 0)
+        printf("error: %s\n", sw_timer_last_error(timer));
+    if (sw_timer_apply(timer, "{\"t\":\"2024-05-01T09:00:00Z\",\"event\":\"rename\","
+                              "\"split\":0,\"name\":\"Plan\"}") != 0)
+        printf("error: %s\n", sw_timer_last_error(timer));
+
+    char *state = sw_timer_serialize(timer);
+    printf("%llu ms\n%s\n", (unsigned long long)sw_timer_elapsed_ms(timer), state);
+    sw_string_free(state);
+    sw_timer_free(timer);
+    return 0;
+}
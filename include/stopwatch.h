@@ -0,0 +1,105 @@
+#ifndef STOPWATCH_H
+#define STOPWATCH_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Bumped whenever a function in `include/stopwatch.h` changes; new
+// functions can be added without it.
+#define SW_API_VERSION 1
+
+// A timer for C callers, behind a pointer from `sw_timer_new`. It isn't
+// safe to use from two threads at once.
+typedef struct SwTimer SwTimer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+uint32_t sw_api_version(void);
+
+// A new timer, stopped and without a goal. Free it with `sw_timer_free`.
+SwTimer *sw_timer_new(void);
+
+// # Safety
+//
+// `timer` must come from `sw_timer_new` and not be used afterwards. NULL
+// is ignored.
+void sw_timer_free(SwTimer *timer);
+
+// Start timing `goal`, dropping any splits of the goal before.
+//
+// # Safety
+//
+// `timer` must be a live timer and `goal` a NUL-terminated string.
+int sw_timer_start(SwTimer *timer, const char *goal);
+
+// # Safety
+//
+// `timer` must be a live timer.
+int sw_timer_pause(SwTimer *timer);
+
+// # Safety
+//
+// `timer` must be a live timer.
+int sw_timer_resume(SwTimer *timer);
+
+// Start a split named `name` under the split at index `parent`, or at the
+// top level when `parent` is negative. It becomes the active split.
+//
+// # Safety
+//
+// `timer` must be a live timer and `name` a NUL-terminated string.
+int sw_timer_split(SwTimer *timer, const char *name, int64_t parent);
+
+// Stop the active split; its parent, if any, becomes the active one.
+//
+// # Safety
+//
+// `timer` must be a live timer.
+int sw_timer_stop(SwTimer *timer);
+
+// Apply any action as a journal record; see the journal format.
+//
+// # Safety
+//
+// `timer` must be a live timer and `record` a NUL-terminated string.
+int sw_timer_apply(SwTimer *timer, const char *record);
+
+// Active time so far, in milliseconds.
+//
+// # Safety
+//
+// `timer` must be a live timer.
+uint64_t sw_timer_elapsed_ms(const SwTimer *timer);
+
+// The timer's state as a JSON object, to be freed with `sw_string_free`.
+//
+// # Safety
+//
+// `timer` must be a live timer.
+char *sw_timer_serialize(const SwTimer *timer);
+
+// Why the last call on `timer` that returned -1 failed. The text belongs
+// to the timer and lasts until its next failure.
+//
+// # Safety
+//
+// `timer` must be a live timer.
+const char *sw_timer_last_error(const SwTimer *timer);
+
+// # Safety
+//
+// `s` must come from `sw_timer_serialize` and not be used afterwards. NULL
+// is ignored.
+void sw_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* STOPWATCH_H */
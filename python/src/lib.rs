@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use engine::journal;
+use engine::json::{self, Value};
+use engine::stopwatch::{Action, Stopwatch};
+use pyo3::exceptions::{PyIOError, PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// A timer with a goal and nested splits, as `sw` keeps it. Times are in
+/// seconds.
+#[pyclass(module = "stopwatch")]
+struct Timer {
+    sw: Stopwatch,
+}
+
+#[pymethods]
+impl Timer {
+    #[new]
+    fn new() -> Self {
+        Timer {
+            sw: Stopwatch::new(),
+        }
+    }
+
+    /// Start timing `goal`, dropping the splits of any goal before.
+    #[pyo3(signature = (goal, project=None))]
+    fn start(&mut self, goal: String, project: Option<String>) {
+        self.sw.apply(&Action::Start { goal, project });
+    }
+
+    fn pause(&mut self) {
+        self.sw.apply(&Action::Pause);
+    }
+
+    fn resume(&mut self) {
+        self.sw.apply(&Action::Resume);
+    }
+
+    /// Start a split under the split at index `parent`, or at the top level,
+    /// and return its index.
+    #[pyo3(signature = (name, parent=None))]
+    fn split(&mut self, name: String, parent: Option<usize>) -> PyResult<usize> {
+        if let Some(p) = parent.filter(|&p| p >= self.sw.splits.len()) {
+            return Err(PyIndexError::new_err(format!("no split {}", p)));
+        }
+        self.sw.apply(&Action::Split { name, parent });
+        Ok(self.sw.splits.len() - 1)
+    }
+
+    /// Stop the active split; its parent, if any, becomes the active one.
+    fn stop(&mut self) {
+        self.sw.apply(&Action::StopActive);
+    }
+
+    /// Apply any action as a journal record, like the lines of `--journal`.
+    fn apply(&mut self, record: &str) -> PyResult<()> {
+        journal::apply(&mut self.sw, record).map_err(PyValueError::new_err)
+    }
+
+    #[getter]
+    fn goal(&self) -> Option<String> {
+        self.sw.main_goal.clone()
+    }
+
+    #[getter]
+    fn project(&self) -> Option<String> {
+        self.sw.project.clone()
+    }
+
+    #[getter]
+    fn running(&self) -> bool {
+        self.sw.running
+    }
+
+    /// Active time so far.
+    #[getter]
+    fn elapsed(&self) -> f64 {
+        self.sw.total().as_secs_f64()
+    }
+
+    /// Time spent paused since the goal was started.
+    #[getter]
+    fn paused(&self) -> f64 {
+        self.sw.paused().as_secs_f64()
+    }
+
+    /// One dict per split, in tree order, ready for `pandas.DataFrame`:
+    /// name, parent, level, start and end (into the goal), elapsed, rollup
+    /// (the time of its direct subgoals), started and ended (ISO 8601 local
+    /// time) and whether it is the active one.
+    fn splits<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let total = self.sw.total();
+        let secs = |d: Duration| d.as_secs_f64();
+        let mut rows = Vec::with_capacity(self.sw.splits.len());
+        for (i, split) in self.sw.splits.iter().enumerate() {
+            let row = PyDict::new_bound(py);
+            row.set_item("name", &split.name)?;
+            row.set_item("parent", split.parent)?;
+            row.set_item("level", split.level)?;
+            row.set_item("start", secs(split.start_offset))?;
+            row.set_item("end", split.end_offset.map(secs))?;
+            row.set_item("elapsed", secs(split.length(total)))?;
+            row.set_item("rollup", self.sw.rollup(i, total).map(secs))?;
+            row.set_item("started", split.start_dt.to_rfc3339())?;
+            row.set_item("ended", split.end_dt.map(|t| t.to_rfc3339()))?;
+            row.set_item("project", &split.project)?;
+            row.set_item("active", self.sw.active == Some(i))?;
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Timer(goal={:?}, running={}, elapsed={:.3}, splits={})",
+            self.sw.main_goal,
+            self.sw.running,
+            self.elapsed(),
+            self.sw.splits.len()
+        )
+    }
+}
+
+/// The session left in the journal at `path`, as `sw` would recover it.
+#[pyfunction]
+fn load(path: &str) -> PyResult<Timer> {
+    let records = journal::read(path).map_err(|e| PyIOError::new_err(e.to_string()))?;
+    Ok(Timer {
+        sw: journal::replay(&records).unwrap_or_default(),
+    })
+}
+
+/// Every record in the journal at `path` as a dict, oldest first: `t`,
+/// `event` and the event's own fields.
+#[pyfunction]
+fn history<'py>(py: Python<'py>, path: &str) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let records = journal::read(path).map_err(|e| PyIOError::new_err(e.to_string()))?;
+    let mut rows = Vec::with_capacity(records.len());
+    for record in &records {
+        let row = PyDict::new_bound(py);
+        let fields = json::parse_object(&journal::encode(record)).map_err(PyValueError::new_err)?;
+        for (key, value) in fields {
+            let value = match value {
+                Value::Str(s) => s.into_py(py),
+                Value::Num(n) if n.fract() == 0.0 => (n as i64).into_py(py),
+                Value::Num(n) => n.into_py(py),
+                Value::Bool(b) => b.into_py(py),
+                Value::Null => py.None(),
+            };
+            row.set_item(key, value)?;
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[pymodule]
+#[pyo3(name = "stopwatch")]
+fn module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Timer>()?;
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_function(wrap_pyfunction!(history, m)?)?;
+    Ok(())
+}
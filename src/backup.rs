@@ -0,0 +1,138 @@
+use std::{env, fmt, io, path::Path};
+
+use crate::platform;
+
+/// Where saved logs are copied to after each save, with `curl`, so the time
+/// kept survives the machine it was kept on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Backup {
+    /// A WebDAV collection the files are put into. The login comes from
+    /// `~/.netrc`.
+    Dav(String),
+    /// A bucket of S3 or a service like it, and the key prefix to put the
+    /// files under. Requests are signed with `AWS_ACCESS_KEY_ID` and
+    /// `AWS_SECRET_ACCESS_KEY` for the region in `AWS_REGION`.
+    S3 { bucket: String, prefix: String },
+}
+
+impl Backup {
+    /// `https://dav.example.com/sw` or `s3://bucket/prefix`.
+    pub fn parse(url: &str) -> Result<Backup, String> {
+        if let Some(rest) = url.strip_prefix("s3://") {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty() {
+                return Err(format!("no bucket in '{}'", url));
+            }
+            return Ok(Backup::S3 {
+                bucket: bucket.to_string(),
+                prefix: prefix.trim_matches('/').to_string(),
+            });
+        }
+        if url.starts_with("https://") || url.starts_with("http://") {
+            return Ok(Backup::Dav(url.trim_end_matches('/').to_string()));
+        }
+        Err(format!(
+            "invalid backup URL '{}' (expected https://... for WebDAV or s3://bucket/prefix)",
+            url
+        ))
+    }
+
+    /// Upload each of `files` under its own name, replacing what is there.
+    /// `endpoint` is the S3 service, by default Amazon's for the region.
+    pub fn upload(&self, files: &[&Path], endpoint: Option<&str>) -> io::Result<()> {
+        for file in files {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            let mut args: Vec<String> = [
+                "--silent",
+                "--show-error",
+                "--fail",
+                "--max-time",
+                "60",
+                "--config",
+                "-",
+                "--upload-file",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+            args.push(file.display().to_string());
+            // the login goes in on stdin, out of sight of other users' `ps`
+            let mut config = String::new();
+            match self {
+                Backup::Dav(url) => {
+                    args.push("--netrc-optional".to_string());
+                    args.push(format!("{}/{}", url, encode(&name)));
+                }
+                Backup::S3 { bucket, prefix } => {
+                    let (id, secret) = match (
+                        env::var("AWS_ACCESS_KEY_ID"),
+                        env::var("AWS_SECRET_ACCESS_KEY"),
+                    ) {
+                        (Ok(id), Ok(secret)) => (id, secret),
+                        _ => {
+                            return Err(io::Error::other(
+                                "S3 backups need AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY",
+                            ))
+                        }
+                    };
+                    let region = env::var("AWS_REGION")
+                        .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+                        .unwrap_or_else(|_| "us-east-1".to_string());
+                    config.push_str(&format!(
+                        "user = \"{}\"\n",
+                        quote(&format!("{}:{}", id, secret))
+                    ));
+                    if let Ok(token) = env::var("AWS_SESSION_TOKEN") {
+                        let header = format!("x-amz-security-token: {}", token);
+                        config.push_str(&format!("header = \"{}\"\n", quote(&header)));
+                    }
+                    args.push("--aws-sigv4".to_string());
+                    args.push(format!("aws:amz:{}:s3", region));
+                    let endpoint = match endpoint {
+                        Some(endpoint) => endpoint.trim_end_matches('/').to_string(),
+                        None => format!("https://s3.{}.amazonaws.com", region),
+                    };
+                    let key = match prefix.as_str() {
+                        "" => encode(&name),
+                        prefix => format!("{}/{}", prefix, encode(&name)),
+                    };
+                    args.push(format!("{}/{}/{}", endpoint, bucket, key));
+                }
+            }
+            platform::filter("curl", &args, config.as_bytes()).map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => io::Error::new(e.kind(), "backups need curl installed"),
+                _ => e,
+            })?;
+            log::info!("backed up {}", file.display());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Backup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backup::Dav(url) => write!(f, "{}", url),
+            Backup::S3 { bucket, prefix } => write!(f, "s3://{}/{}", bucket, prefix),
+        }
+    }
+}
+
+/// `name` fit for the last segment of a URL.
+fn encode(name: &str) -> String {
+    let mut encoded = String::new();
+    for b in name.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+/// `value` escaped for a quoted string in a curl config.
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
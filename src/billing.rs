@@ -0,0 +1,114 @@
+use std::{fmt, time::Duration};
+
+/// Hourly rates for billing mode: a default, plus rates for org-style tags
+/// at the end of a goal or subgoal name (`Fix login :acme:`).
+#[derive(Clone, Debug)]
+pub struct Rates {
+    pub default: Option<f64>,
+    pub tags: Vec<(String, f64)>,
+    pub currency: String,
+}
+
+impl Default for Rates {
+    fn default() -> Self {
+        Rates {
+            default: None,
+            tags: Vec::new(),
+            currency: "$".to_string(),
+        }
+    }
+}
+
+impl Rates {
+    /// The rate for `name`: that of its first tag with a rate, else `fallback`.
+    pub fn rate_for(&self, name: &str, fallback: Option<f64>) -> Option<f64> {
+        tags(name).find_map(|tag| self.tag_rate(tag)).or(fallback)
+    }
+
+    pub fn tag_rate(&self, tag: &str) -> Option<f64> {
+        self.tags
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|&(_, rate)| rate)
+    }
+
+    /// The rate for a main goal: from its tags, else its project's, else
+    /// the default.
+    pub fn goal_rate(&self, goal: &str, project: Option<&str>) -> Option<f64> {
+        let fallback = project.and_then(|p| self.tag_rate(p)).or(self.default);
+        self.rate_for(goal, fallback)
+    }
+
+    /// `$12.50` for `d` at `rate` per hour.
+    pub fn amount(&self, d: Duration, rate: f64) -> Amount<'_> {
+        Amount {
+            currency: &self.currency,
+            value: d.as_secs_f64() / 3600.0 * rate,
+        }
+    }
+}
+
+/// Money as `Rates::amount` shows it.
+pub struct Amount<'a> {
+    currency: &'a str,
+    value: f64,
+}
+
+impl fmt::Display for Amount<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{:.2}", self.currency, self.value)
+    }
+}
+
+/// Beancount accounts for `sw export --format beancount`: the income
+/// account time is booked to, per tag (or project) like the rates, and the
+/// account on the other side that holds it until it is invoiced.
+#[derive(Clone, Debug)]
+pub struct Accounts {
+    pub default: String,
+    pub tags: Vec<(String, String)>,
+    pub unbilled: String,
+}
+
+impl Default for Accounts {
+    fn default() -> Self {
+        Accounts {
+            default: "Income:Time".to_string(),
+            tags: Vec::new(),
+            unbilled: "Assets:Unbilled".to_string(),
+        }
+    }
+}
+
+impl Accounts {
+    /// The account for a (sub)goal `name`: that of its first tag with one,
+    /// else of the goal's tags, else of its project, else the default.
+    pub fn account_for(&self, name: &str, goal: &str, project: Option<&str>) -> &str {
+        let mapped = |tag: &str| {
+            self.tags
+                .iter()
+                .find(|(t, _)| t == tag)
+                .map(|(_, account)| account.as_str())
+        };
+        tags(name)
+            .chain(tags(goal))
+            .chain(project)
+            .find_map(mapped)
+            .unwrap_or(&self.default)
+    }
+}
+
+/// Tags of an org heading: `Fix login :acme:web:` has `acme` and `web`.
+pub fn tags(name: &str) -> impl Iterator<Item = &str> {
+    let last = name.split_whitespace().last().unwrap_or("");
+    let tagged = last.len() > 2 && last.starts_with(':') && last.ends_with(':');
+    let list = if tagged { &last[1..last.len() - 1] } else { "" };
+    list.split(':').filter(|t| !t.is_empty())
+}
+
+pub fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate >= 0.0 => Ok(rate),
+        _ => Err(format!("invalid hourly rate '{}'", s)),
+    }
+}
@@ -0,0 +1,333 @@
+use std::{path::Path, time::Duration};
+
+use chrono::{Days, NaiveDateTime};
+
+use crate::logfile;
+use crate::options::{CheckArgs, Options};
+use crate::orglog;
+
+/// Something wrong with one line of the log.
+struct Problem {
+    /// 0-based line index.
+    line: usize,
+    message: String,
+    fix: Option<Fix>,
+}
+
+/// How `--fix` repairs a problem.
+enum Fix {
+    /// Put a line before this one.
+    Insert(usize, String),
+    Remove,
+    Replace(String),
+}
+
+/// A closed CLOCK entry and the heading it belongs to.
+struct Clock {
+    line: usize,
+    heading: usize,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+}
+
+/// `sw check`: look for what hand edits tend to break in an org log:
+/// drawers that are never closed or closed twice, CLOCK lines that don't
+/// read, entries that end before they start, and clocked time counted twice
+/// by overlapping entries. With `--fix`, drawers are closed, stray `:END:`
+/// lines and duplicated CLOCK lines are dropped, and backwards entries are
+/// turned around; overlaps are only reported, since there is no telling
+/// which side is right.
+pub fn run(opts: &Options, args: &CheckArgs) -> Result<(), String> {
+    let path = &opts.log_file;
+    let problems = if args.fix {
+        let mut problems = Vec::new();
+        logfile::update(Path::new(path), &opts.crypt, |contents| {
+            let text = String::from_utf8_lossy(&contents).into_owned();
+            problems = check(&text);
+            fix(&text, &problems).into_bytes()
+        })
+        .map_err(|e| format!("{}: {}", path, e))?;
+        problems
+    } else {
+        let text =
+            orglog::load(Path::new(path), &opts.crypt).map_err(|e| format!("{}: {}", path, e))?;
+        check(&text)
+    };
+
+    let mut left = 0;
+    for problem in &problems {
+        let fixed = args.fix && problem.fix.is_some();
+        if !fixed {
+            left += 1;
+        }
+        println!(
+            "{}:{}: {}{}",
+            path,
+            problem.line + 1,
+            problem.message,
+            if fixed { " (fixed)" } else { "" }
+        );
+    }
+    match left {
+        0 => {
+            if !opts.quiet && problems.is_empty() {
+                println!("{}: no problems found", path);
+            }
+            Ok(())
+        }
+        1 => Err(format!("{}: 1 problem left", path)),
+        n => Err(format!("{}: {} problems left", path, n)),
+    }
+}
+
+fn check(text: &str) -> Vec<Problem> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut problems = Vec::new();
+    // heading titles and the index of each one's parent heading
+    let mut headings: Vec<(&str, Option<usize>)> = Vec::new();
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    let mut clocks: Vec<Clock> = Vec::new();
+    // the open drawer's name and line
+    let mut drawer: Option<(&str, usize)> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        let heading = orglog::parse_heading(line);
+        let opens = drawer_name(trimmed).filter(|&name| {
+            // inside a property drawer an empty `:NAME:` is just a property
+            name != "END"
+                && drawer.is_none_or(|(open, _)| open != "PROPERTIES" || name == "LOGBOOK")
+        });
+        if heading.is_some() || opens.is_some() {
+            if let Some((name, at)) = drawer.take() {
+                problems.push(Problem {
+                    line: at,
+                    message: format!(":{}: drawer is never closed", name),
+                    fix: Some(Fix::Insert(
+                        last_content(&lines, at, i) + 1,
+                        format!("{}:END:", indent(lines[at])),
+                    )),
+                });
+            }
+        }
+        if let Some((stars, title)) = heading {
+            while stack.last().is_some_and(|&(s, _)| s >= stars) {
+                stack.pop();
+            }
+            headings.push((title, stack.last().map(|&(_, h)| h)));
+            stack.push((stars, headings.len() - 1));
+            continue;
+        }
+        if let Some(name) = opens {
+            drawer = Some((name, i));
+            continue;
+        }
+        if trimmed == ":END:" {
+            if drawer.take().is_none() {
+                problems.push(Problem {
+                    line: i,
+                    message: ":END: without a drawer to close".to_string(),
+                    fix: Some(Fix::Remove),
+                });
+            }
+            continue;
+        }
+        let Some(clock) = trimmed.strip_prefix("CLOCK:") else {
+            continue;
+        };
+        if !clock.contains("--") && orglog::parse_timestamp(clock).is_some() {
+            problems.push(Problem {
+                line: i,
+                message: "CLOCK entry is still running and is not counted".to_string(),
+                fix: None,
+            });
+            continue;
+        }
+        let Some((start, end, written)) = orglog::parse_clock(clock) else {
+            problems.push(Problem {
+                line: i,
+                message: format!("can't read CLOCK entry '{}'", clock.trim()),
+                fix: None,
+            });
+            continue;
+        };
+        let (start, end) = if end < start {
+            let (fixed, new_start, new_end) = turn_around(line, clock, (start, end, written));
+            problems.push(Problem {
+                line: i,
+                message: format!(
+                    "CLOCK entry ends before it starts ({} before {})",
+                    stamp(end),
+                    stamp(start)
+                ),
+                fix: fixed.map(Fix::Replace),
+            });
+            (new_start, new_end)
+        } else {
+            (start, end)
+        };
+        let Some(heading) = headings.len().checked_sub(1) else {
+            problems.push(Problem {
+                line: i,
+                message: "CLOCK entry before the first heading".to_string(),
+                fix: None,
+            });
+            continue;
+        };
+        let repeated = clocks
+            .iter()
+            .find(|c| c.heading == heading && lines[c.line].trim() == trimmed);
+        if let Some(first) = repeated {
+            problems.push(Problem {
+                line: i,
+                message: format!("CLOCK entry repeats line {}", first.line + 1),
+                fix: Some(Fix::Remove),
+            });
+            continue;
+        }
+        clocks.push(Clock {
+            line: i,
+            heading,
+            start,
+            end,
+        });
+    }
+    if let Some((name, at)) = drawer {
+        problems.push(Problem {
+            line: at,
+            message: format!(":{}: drawer is never closed", name),
+            fix: Some(Fix::Insert(
+                last_content(&lines, at, lines.len()) + 1,
+                format!("{}:END:", indent(lines[at])),
+            )),
+        });
+    }
+
+    // a heading's clocks fall inside its parents', so only clocks on
+    // unrelated headings, or twice on the same one, can overlap
+    let ancestor = |a: usize, mut b: usize| loop {
+        match headings[b].1 {
+            Some(parent) if parent == a => return true,
+            Some(parent) => b = parent,
+            None => return false,
+        }
+    };
+    clocks.sort_by_key(|c| c.start);
+    for (n, a) in clocks.iter().enumerate() {
+        for b in clocks[n + 1..].iter().take_while(|b| b.start < a.end) {
+            if b.heading != a.heading
+                && (ancestor(a.heading, b.heading) || ancestor(b.heading, a.heading))
+            {
+                continue;
+            }
+            let (first, second) = if a.line < b.line { (a, b) } else { (b, a) };
+            problems.push(Problem {
+                line: second.line,
+                message: format!(
+                    "CLOCK entry overlaps line {} ('{}')",
+                    first.line + 1,
+                    headings[first.heading].0
+                ),
+                fix: None,
+            });
+        }
+    }
+    problems.sort_by_key(|p| p.line);
+    problems
+}
+
+/// The text with every fixable problem repaired.
+fn fix(text: &str, problems: &[Problem]) -> String {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let mut fixes: Vec<(usize, &Problem)> = problems
+        .iter()
+        .map(|p| match p.fix {
+            Some(Fix::Insert(at, _)) => (at, p),
+            _ => (p.line, p),
+        })
+        .collect();
+    // from the bottom up, so earlier line numbers stay put
+    fixes.sort_by_key(|&(at, _)| std::cmp::Reverse(at));
+    for (_, problem) in fixes {
+        match &problem.fix {
+            Some(Fix::Insert(at, line)) => lines.insert(*at, line.clone()),
+            Some(Fix::Remove) => {
+                lines.remove(problem.line);
+            }
+            Some(Fix::Replace(line)) => lines[problem.line] = line.clone(),
+            None => {}
+        }
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// `:NAME:` alone on a line.
+fn drawer_name(line: &str) -> Option<&str> {
+    let name = line.strip_prefix(':')?.strip_suffix(':')?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then_some(name)
+}
+
+/// The last non-blank line from `from` up to `to`.
+fn last_content(lines: &[&str], from: usize, to: usize) -> usize {
+    (from..to)
+        .rev()
+        .find(|&i| !lines[i].trim().is_empty())
+        .unwrap_or(from)
+}
+
+fn indent(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Repair a CLOCK entry that ends before it starts. When the written
+/// duration fits the two times the other way round they were swapped;
+/// otherwise an end earlier the same day most likely went past midnight, so
+/// it moves to the next day. The written duration is kept. Returns the new
+/// line, if one could be written, and the times.
+fn turn_around(
+    line: &str,
+    clock: &str,
+    (start, end, written): (NaiveDateTime, NaiveDateTime, Duration),
+) -> (Option<String>, NaiveDateTime, NaiveDateTime) {
+    let (range, duration) = match clock.split_once("=>") {
+        Some((range, duration)) => (range, Some(duration)),
+        None => (clock, None),
+    };
+    let Some((start_text, end_text)) = range.trim().split_once("--") else {
+        return (None, start, end);
+    };
+    let swapped = (start - end).to_std().ok() == Some(written);
+    let (new_start, new_end, start_text, end_text) = if start.date() == end.date() && !swapped {
+        let next = end + Days::new(1);
+        (start, next, start_text.to_string(), restamp(end_text, next))
+    } else {
+        (end, start, end_text.to_string(), start_text.to_string())
+    };
+    let mut fixed = format!("{}CLOCK: {}--{}", indent(line), start_text, end_text);
+    if let Some(duration) = duration {
+        fixed.push_str(" => ");
+        fixed.push_str(duration.trim());
+    }
+    (Some(fixed), new_start, new_end)
+}
+
+/// `stamp` rewritten for `time`, keeping a weekday if it had one.
+fn restamp(stamp: &str, time: NaiveDateTime) -> String {
+    let with_weekday = stamp.split_whitespace().count() > 2;
+    let format = if with_weekday {
+        "[%Y-%m-%d %a %H:%M]"
+    } else {
+        "[%Y-%m-%d %H:%M]"
+    };
+    time.format(format).to_string()
+}
+
+fn stamp(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M").to_string()
+}
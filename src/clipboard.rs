@@ -0,0 +1,116 @@
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// Clipboard helpers tried in order; the first one that can be spawned and
+/// exits successfully wins.
+const TOOLS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("pbcopy", &[]),
+    ("clip.exe", &[]),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// System tool locally, OSC 52 over SSH or when no tool is installed.
+    Auto,
+    /// Only the external clipboard tools.
+    System,
+    /// Only the OSC 52 terminal escape.
+    Osc52,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "auto" => Some(Mode::Auto),
+            "system" => Some(Mode::System),
+            "osc52" => Some(Mode::Osc52),
+            _ => None,
+        }
+    }
+}
+
+/// Copy `text` according to `mode`, returning a description of the mechanism
+/// that was used. `out` is the terminal, needed for the OSC 52 path.
+pub fn copy<W: Write>(out: &mut W, text: &str, mode: Mode) -> io::Result<&'static str> {
+    let remote = env::var_os("SSH_CONNECTION").is_some() || env::var_os("SSH_TTY").is_some();
+    match mode {
+        Mode::Osc52 => copy_osc52(out, text),
+        Mode::System => copy_system(text),
+        Mode::Auto if remote => copy_osc52(out, text),
+        Mode::Auto => match copy_system(text) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => copy_osc52(out, text),
+            result => result,
+        },
+    }
+}
+
+/// Ask the terminal itself to set the clipboard. Works across SSH as long as
+/// the terminal emulator allows it; inside tmux the sequence is wrapped in a
+/// passthrough so tmux forwards it.
+fn copy_osc52<W: Write>(out: &mut W, text: &str) -> io::Result<&'static str> {
+    let seq = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if env::var_os("TMUX").is_some() {
+        write!(out, "\x1bPtmux;\x1b{}\x1b\\", seq)?;
+    } else {
+        out.write_all(seq.as_bytes())?;
+    }
+    out.flush()?;
+    Ok("OSC 52")
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn copy_system(text: &str) -> io::Result<&'static str> {
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+    for &(tool, args) in TOOLS {
+        if tool == "wl-copy" && !wayland {
+            continue;
+        }
+        let mut child = match Command::new(tool)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(tool);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard tool found (install wl-copy, xclip or xsel)",
+    ))
+}
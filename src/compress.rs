@@ -0,0 +1,103 @@
+use std::{
+    fs, io,
+    path::Path,
+    process::{Command, Stdio},
+    time::{Duration, SystemTime},
+};
+
+use crate::platform;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// How old archived sessions are compressed, with the `gzip` or `zstd`
+/// program.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Codec {
+    #[default]
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    pub fn from_name(name: &str) -> Option<Codec> {
+        match name {
+            "gzip" | "gz" => Some(Codec::Gzip),
+            "zstd" | "zst" => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Codec::Gzip => "gzip",
+            Codec::Zstd => "zstd",
+        }
+    }
+
+    /// Arguments that compress a file in place, keeping its times.
+    fn in_place(self) -> &'static [&'static str] {
+        match self {
+            Codec::Gzip => &["-q"],
+            Codec::Zstd => &["-q", "--rm"],
+        }
+    }
+}
+
+/// Compress each session file in the archive directory last written more
+/// than `days` days ago, in place: `x.org` becomes `x.org.gz` or
+/// `x.org.zst`. Encrypted sessions are left alone, as they wouldn't get any
+/// smaller.
+pub fn compress_old(dir: &Path, codec: Codec, days: u64) -> io::Result<()> {
+    // nothing was written before the clock can go back to
+    let Some(cutoff) = days
+        .checked_mul(24 * 60 * 60)
+        .and_then(|secs| SystemTime::now().checked_sub(Duration::from_secs(secs)))
+    else {
+        return Ok(());
+    };
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "org") || !entry.file_type()?.is_file() {
+            continue;
+        }
+        if entry.metadata()?.modified()? > cutoff {
+            continue;
+        }
+        let status = Command::new(codec.program())
+            .args(codec.in_place())
+            .arg(&path)
+            .stdin(Stdio::null())
+            .status()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => io::Error::new(
+                    e.kind(),
+                    format!("compressing needs {} installed", codec.program()),
+                ),
+                _ => e,
+            })?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{} failed on {}",
+                codec.program(),
+                path.display()
+            )));
+        }
+        log::info!("compressed {}", path.display());
+    }
+    Ok(())
+}
+
+/// `contents` uncompressed if it is gzip or zstd data, or as it is.
+pub fn decompress(contents: Vec<u8>) -> io::Result<Vec<u8>> {
+    let codec = if contents.starts_with(GZIP_MAGIC) {
+        Codec::Gzip
+    } else if contents.starts_with(ZSTD_MAGIC) {
+        Codec::Zstd
+    } else {
+        return Ok(contents);
+    };
+    let args = ["-d".to_string(), "-c".to_string(), "-q".to_string()];
+    platform::filter(codec.program(), &args, &contents)
+}
@@ -0,0 +1,243 @@
+use std::{env, fs, io, path::PathBuf};
+
+/// A value from the config file. Only the TOML subset the stopwatch needs is
+/// understood.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Str(String),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "a string",
+            Value::Bool(_) => "a boolean",
+            Value::Int(_) => "an integer",
+            Value::Float(_) => "a number",
+        }
+    }
+}
+
+/// One `key = value` line. Keys inside a `[section]` are stored as
+/// `section.key`, and those inside the n-th `[[table]]` as `table.n.key`,
+/// counting from 1.
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+pub struct Config {
+    pub path: PathBuf,
+    pub entries: Vec<Entry>,
+}
+
+impl Config {
+    /// Error message for `entry`, prefixed with its location in the file.
+    pub fn error(&self, entry: &Entry, msg: &str) -> String {
+        format!("{}:{}: {}", self.path.display(), entry.line, msg)
+    }
+
+    pub fn string(&self, entry: &Entry) -> Result<String, String> {
+        match &entry.value {
+            Value::Str(s) => Ok(s.clone()),
+            other => Err(self.error(
+                entry,
+                &format!(
+                    "'{}' must be a string, not {}",
+                    entry.key,
+                    other.type_name()
+                ),
+            )),
+        }
+    }
+
+    pub fn int(&self, entry: &Entry) -> Result<i64, String> {
+        match &entry.value {
+            Value::Int(i) => Ok(*i),
+            other => Err(self.error(
+                entry,
+                &format!(
+                    "'{}' must be an integer, not {}",
+                    entry.key,
+                    other.type_name()
+                ),
+            )),
+        }
+    }
+
+    /// An integer or a float.
+    pub fn number(&self, entry: &Entry) -> Result<f64, String> {
+        match &entry.value {
+            Value::Int(i) => Ok(*i as f64),
+            Value::Float(f) => Ok(*f),
+            other => Err(self.error(
+                entry,
+                &format!(
+                    "'{}' must be a number, not {}",
+                    entry.key,
+                    other.type_name()
+                ),
+            )),
+        }
+    }
+
+    pub fn bool(&self, entry: &Entry) -> Result<bool, String> {
+        match &entry.value {
+            Value::Bool(b) => Ok(*b),
+            other => Err(self.error(
+                entry,
+                &format!(
+                    "'{}' must be a boolean, not {}",
+                    entry.key,
+                    other.type_name()
+                ),
+            )),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/stopwatch/config.toml`, falling back to `~/.config`
+/// (`%APPDATA%` on Windows).
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(crate::platform::config_home)?;
+    Some(base.join("stopwatch").join("config.toml"))
+}
+
+/// Load `path`. A missing file is only an error when `required` is set, i.e.
+/// when the user pointed at it explicitly.
+pub fn load(path: PathBuf, required: bool) -> Result<Option<Config>, String> {
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !required => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let entries =
+        parse(&text).map_err(|(line, msg)| format!("{}:{}: {}", path.display(), line, msg))?;
+    Ok(Some(Config { path, entries }))
+}
+
+fn parse(text: &str) -> Result<Vec<Entry>, (usize, String)> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+    let mut tables: Vec<(String, usize)> = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = strip_comment(raw).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("[[") {
+            let name = name
+                .strip_suffix("]]")
+                .ok_or((line_no, "unterminated table header".to_string()))?
+                .trim();
+            let n = match tables.iter_mut().find(|(t, _)| t == name) {
+                Some((_, n)) => {
+                    *n += 1;
+                    *n
+                }
+                None => {
+                    tables.push((name.to_string(), 1));
+                    1
+                }
+            };
+            section = format!("{}.{}", name, n);
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or((line_no, "unterminated section header".to_string()))?;
+            section = name.trim().to_string();
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or((line_no, format!("expected 'key = value', got '{}'", line)))?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err((line_no, "missing key".to_string()));
+        }
+        let value = parse_value(value.trim()).map_err(|e| (line_no, e))?;
+        let key = if section.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", section, key)
+        };
+        entries.push(Entry {
+            key,
+            value,
+            line: line_no,
+        });
+    }
+    Ok(entries)
+}
+
+/// Drop a trailing `# comment`, ignoring `#` inside quoted strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_str = false;
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if in_str => {
+                chars.next();
+            }
+            '"' => in_str = !in_str,
+            '#' if !in_str => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+    if let Some(inner) = s.strip_prefix('"') {
+        let inner = inner
+            .strip_suffix('"')
+            .ok_or_else(|| format!("unterminated string {}", s))?;
+        return unescape(inner).map(Value::Str);
+    }
+    match s {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+    if let Ok(i) = s.replace('_', "").parse::<i64>() {
+        return Ok(Value::Int(i));
+    }
+    if let Ok(f) = s.replace('_', "").parse::<f64>() {
+        if f.is_finite() {
+            return Ok(Value::Float(f));
+        }
+    }
+    Err(format!(
+        "cannot parse value '{}' (strings need double quotes)",
+        s
+    ))
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some(other) => return Err(format!("unknown escape '\\{}'", other)),
+            None => return Err("trailing backslash".to_string()),
+        }
+    }
+    Ok(out)
+}
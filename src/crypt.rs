@@ -0,0 +1,103 @@
+use std::{io, path::PathBuf};
+
+use crate::platform;
+
+const BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+const END: &str = "-----END AGE ENCRYPTED FILE-----";
+
+/// Encryption of the log and session files with `age`, or `rage` when
+/// `age` isn't installed. Each save is added as an armored block of its
+/// own, so writing only takes the recipients' public keys; reading takes
+/// the identity, whose passphrase `age` asks for on the terminal if it has
+/// one.
+#[derive(Clone, Debug, Default)]
+pub struct Crypt {
+    /// Public keys to encrypt to, e.g. `age1...`. Nothing is encrypted
+    /// without one.
+    pub recipients: Vec<String>,
+    /// The secret key file to decrypt with.
+    pub identity: Option<PathBuf>,
+}
+
+impl Crypt {
+    pub fn enabled(&self) -> bool {
+        !self.recipients.is_empty()
+    }
+
+    /// `text` encrypted as an armored block, or as it is when encryption is
+    /// off.
+    pub fn seal(&self, text: &[u8]) -> io::Result<Vec<u8>> {
+        if !self.enabled() {
+            return Ok(text.to_vec());
+        }
+        let mut args = vec!["--encrypt".to_string(), "--armor".to_string()];
+        for recipient in &self.recipients {
+            args.push("-r".to_string());
+            args.push(recipient.clone());
+        }
+        let mut sealed = age(&args, text)?;
+        if !sealed.ends_with(b"\n") {
+            sealed.push(b'\n');
+        }
+        Ok(sealed)
+    }
+
+    /// `contents` with every encrypted block in it decrypted. Plain text
+    /// around the blocks, such as entries saved before encryption was
+    /// turned on, is kept as it is; a file encrypted by hand with `age` is
+    /// decrypted whole.
+    pub fn open(&self, contents: Vec<u8>) -> io::Result<Vec<u8>> {
+        if contents.starts_with(b"age-encryption.org/") {
+            return self.decrypt(&contents);
+        }
+        let text = match String::from_utf8(contents) {
+            Ok(text) if text.contains(BEGIN) => text,
+            Ok(text) => return Ok(text.into_bytes()),
+            Err(e) => return Ok(e.into_bytes()),
+        };
+        let mut plain = Vec::new();
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find(BEGIN) {
+            let end = rest[start..]
+                .find(END)
+                .map(|end| start + end + END.len())
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "an encrypted block is cut off")
+                })?;
+            plain.extend_from_slice(&rest.as_bytes()[..start]);
+            plain.extend(self.decrypt(&rest.as_bytes()[start..end])?);
+            rest = rest[end..].strip_prefix('\n').unwrap_or(&rest[end..]);
+        }
+        plain.extend_from_slice(rest.as_bytes());
+        Ok(plain)
+    }
+
+    fn decrypt(&self, sealed: &[u8]) -> io::Result<Vec<u8>> {
+        let mut args = vec!["--decrypt".to_string()];
+        if let Some(identity) = &self.identity {
+            args.push("-i".to_string());
+            args.push(identity.display().to_string());
+        }
+        age(&args, sealed)
+    }
+}
+
+/// Whether `contents` holds anything encrypted.
+pub fn sealed(contents: &[u8]) -> bool {
+    contents.starts_with(b"age-encryption.org/")
+        || String::from_utf8_lossy(contents).contains(BEGIN)
+}
+
+/// What `age` printed for `input`, trying `rage` if `age` isn't there.
+fn age(args: &[String], input: &[u8]) -> io::Result<Vec<u8>> {
+    match platform::filter("age", args, input) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => platform::filter("rage", args, input)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => {
+                    io::Error::new(e.kind(), "encrypting needs age or rage installed")
+                }
+                _ => e,
+            }),
+        output => output,
+    }
+}
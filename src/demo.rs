@@ -0,0 +1,172 @@
+use std::{
+    io::{self, BufWriter, Write},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+use crate::error::{Error, Result};
+use crate::options::Options;
+use crate::stopwatch::{Action, Clock, Stopwatch};
+use crate::ui::{draw_dynamic, redraw, restore_terminal, setup_terminal, View};
+use crate::{Message, Workers, EXIT_INTERRUPTED};
+
+/// How far into the script the demo opens, so there is a list to look at
+/// straight away.
+const OPENING: Duration = Duration::from_secs(27 * 60 + 40);
+
+/// The made-up session: when each change happens, from the goal's start.
+fn script() -> Vec<(Duration, Action)> {
+    let min = |m: u64| Duration::from_secs(m * 60);
+    let split = |name: &str, parent: Option<usize>| Action::Split {
+        name: name.to_string(),
+        parent,
+    };
+    vec![
+        (
+            Duration::ZERO,
+            Action::Start {
+                goal: "Quarterly report".to_string(),
+                project: Some("acme".to_string()),
+            },
+        ),
+        (Duration::ZERO, split("Research", None)),
+        (min(4), split("Sources", Some(0))),
+        (min(12), Action::Stop { index: 1 }),
+        (min(12), split("Interviews", Some(0))),
+        (min(21), Action::Stop { index: 2 }),
+        (min(21), Action::Stop { index: 0 }),
+        (min(21), split("Draft", None)),
+        (min(22), split("Outline", Some(3))),
+        (min(27), Action::Stop { index: 4 }),
+        (min(27), split("Figures", Some(3))),
+        // from here on it plays out while you watch
+        (min(28), Action::Stop { index: 5 }),
+        (min(28), split("Summary", Some(3))),
+        (min(29), Action::Stop { index: 6 }),
+        (min(29), Action::Stop { index: 3 }),
+        (min(29), split("Review", None)),
+        (min(30), split("Comments", Some(7))),
+    ]
+}
+
+/// `--demo`: show a made-up session that moves along by itself, for
+/// screenshots and trying out colors and layouts. Nothing is saved.
+pub fn run(opts: &Options) -> Result<i32> {
+    // frames are flushed whole, see `redraw`
+    let mut stdout = BufWriter::new(io::stdout());
+    setup_terminal(&mut stdout, opts).map_err(Error::Terminal)?;
+    let result = show(&mut stdout, opts);
+    restore_terminal(&mut stdout, opts).map_err(Error::Terminal)?;
+    result
+}
+
+struct Demo {
+    steps: Vec<(Duration, Action)>,
+    base: Instant,
+    next: usize,
+}
+
+impl Demo {
+    /// Apply every step that is due. Returns true if anything changed.
+    fn advance(&mut self, sw: &mut Stopwatch) -> bool {
+        let now = sw.clock.now();
+        let start = self.next;
+        while let Some((offset, action)) = self.steps.get(self.next) {
+            let at = self.base + *offset;
+            if at > now {
+                break;
+            }
+            // as if the goal had started on the wall clock `now - at` ago
+            let ago = chrono::Duration::from_std(now - at).unwrap_or_default();
+            sw.apply_at(action, at, Local::now() - ago);
+            self.next += 1;
+        }
+        start < self.next
+    }
+}
+
+fn show<W: Write>(stdout: &mut W, opts: &Options) -> Result<i32> {
+    let base = Instant::now();
+    let mut sw = Stopwatch::new();
+    sw.clock = Clock::starting_at(base + OPENING, 1);
+    let mut demo = Demo {
+        steps: script(),
+        base,
+        next: 0,
+    };
+    let mut view = View {
+        compact: opts.compact,
+        private: opts.private,
+        message: Some("Demo, nothing is saved | v/o/S/b/P=view q=quit".to_string()),
+        ..View::default()
+    };
+    demo.advance(&mut sw);
+    redraw(stdout, opts, &sw, &view).map_err(Error::Terminal)?;
+
+    let (tx, rx) = mpsc::channel::<Message>();
+    let workers = Workers::spawn(tx, opts.tick)?;
+    let result = handle_events(stdout, opts, &mut sw, &mut view, &mut demo, &rx);
+    workers.stop();
+    result
+}
+
+fn handle_events<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    view: &mut View,
+    demo: &mut Demo,
+    rx: &mpsc::Receiver<Message>,
+) -> Result<i32> {
+    for msg in rx {
+        match msg {
+            Message::Tick => {
+                if demo.advance(sw) {
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                } else {
+                    draw_dynamic(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+            }
+            #[cfg(unix)]
+            Message::Signal(sig) => return Ok(128 + sig),
+            Message::Failed(e) => return Err(e),
+            Message::Remote(_) | Message::Press(_) => {}
+            Message::Commit(_) | Message::Line(_) | Message::LinesEnded => {}
+            Message::Watched(..) | Message::Advance(_) => {}
+            #[cfg(feature = "plugins")]
+            Message::Plugin(_) => {}
+            #[cfg(feature = "window")]
+            Message::Focus(_) => {}
+            Message::Input(Event::Key(key), _) => {
+                match key.code {
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(EXIT_INTERRUPTED);
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(0),
+                    KeyCode::Char('v') => view.compact = !view.compact,
+                    KeyCode::Char('o') => {
+                        view.open_only = !view.open_only;
+                        view.sorted = false;
+                    }
+                    KeyCode::Char('S') => {
+                        view.sorted = !view.sorted;
+                        view.open_only = false;
+                    }
+                    KeyCode::Char('b') => view.breakdown = !view.breakdown,
+                    KeyCode::Char('P') => view.private = !view.private,
+                    _ => {}
+                }
+                view.dismiss();
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            Message::Input(Event::Resize(..), _) => {
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?
+            }
+            Message::Input(..) => {}
+        }
+    }
+    Ok(0)
+}
@@ -0,0 +1,380 @@
+use std::{error::Error, fmt, time::Duration};
+
+/// How elapsed times are written, both on screen and in exports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// `01:02:03.456`
+    Clock,
+    /// `1h 02m 03s`
+    Human,
+    /// `1.04h`
+    Decimal,
+    /// `PT1H2M3.456S`
+    Iso,
+}
+
+/// Sub-second part of the clock style.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// `.456`
+    Millis,
+    /// `.45`, as on sports timing boards
+    Centis,
+    /// `:27`, the frame number within the second at `fps`
+    Frames,
+    /// Whole seconds only
+    Seconds,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DurationFormat {
+    pub style: Style,
+    /// Drop leading fields that are zero (`3.456`, `2:03.456`, `2m 03s`).
+    pub trim_zeros: bool,
+    pub precision: Precision,
+    pub fps: u32,
+}
+
+impl Default for DurationFormat {
+    fn default() -> Self {
+        DurationFormat {
+            style: Style::Clock,
+            trim_zeros: false,
+            precision: Precision::Millis,
+            fps: 60,
+        }
+    }
+}
+
+impl Precision {
+    pub fn from_name(name: &str) -> Option<Precision> {
+        match name {
+            "ms" | "millis" => Some(Precision::Millis),
+            "cs" | "centis" => Some(Precision::Centis),
+            "frames" => Some(Precision::Frames),
+            "s" | "secs" => Some(Precision::Seconds),
+            _ => None,
+        }
+    }
+}
+
+impl Style {
+    pub fn from_name(name: &str) -> Option<Style> {
+        match name {
+            "clock" => Some(Style::Clock),
+            "human" => Some(Style::Human),
+            "decimal" => Some(Style::Decimal),
+            "iso" | "iso8601" => Some(Style::Iso),
+            _ => None,
+        }
+    }
+}
+
+impl DurationFormat {
+    pub fn format(&self, dur: Duration) -> String {
+        self.display(dur).to_string()
+    }
+
+    /// `dur` in this format, written out where it is used rather than
+    /// built up first, for text redrawn on every tick.
+    pub fn display(&self, dur: Duration) -> Formatted<'_> {
+        Formatted { format: self, dur }
+    }
+
+    fn write(&self, f: &mut impl fmt::Write, dur: Duration) -> fmt::Result {
+        let secs = dur.as_secs();
+        let s = secs % 60;
+        let m = (secs / 60) % 60;
+        let h = secs / 3600;
+        match self.style {
+            Style::Clock if self.trim_zeros && h == 0 && m == 0 => {
+                write!(f, "{}", s)?;
+                self.write_fraction(f, dur)
+            }
+            Style::Clock if self.trim_zeros && h == 0 => {
+                write!(f, "{}:{:02}", m, s)?;
+                self.write_fraction(f, dur)
+            }
+            Style::Clock if self.trim_zeros => {
+                write!(f, "{}:{:02}:{:02}", h, m, s)?;
+                self.write_fraction(f, dur)
+            }
+            Style::Clock => {
+                write!(f, "{:02}:{:02}:{:02}", h, m, s)?;
+                self.write_fraction(f, dur)
+            }
+            Style::Human if self.trim_zeros && h == 0 && m == 0 => write!(f, "{}s", s),
+            Style::Human if self.trim_zeros && h == 0 => write!(f, "{}m {:02}s", m, s),
+            Style::Human => write!(f, "{}h {:02}m {:02}s", h, m, s),
+            Style::Decimal => write!(f, "{:.2}h", dur.as_secs_f64() / 3600.0),
+            Style::Iso => write_iso(f, dur),
+        }
+    }
+
+    fn write_fraction(&self, f: &mut impl fmt::Write, dur: Duration) -> fmt::Result {
+        match self.precision {
+            Precision::Millis => write!(f, ".{:03}", dur.subsec_millis()),
+            Precision::Centis => write!(f, ".{:02}", dur.subsec_millis() / 10),
+            Precision::Frames => {
+                let frame = dur.subsec_nanos() as u64 * self.fps as u64 / 1_000_000_000;
+                write!(f, ":{:02}", frame)
+            }
+            Precision::Seconds => Ok(()),
+        }
+    }
+
+    /// Stand-in for a duration that hasn't ended yet.
+    pub fn placeholder(&self) -> &'static str {
+        match (self.style, self.precision) {
+            (Style::Clock, Precision::Millis) => "--:--:--.---",
+            (Style::Clock, Precision::Centis) => "--:--:--.--",
+            (Style::Clock, Precision::Frames) => "--:--:--:--",
+            (Style::Clock, Precision::Seconds) => "--:--:--",
+            _ => "--",
+        }
+    }
+}
+
+/// A duration as `DurationFormat::display` shows it.
+pub struct Formatted<'a> {
+    format: &'a DurationFormat,
+    dur: Duration,
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.format.write(f, self.dur)
+    }
+}
+
+/// ISO 8601 duration, omitting zero components (`PT0S` for nothing at all).
+fn write_iso(f: &mut impl fmt::Write, dur: Duration) -> fmt::Result {
+    let ms = dur.as_millis() % 1000;
+    let secs = dur.as_secs();
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    f.write_str("PT")?;
+    if h > 0 {
+        write!(f, "{}H", h)?;
+    }
+    if m > 0 {
+        write!(f, "{}M", m)?;
+    }
+    if ms > 0 {
+        write!(f, "{}.{:03}S", s, ms)?;
+    } else if s > 0 || (h == 0 && m == 0) {
+        write!(f, "{}S", s)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct ParseDurationError {
+    input: String,
+    reason: String,
+}
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid duration '{}': {} (expected e.g. 1h30m, 90m, 45s or 1:30:00)",
+            self.input, self.reason
+        )
+    }
+}
+
+impl Error for ParseDurationError {}
+
+/// How durations are rounded when written to the log or exported. The
+/// running display always shows the exact time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    #[default]
+    Exact,
+    /// To the nearest multiple of the step.
+    Nearest(Duration),
+    /// Up to the next multiple of the step, as billing usually does.
+    Up(Duration),
+}
+
+impl Rounding {
+    /// `exact`, a step such as `15m` to round to the nearest, or `up:15m`.
+    pub fn parse(spec: &str) -> Result<Rounding, String> {
+        if spec == "exact" || spec == "none" {
+            return Ok(Rounding::Exact);
+        }
+        let (up, step) = match spec.strip_prefix("up:") {
+            Some(step) => (true, step),
+            None => (false, spec),
+        };
+        let step = parse_duration(step).map_err(|e| e.to_string())?;
+        if step.is_zero() {
+            return Err("rounding step must be more than zero".to_string());
+        }
+        Ok(if up {
+            Rounding::Up(step)
+        } else {
+            Rounding::Nearest(step)
+        })
+    }
+
+    pub fn apply(self, d: Duration) -> Duration {
+        let (step, bias) = match self {
+            Rounding::Exact => return d,
+            Rounding::Nearest(step) => (step.as_nanos(), step.as_nanos() / 2),
+            Rounding::Up(step) => (step.as_nanos(), step.as_nanos() - 1),
+        };
+        let steps = (d.as_nanos() + bias) / step;
+        Duration::from_nanos((steps * step) as u64)
+    }
+}
+
+/// Parse a user-supplied duration.
+///
+/// Accepts unit sequences (`1h30m`, `90m`, `45s`, `1.5h`, `250ms`, with
+/// optional spaces between parts), clock notation (`1:30:00`, `30:00`,
+/// `0:45.5`) and bare numbers, which are taken as seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, ParseDurationError> {
+    let err = |reason: String| ParseDurationError {
+        input: input.to_string(),
+        reason,
+    };
+    let s = input.trim();
+    if s.is_empty() {
+        return Err(err("empty input".to_string()));
+    }
+    if s.contains(':') {
+        return parse_clock(s).map_err(err);
+    }
+    if let Ok(secs) = s.parse::<f64>() {
+        return seconds(secs).map_err(err);
+    }
+    parse_units(s).map_err(err)
+}
+
+fn parse_clock(s: &str) -> Result<Duration, String> {
+    let fields: Vec<&str> = s.split(':').collect();
+    if fields.len() > 3 {
+        return Err("too many ':' fields".to_string());
+    }
+    let (last, whole) = fields.split_last().unwrap();
+    let mut secs = 0.0;
+    for (i, field) in whole.iter().enumerate() {
+        let value: u64 = field
+            .parse()
+            .map_err(|_| format!("'{}' is not a whole number", field))?;
+        // the leading field is unbounded, later ones are minutes/seconds
+        if i > 0 && value >= 60 {
+            return Err(format!("'{}' is out of range (0-59)", field));
+        }
+        secs = secs * 60.0 + value as f64;
+    }
+    let last_value: f64 = last
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", last))?;
+    if !(0.0..60.0).contains(&last_value) {
+        return Err(format!("'{}' is out of range (0-59)", last));
+    }
+    seconds(secs * 60.0 + last_value)
+}
+
+fn parse_units(s: &str) -> Result<Duration, String> {
+    let mut total = 0.0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        rest = rest.trim_start();
+        let num_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if num_len == 0 {
+            return Err(format!("expected a number at '{}'", rest));
+        }
+        let (num, tail) = rest.split_at(num_len);
+        let value: f64 = num
+            .parse()
+            .map_err(|_| format!("'{}' is not a number", num))?;
+        let tail = tail.trim_start();
+        let unit_len = tail
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let scale = match unit.to_ascii_lowercase().as_str() {
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "ms" => 0.001,
+            "" => return Err(format!("missing unit after '{}'", num)),
+            other => return Err(format!("unknown unit '{}'", other)),
+        };
+        total += value * scale;
+        rest = tail;
+    }
+    seconds(total)
+}
+
+fn seconds(secs: f64) -> Result<Duration, String> {
+    if secs < 0.0 {
+        return Err("duration must not be negative".to_string());
+    }
+    Duration::try_from_secs_f64(secs).map_err(|_| "duration is too long".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Duration, String> {
+        parse_duration(s).map_err(|e| e.reason)
+    }
+
+    #[test]
+    fn clock_form() {
+        assert_eq!(parse("1:30:00"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse("30:00"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse("0:45.5"), Ok(Duration::from_millis(45_500)));
+        assert_eq!(parse("100:00:00"), Ok(Duration::from_secs(360_000)));
+        assert!(parse("1:60:00").is_err());
+        assert!(parse("1:00:60").is_err());
+        assert!(parse("1:2:3:4").is_err());
+        assert!(parse("a:00").is_err());
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(parse("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse("90m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse("1h 30m 15s"), Ok(Duration::from_secs(5415)));
+        assert_eq!(parse("2 hours"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse("45"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse("  45s "), Ok(Duration::from_secs(45)));
+        assert!(parse("").is_err());
+        assert!(parse("10x").is_err());
+        assert!(parse("h").is_err());
+        assert!(parse("1h30").is_err());
+    }
+
+    #[test]
+    fn fractions() {
+        assert_eq!(parse("1.5h"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse("0.5m"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse("2.25"), Ok(Duration::from_millis(2250)));
+        assert!(parse("1.2.3m").is_err());
+    }
+
+    #[test]
+    fn negatives() {
+        assert!(parse("-5").is_err());
+        assert!(parse("-1h").is_err());
+        assert!(parse("-1:00").is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        assert!(parse("1e30").is_err());
+        assert!(parse("inf").is_err());
+        assert!(parse("NaN").is_err());
+        assert!(parse("99999999999999999999999h").is_err());
+        assert!(parse("99999999999999999999999:00").is_err());
+    }
+}
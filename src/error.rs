@@ -0,0 +1,103 @@
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum Error {
+    /// Bad command line or config file.
+    Usage(String),
+    /// Writing to or controlling the terminal failed.
+    Terminal(io::Error),
+    /// Reading keyboard input failed.
+    Input(io::Error),
+    /// Appending the session to the log file failed.
+    Save { path: String, source: io::Error },
+    /// Copying the saved files to the backup failed; they are saved
+    /// locally all the same.
+    Backup { url: String, source: io::Error },
+    /// Committing the saved log to git failed.
+    Commit { path: String, source: io::Error },
+    /// Reading or appending to the journal failed.
+    Journal { path: String, source: io::Error },
+    /// Sharing the timer, following one, or a remote control input failed.
+    Remote { addr: String, source: io::Error },
+    /// Listening for signals failed.
+    #[cfg(unix)]
+    Signals(io::Error),
+    /// Starting a plugin failed.
+    #[cfg(feature = "plugins")]
+    Plugin { command: String, source: io::Error },
+    /// Starting the watcher that splits on git commits failed.
+    Git { path: String, source: io::Error },
+    /// Starting the watcher that follows the focused window failed.
+    #[cfg(feature = "window")]
+    Window(io::Error),
+    /// Writing the OBS overlay file failed.
+    Overlay { path: String, source: io::Error },
+    /// Copying to the clipboard failed.
+    Clipboard(io::Error),
+    /// A subcommand such as `export` failed.
+    Command(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Usage(msg) | Error::Command(msg) => write!(f, "{}", msg),
+            Error::Terminal(e) => write!(f, "terminal error: {}", e),
+            Error::Input(e) => write!(f, "cannot read input: {}", e),
+            Error::Save { path, source } => write!(f, "cannot save to {}: {}", path, source),
+            Error::Backup { url, source } => write!(f, "cannot back up to {}: {}", url, source),
+            Error::Commit { path, source } => write!(f, "cannot commit {}: {}", path, source),
+            Error::Journal { path, source } => write!(f, "journal {}: {}", path, source),
+            Error::Remote { addr, source } => write!(f, "{}: {}", addr, source),
+            #[cfg(unix)]
+            Error::Signals(e) => write!(f, "cannot listen for signals: {}", e),
+            #[cfg(feature = "plugins")]
+            Error::Plugin { command, source } => {
+                write!(f, "cannot start plugin {}: {}", command, source)
+            }
+            Error::Git { path, source } => {
+                write!(f, "cannot watch git repository {}: {}", path, source)
+            }
+            #[cfg(feature = "window")]
+            Error::Window(e) => write!(f, "cannot follow the focused window: {}", e),
+            Error::Overlay { path, source } => {
+                write!(f, "cannot write overlay {}: {}", path, source)
+            }
+            Error::Clipboard(e) => write!(f, "copy failed: {}", e),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Terminal(e) | Error::Input(e) | Error::Clipboard(e) => Some(e),
+            #[cfg(unix)]
+            Error::Signals(e) => Some(e),
+            #[cfg(feature = "window")]
+            Error::Window(e) => Some(e),
+            #[cfg(feature = "plugins")]
+            Error::Plugin { source, .. } => Some(source),
+            Error::Save { source, .. }
+            | Error::Backup { source, .. }
+            | Error::Commit { source, .. }
+            | Error::Journal { source, .. }
+            | Error::Remote { source, .. }
+            | Error::Git { source, .. }
+            | Error::Overlay { source, .. } => Some(source),
+            Error::Usage(_) | Error::Command(_) => None,
+        }
+    }
+}
+
+impl Error {
+    /// Exit status for the process: 2 for usage errors, 1 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => 2,
+            _ => 1,
+        }
+    }
+}
@@ -0,0 +1,889 @@
+use std::{fs, io::Write, time::Duration};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone};
+
+use crate::billing::{self, Accounts, Rates};
+use crate::duration::{DurationFormat, Rounding, Style};
+use crate::options::{ExportArgs, Options};
+use crate::orglog::{self, Entry, Session};
+use crate::outlier::{outliers, Outlier};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Html,
+    Mermaid,
+    Dot,
+    Svg,
+    Csv,
+    /// Line items per tag and day across the log, as a Markdown table.
+    Invoice,
+    InvoiceCsv,
+    /// hledger/ledger timeclock `i`/`o` lines across the log.
+    Timeclock,
+    /// Beancount transactions across the log, in hours.
+    Beancount,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "html" => Some(Format::Html),
+            "mermaid" => Some(Format::Mermaid),
+            "dot" | "graphviz" => Some(Format::Dot),
+            "svg" => Some(Format::Svg),
+            "csv" => Some(Format::Csv),
+            "invoice" | "invoice-md" => Some(Format::Invoice),
+            "invoice-csv" => Some(Format::InvoiceCsv),
+            "timeclock" | "hledger" | "ledger" => Some(Format::Timeclock),
+            "beancount" => Some(Format::Beancount),
+            _ => None,
+        }
+    }
+}
+
+/// What invoice line items are grouped by, besides the day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group {
+    Tag,
+    Project,
+}
+
+impl Group {
+    pub fn from_name(name: &str) -> Option<Group> {
+        match name {
+            "tag" => Some(Group::Tag),
+            "project" => Some(Group::Project),
+            _ => None,
+        }
+    }
+}
+
+/// How timestamps and durations are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// `2024-01-31 10:00` and durations as `--format` says.
+    Org,
+    /// RFC 3339 timestamps (`2024-01-31T10:00:00+01:00`) and ISO 8601
+    /// durations (`PT1H5M`).
+    Iso,
+}
+
+impl Profile {
+    pub fn from_name(name: &str) -> Option<Profile> {
+        match name {
+            "org" => Some(Profile::Org),
+            "iso" | "iso8601" | "rfc3339" => Some(Profile::Iso),
+            _ => None,
+        }
+    }
+
+    fn duration_format(self, fmt: &DurationFormat) -> DurationFormat {
+        match self {
+            Profile::Org => *fmt,
+            Profile::Iso => DurationFormat {
+                style: Style::Iso,
+                ..*fmt
+            },
+        }
+    }
+
+    fn stamp(self, t: NaiveDateTime) -> String {
+        match self {
+            Profile::Org => t.format("%Y-%m-%d %H:%M").to_string(),
+            // log times are local; a time skipped by a DST change has no
+            // offset, so keep it without one
+            Profile::Iso => match Local.from_local_datetime(&t).earliest() {
+                Some(t) => t.to_rfc3339_opts(SecondsFormat::Secs, false),
+                None => t.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            },
+        }
+    }
+
+    /// A time of day when the date is clear from context.
+    fn time(self, t: NaiveDateTime) -> String {
+        match self {
+            Profile::Org => t.format("%H:%M").to_string(),
+            Profile::Iso => self.stamp(t),
+        }
+    }
+}
+
+/// `sw export`: render one session of the log in another format, to stdout
+/// or to `--output`. Invoices, timeclock and Beancount files cover the whole
+/// log unless `--session` picks one, and so does a `--plugin` export.
+pub fn run(opts: &Options, args: &ExportArgs) -> Result<(), String> {
+    let sessions = orglog::read(&opts.log_file, &opts.crypt)
+        .map_err(|e| format!("{}: {}", opts.log_file, e))?;
+    if sessions.is_empty() {
+        return Err(format!("{}: no sessions found", opts.log_file));
+    }
+    let picked = match args.session {
+        Some(n) => sessions
+            .get(n.wrapping_sub(1))
+            .map(std::slice::from_ref)
+            .ok_or_else(|| format!("session {} not found ({} in log)", n, sessions.len()))?,
+        None => &sessions[..],
+    };
+    let session = &rounded(picked.last().unwrap(), opts.rounding);
+    let fmt = &args.profile.duration_format(&opts.format);
+    #[cfg(feature = "plugins")]
+    if let Some(command) = &args.plugin {
+        let sessions: Vec<Session> = picked.iter().map(|s| rounded(s, opts.rounding)).collect();
+        return write_output(args, &crate::plugin::export(command, &sessions, fmt)?);
+    }
+    let text = match args.format {
+        Format::Html => html(session, fmt, &opts.rates, args.profile),
+        Format::Mermaid => mermaid(session),
+        Format::Dot => dot(session, fmt),
+        Format::Svg => svg(session, fmt, args.profile),
+        Format::Csv => csv(session, fmt, &opts.rates, args.profile),
+        Format::Invoice => invoice_markdown(
+            &line_items(picked, args.group, opts.rounding, &opts.rates),
+            &opts.rates,
+        ),
+        Format::InvoiceCsv => {
+            invoice_csv(&line_items(picked, args.group, opts.rounding, &opts.rates))
+        }
+        Format::Timeclock => timeclock(picked),
+        Format::Beancount => beancount(picked, opts.rounding, &opts.rates, &opts.accounts),
+    };
+    write_output(args, &text)
+}
+
+fn write_output(args: &ExportArgs, text: &str) -> Result<(), String> {
+    match &args.output {
+        Some(path) => fs::write(path, text).map_err(|e| format!("{}: {}", path, e)),
+        None => std::io::stdout()
+            .write_all(text.as_bytes())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// A copy of `session` with every duration rounded.
+fn rounded(session: &Session, rounding: Rounding) -> Session {
+    Session {
+        goal: session.goal.clone(),
+        start: session.start,
+        end: session.end,
+        total: rounding.apply(session.total),
+        project: session.project.clone(),
+        splits: session
+            .splits
+            .iter()
+            .map(|e| Entry {
+                name: e.name.clone(),
+                parent: e.parent,
+                start: e.start,
+                end: e.end,
+                duration: rounding.apply(e.duration),
+                project: e.project.clone(),
+            })
+            .collect(),
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;max-width:50em;margin:2em auto;color:#222}
+table{border-collapse:collapse}td{padding:.2em .8em .2em 0}
+details,.leaf{margin-left:1.2em}summary{cursor:pointer}
+.dur{font-family:monospace;color:#555}
+.bar{display:flex;align-items:center;margin:.2em 0}
+.bar span{width:14em;overflow:hidden;white-space:nowrap;text-overflow:ellipsis}
+.bar div{background:#4a8;height:1em;margin-right:.5em}
+.longest{color:#c33}.shortest{color:#393}";
+
+/// Standalone HTML page: summary table, collapsible split tree with the
+/// outliers marked, and a bar chart of top-level durations.
+pub fn html(session: &Session, fmt: &DurationFormat, rates: &Rates, profile: Profile) -> String {
+    let goal = escape_html(&session.goal);
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", goal));
+    out.push_str(&format!(
+        "<style>\n{}\n</style>\n</head>\n<body>\n",
+        HTML_STYLE
+    ));
+    out.push_str(&format!("<h1>{}</h1>\n<table>\n", goal));
+    out.push_str(&format!(
+        "<tr><td>Start</td><td>{}</td></tr>\n<tr><td>End</td><td>{}</td></tr>\n",
+        profile.stamp(session.start),
+        profile.stamp(session.end)
+    ));
+    out.push_str(&format!(
+        "<tr><td>Total</td><td class=\"dur\">{}</td></tr>\n<tr><td>Subgoals</td><td>{}</td></tr>\n",
+        fmt.format(session.total),
+        session.splits.len()
+    ));
+    if let Some(rate) = rates.goal_rate(&session.goal, session.project.as_deref()) {
+        out.push_str(&format!(
+            "<tr><td>Amount</td><td class=\"dur\">{}</td></tr>\n",
+            escape_html(&rates.amount(session.total, rate).to_string())
+        ));
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Subgoals</h2>\n");
+    let finished: Vec<(Option<usize>, Option<Duration>)> = session
+        .splits
+        .iter()
+        .map(|s| (s.parent, Some(s.duration)))
+        .collect();
+    let outliers = outliers(&finished);
+    html_tree(&mut out, session, None, (fmt, profile), &outliers);
+
+    out.push_str("<h2>Durations</h2>\n");
+    let longest = session
+        .splits
+        .iter()
+        .filter(|s| s.parent.is_none())
+        .map(|s| s.duration)
+        .max()
+        .unwrap_or_default();
+    for split in session.splits.iter().filter(|s| s.parent.is_none()) {
+        let pct = if longest.is_zero() {
+            0.0
+        } else {
+            split.duration.as_secs_f64() / longest.as_secs_f64() * 60.0
+        };
+        out.push_str(&format!(
+            "<div class=\"bar\"><span>{}</span><div style=\"width:{:.1}%\"></div><span class=\"dur\">{}</span></div>\n",
+            escape_html(&split.name),
+            pct,
+            fmt.format(split.duration)
+        ));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_tree(
+    out: &mut String,
+    session: &Session,
+    parent: Option<usize>,
+    (fmt, profile): (&DurationFormat, Profile),
+    outliers: &[Option<Outlier>],
+) {
+    for (i, split) in session.splits.iter().enumerate() {
+        if split.parent != parent {
+            continue;
+        }
+        let mut label = format!(
+            "{} <span class=\"dur\">{} ({}&ndash;{})</span>",
+            escape_html(&split.name),
+            fmt.format(split.duration),
+            profile.time(split.start),
+            profile.time(split.end)
+        );
+        if let Some(rollup) = rollup(session, Some(i)) {
+            label.push_str(&format!(
+                " <span class=\"dur\">&Sigma; {} in subgoals</span>",
+                fmt.format(rollup)
+            ));
+        }
+        match outliers[i] {
+            Some(Outlier::Longest) => {
+                label.push_str(" <span class=\"longest\" title=\"longest\">&#9650;</span>")
+            }
+            Some(Outlier::Shortest) => {
+                label.push_str(" <span class=\"shortest\" title=\"shortest\">&#9660;</span>")
+            }
+            None => {}
+        }
+        if session.splits.iter().any(|s| s.parent == Some(i)) {
+            out.push_str(&format!("<details open>\n<summary>{}</summary>\n", label));
+            html_tree(out, session, Some(i), (fmt, profile), outliers);
+            out.push_str("</details>\n");
+        } else {
+            out.push_str(&format!("<div class=\"leaf\">{}</div>\n", label));
+        }
+    }
+}
+
+/// Time in the direct subgoals of split `parent`, or of the goal itself
+/// for `None`; `None` when there are none.
+fn rollup(session: &Session, parent: Option<usize>) -> Option<Duration> {
+    let mut children = session
+        .splits
+        .iter()
+        .filter(|s| s.parent == parent)
+        .peekable();
+    children.peek()?;
+    Some(children.map(|s| s.duration).sum())
+}
+
+/// The hourly rate for split `i`: from its own tags, else its parent's.
+fn split_rate(session: &Session, i: usize, rates: &Rates) -> Option<f64> {
+    let inherited = match session.splits[i].parent {
+        Some(p) => split_rate(session, p, rates),
+        None => rates.goal_rate(&session.goal, session.splits[i].project.as_deref()),
+    };
+    rates.rate_for(&session.splits[i].name, inherited)
+}
+
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// One row for the goal and one per subgoal, with the time in its own
+/// subgoals, hours and the billed amount where a rate applies.
+pub fn csv(session: &Session, fmt: &DurationFormat, rates: &Rates, profile: Profile) -> String {
+    let mut rows = vec![(
+        session.goal.as_str(),
+        "",
+        session.project.as_deref(),
+        session.start,
+        session.end,
+        session.total,
+        rollup(session, None),
+        rates.goal_rate(&session.goal, session.project.as_deref()),
+    )];
+    for (i, split) in session.splits.iter().enumerate() {
+        let parent = match split.parent {
+            Some(p) => &session.splits[p].name,
+            None => &session.goal,
+        };
+        rows.push((
+            &split.name,
+            parent,
+            split.project.as_deref(),
+            split.start,
+            split.end,
+            split.duration,
+            rollup(session, Some(i)),
+            split_rate(session, i, rates),
+        ));
+    }
+    let mut out =
+        String::from("name,parent,project,start,end,duration,subgoals,hours,rate,amount\n");
+    for (name, parent, project, start, end, duration, rollup, rate) in rows {
+        let hours = duration.as_secs_f64() / 3600.0;
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{:.4},{},{}\n",
+            csv_field(name),
+            csv_field(parent),
+            csv_field(project.unwrap_or("")),
+            profile.stamp(start),
+            profile.stamp(end),
+            fmt.format(duration),
+            rollup.map(|d| fmt.format(d)).unwrap_or_default(),
+            hours,
+            rate.map(|r| format!("{:.2}", r)).unwrap_or_default(),
+            rate.map(|r| format!("{:.2}", hours * r))
+                .unwrap_or_default(),
+        ));
+    }
+    out
+}
+
+/// One invoice line: the time billed under a tag on one day.
+pub struct LineItem {
+    pub date: NaiveDate,
+    /// The tag, or the name of the work when it has none.
+    pub item: String,
+    /// Rounded according to `--round`.
+    pub duration: Duration,
+    pub rate: Option<f64>,
+}
+
+impl LineItem {
+    pub fn hours(&self) -> f64 {
+        self.duration.as_secs_f64() / 3600.0
+    }
+
+    pub fn amount(&self) -> Option<f64> {
+        self.rate.map(|r| self.hours() * r)
+    }
+}
+
+/// Group the top-level subgoals of `sessions` (or the goal itself when it
+/// has none) by day and either project or first tag, inheriting the goal's
+/// tag. Durations are rounded per line, as they would be on a timesheet.
+pub fn line_items(
+    sessions: &[Session],
+    group: Group,
+    rounding: Rounding,
+    rates: &Rates,
+) -> Vec<LineItem> {
+    let mut items: Vec<LineItem> = Vec::new();
+    for session in sessions {
+        let goal_tag = billing::tags(&session.goal).next();
+        let mut work: Vec<(&str, Option<&str>, NaiveDate, Duration)> = session
+            .splits
+            .iter()
+            .filter(|s| s.parent.is_none())
+            .map(|s| {
+                (
+                    s.name.as_str(),
+                    s.project.as_deref(),
+                    s.start.date(),
+                    s.duration,
+                )
+            })
+            .collect();
+        if work.is_empty() {
+            work.push((
+                &session.goal,
+                session.project.as_deref(),
+                session.start.date(),
+                session.total,
+            ));
+        }
+        for (name, project, date, duration) in work {
+            let rate = rates.rate_for(name, rates.goal_rate(&session.goal, project));
+            let item = match group {
+                Group::Tag => billing::tags(name).next().or(goal_tag).unwrap_or(name),
+                Group::Project => project.unwrap_or("(no project)"),
+            }
+            .to_string();
+            match items.iter_mut().find(|i| i.date == date && i.item == item) {
+                Some(line) => line.duration += duration,
+                None => items.push(LineItem {
+                    date,
+                    item,
+                    duration,
+                    rate,
+                }),
+            }
+        }
+    }
+    for line in &mut items {
+        line.duration = rounding.apply(line.duration);
+    }
+    items.sort_by(|a, b| (a.date, &a.item).cmp(&(b.date, &b.item)));
+    items
+}
+
+/// Invoice draft as a Markdown table with a total row.
+pub fn invoice_markdown(items: &[LineItem], rates: &Rates) -> String {
+    let money =
+        |v: Option<f64>| v.map_or_else(String::new, |v| format!("{}{:.2}", rates.currency, v));
+    let mut out = String::from("# Invoice\n\n");
+    out.push_str("| Date | Item | Hours | Rate | Amount |\n");
+    out.push_str("|------|------|------:|-----:|-------:|\n");
+    for line in items {
+        out.push_str(&format!(
+            "| {} | {} | {:.2} | {} | {} |\n",
+            line.date,
+            line.item.replace('|', "\\|"),
+            line.hours(),
+            money(line.rate),
+            money(line.amount())
+        ));
+    }
+    let hours: f64 = items.iter().map(LineItem::hours).sum();
+    let amount: f64 = items.iter().filter_map(LineItem::amount).sum();
+    out.push_str(&format!(
+        "| | **Total** | **{:.2}** | | **{}** |\n",
+        hours,
+        money(Some(amount))
+    ));
+    out
+}
+
+pub fn invoice_csv(items: &[LineItem]) -> String {
+    let mut out = String::from("date,item,hours,rate,amount\n");
+    for line in items {
+        out.push_str(&format!(
+            "{},{},{:.2},{},{}\n",
+            line.date,
+            csv_field(&line.item),
+            line.hours(),
+            line.rate.map(|r| format!("{:.2}", r)).unwrap_or_default(),
+            line.amount()
+                .map(|a| format!("{:.2}", a))
+                .unwrap_or_default()
+        ));
+    }
+    out
+}
+
+/// An account name part: no tags, no `:` (which separates the parts) and
+/// no runs of spaces (two end the account name).
+fn account_part(name: &str) -> String {
+    let mut words: Vec<&str> = name.split_whitespace().collect();
+    if billing::tags(name).next().is_some() {
+        words.pop();
+    }
+    let part = words.join(" ").replace(':', "-");
+    if part.is_empty() {
+        "(unnamed)".to_string()
+    } else {
+        part
+    }
+}
+
+/// A heading as a timeclock account.
+struct Account<'a> {
+    name: String,
+    parent: Option<usize>,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    project: Option<&'a str>,
+}
+
+/// Timeclock entries for hledger or ledger, one account per heading: the
+/// goal, then `goal:subgoal:nested` for its subgoals. Each stretch of time
+/// is clocked to the innermost heading running then, so nothing is counted
+/// twice and the goal's own account only gets the time outside its
+/// subgoals. The project, if any, is the description.
+pub fn timeclock(sessions: &[Session]) -> String {
+    let mut out = String::new();
+    for session in sessions {
+        // the goal first, then the splits; parents as indexes into this
+        let mut accounts = vec![Account {
+            name: account_part(&session.goal),
+            parent: None,
+            start: session.start,
+            end: session.end,
+            project: session.project.as_deref(),
+        }];
+        for split in &session.splits {
+            let parent = split.parent.map_or(0, |p| p + 1);
+            accounts.push(Account {
+                name: format!("{}:{}", accounts[parent].name, account_part(&split.name)),
+                parent: Some(parent),
+                start: split.start,
+                end: split.end,
+                project: split.project.as_deref(),
+            });
+        }
+        let depth = |mut i: usize| {
+            let mut depth = 0;
+            while let Some(parent) = accounts[i].parent {
+                depth += 1;
+                i = parent;
+            }
+            depth
+        };
+        let mut times: Vec<NaiveDateTime> =
+            accounts.iter().flat_map(|a| [a.start, a.end]).collect();
+        times.sort();
+        times.dedup();
+        // (node, from, to), with neighbouring stretches of a node joined
+        let mut stretches: Vec<(usize, NaiveDateTime, NaiveDateTime)> = Vec::new();
+        for pair in times.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let Some(node) = (0..accounts.len())
+                .filter(|&i| accounts[i].start <= from && to <= accounts[i].end)
+                .max_by_key(|&i| (depth(i), i))
+            else {
+                continue;
+            };
+            match stretches.last_mut() {
+                Some(last) if last.0 == node && last.2 == from => last.2 = to,
+                _ => stretches.push((node, from, to)),
+            }
+        }
+        for (node, from, to) in stretches {
+            let account = &accounts[node];
+            out.push_str(&format!(
+                "i {} {}",
+                from.format("%Y-%m-%d %H:%M:%S"),
+                account.name
+            ));
+            if let Some(project) = account.project {
+                out.push_str(&format!("  {}", project));
+            }
+            out.push_str(&format!("\no {}\n", to.format("%Y-%m-%d %H:%M:%S")));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// A Beancount currency for a rate's currency symbol, if there is one.
+fn currency_code(currency: &str) -> Option<&str> {
+    match currency {
+        "$" => Some("USD"),
+        "€" => Some("EUR"),
+        "£" => Some("GBP"),
+        "¥" => Some("JPY"),
+        code if code.len() >= 2
+            && code.starts_with(|c: char| c.is_ascii_uppercase())
+            && code
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) =>
+        {
+            Some(code)
+        }
+        _ => None,
+    }
+}
+
+/// A Beancount string.
+fn bean_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// One transaction per goal, with a posting of `HOUR`s per top-level
+/// subgoal (or for the goal itself when it has none) to the account its
+/// tags map to, priced at its rate when it has one, and the balance on the
+/// unbilled account. Hours are rounded per posting, as on an invoice. The
+/// accounts used are opened before the first transaction.
+pub fn beancount(
+    sessions: &[Session],
+    rounding: Rounding,
+    rates: &Rates,
+    accounts: &Accounts,
+) -> String {
+    let currency = currency_code(&rates.currency);
+    let mut used: Vec<&str> = vec![&accounts.unbilled];
+    let mut transactions = String::new();
+    for session in sessions {
+        let mut work: Vec<(&str, Option<&str>, Duration)> = session
+            .splits
+            .iter()
+            .filter(|s| s.parent.is_none())
+            .map(|s| (s.name.as_str(), s.project.as_deref(), s.duration))
+            .collect();
+        if work.is_empty() {
+            work.push((&session.goal, session.project.as_deref(), session.total));
+        }
+        transactions.push_str(&format!("{} *", session.start.date()));
+        if let Some(project) = &session.project {
+            transactions.push_str(&format!(" {}", bean_string(project)));
+        }
+        transactions.push_str(&format!(" {}", bean_string(&account_part(&session.goal))));
+        for tag in billing::tags(&session.goal) {
+            transactions.push_str(&format!(" #{}", tag));
+        }
+        transactions.push('\n');
+        for (name, project, duration) in work {
+            let account = accounts.account_for(name, &session.goal, project);
+            if !used.contains(&account) {
+                used.push(account);
+            }
+            let hours = rounding.apply(duration).as_secs_f64() / 3600.0;
+            transactions.push_str(&format!("  {}  {:.2} HOUR", account, -hours));
+            let rate = rates.rate_for(name, rates.goal_rate(&session.goal, project));
+            if let (Some(rate), Some(currency)) = (rate, currency) {
+                transactions.push_str(&format!(" @ {:.2} {}", rate, currency));
+            }
+            transactions.push('\n');
+            if name != session.goal {
+                transactions.push_str(&format!("    subgoal: {}\n", bean_string(name)));
+            }
+        }
+        transactions.push_str(&format!("  {}\n\n", accounts.unbilled));
+    }
+
+    let mut out = String::new();
+    if let Some(first) = sessions.iter().map(|s| s.start.date()).min() {
+        out.push_str(&format!("{} commodity HOUR\n", first));
+        for account in used {
+            out.push_str(&format!("{} open {}\n", first, account));
+        }
+        out.push('\n');
+    }
+    out.push_str(&transactions);
+    out
+}
+
+/// Mermaid task names end at ':' and '#' starts an entity, so replace both.
+fn mermaid_label(s: &str) -> String {
+    s.replace([':', '#', ';'], " ").trim().to_string()
+}
+
+/// Mermaid `gantt` diagram with one section per top-level subgoal. Tasks are
+/// placed at their wall-clock start and sized by their recorded duration,
+/// since the log only keeps minute-resolution timestamps.
+pub fn mermaid(session: &Session) -> String {
+    let mut out = String::from("gantt\n");
+    out.push_str(&format!("    title {}\n", mermaid_label(&session.goal)));
+    out.push_str("    dateFormat YYYY-MM-DD HH:mm\n");
+    out.push_str("    axisFormat %H:%M\n");
+    for (i, split) in session.splits.iter().enumerate() {
+        if split.parent.is_none() {
+            out.push_str(&format!("    section {}\n", mermaid_label(&split.name)));
+        }
+        let depth = depth(session, i);
+        let name = format!("{}{}", "- ".repeat(depth), mermaid_label(&split.name));
+        out.push_str(&format!(
+            "    {} :s{}, {}, {}s\n",
+            name,
+            i + 1,
+            split.start.format("%Y-%m-%d %H:%M"),
+            split.duration.as_secs().max(1)
+        ));
+    }
+    out
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Graphviz digraph of the goal tree; every node is labelled with its name
+/// and duration.
+pub fn dot(session: &Session, fmt: &DurationFormat) -> String {
+    let mut out = String::from("digraph session {\n");
+    out.push_str("    rankdir=LR;\n    node [shape=box, fontname=\"sans-serif\"];\n");
+    out.push_str(&format!(
+        "    goal [label=\"{}\\n{}\", style=bold];\n",
+        escape_dot(&session.goal),
+        fmt.format(session.total)
+    ));
+    for (i, split) in session.splits.iter().enumerate() {
+        out.push_str(&format!(
+            "    s{} [label=\"{}\\n{}\"];\n",
+            i + 1,
+            escape_dot(&split.name),
+            fmt.format(split.duration)
+        ));
+        match split.parent {
+            Some(p) => out.push_str(&format!("    s{} -> s{};\n", p + 1, i + 1)),
+            None => out.push_str(&format!("    goal -> s{};\n", i + 1)),
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+const SVG_WIDTH: f64 = 900.0;
+const SVG_ROW: f64 = 28.0;
+const SVG_MARGIN: f64 = 20.0;
+const SVG_COLORS: &[&str] = &[
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+/// Horizontal SVG timeline. The goal spans the top row, every subgoal is a
+/// bar placed at its start time and sized by its duration, nested subgoals
+/// go one row further down, and each top-level subtree gets its own color.
+pub fn svg(session: &Session, fmt: &DurationFormat, profile: Profile) -> String {
+    // The log only has minute timestamps, so splits that started within the
+    // same minute would be drawn on top of each other. Subgoals on one row
+    // never overlap in reality, so push each one past the previous bar on its
+    // row and never before its parent.
+    let mut offsets: Vec<Duration> = Vec::with_capacity(session.splits.len());
+    let mut row_end: Vec<Duration> = Vec::new();
+    for (i, split) in session.splits.iter().enumerate() {
+        let row = depth(session, i);
+        row_end.resize(row_end.len().max(row + 1), Duration::ZERO);
+        let mut offset = (split.start - session.start).to_std().unwrap_or_default();
+        offset = offset.max(row_end[row]);
+        if let Some(p) = split.parent {
+            offset = offset.max(offsets[p]);
+        }
+        row_end[row] = offset + split.duration;
+        offsets.push(offset);
+    }
+    let span = row_end
+        .iter()
+        .copied()
+        .fold(session.total, Duration::max)
+        .as_secs_f64()
+        .max(1.0);
+    let scale = (SVG_WIDTH - 2.0 * SVG_MARGIN) / span;
+    let rows = row_end.len() + 1;
+    let height = SVG_MARGIN * 2.0 + rows as f64 * SVG_ROW + 20.0;
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+        SVG_WIDTH, height
+    );
+    let bar = |out: &mut String, row: usize, x: f64, dur: Duration, color: &str, label: &str| {
+        let y = SVG_MARGIN + row as f64 * SVG_ROW;
+        let w = (dur.as_secs_f64() * scale).max(2.0);
+        out.push_str(&format!(
+            "  <g><title>{}</title><rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"3\" fill=\"{}\"/>",
+            label,
+            SVG_MARGIN + x * scale,
+            y,
+            w,
+            SVG_ROW - 6.0,
+            color
+        ));
+        // only label bars wide enough to hold the text; the tooltip has it
+        if w > label.chars().count() as f64 * 7.0 {
+            out.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"#fff\">{}</text>",
+                SVG_MARGIN + x * scale + 4.0,
+                y + SVG_ROW / 2.0 + 1.0,
+                label
+            ));
+        }
+        out.push_str("</g>\n");
+    };
+
+    let goal_label = format!(
+        "{} ({})",
+        escape_html(&session.goal),
+        fmt.format(session.total)
+    );
+    bar(&mut out, 0, 0.0, session.total, "#555", &goal_label);
+    for (i, split) in session.splits.iter().enumerate() {
+        let mut root = i;
+        while let Some(p) = session.splits[root].parent {
+            root = p;
+        }
+        let top = session.splits[..=root]
+            .iter()
+            .filter(|s| s.parent.is_none())
+            .count();
+        let color = SVG_COLORS[(top - 1) % SVG_COLORS.len()];
+        let label = format!(
+            "{} ({})",
+            escape_html(&split.name),
+            fmt.format(split.duration)
+        );
+        bar(
+            &mut out,
+            depth(session, i) + 1,
+            offsets[i].as_secs_f64(),
+            split.duration,
+            color,
+            &label,
+        );
+    }
+
+    // time axis with the wall-clock start and end
+    let axis_y = SVG_MARGIN + rows as f64 * SVG_ROW + 4.0;
+    out.push_str(&format!(
+        "  <line x1=\"{m}\" y1=\"{y:.1}\" x2=\"{x2:.1}\" y2=\"{y:.1}\" stroke=\"#999\"/>\n",
+        m = SVG_MARGIN,
+        y = axis_y,
+        x2 = SVG_WIDTH - SVG_MARGIN
+    ));
+    out.push_str(&format!(
+        "  <text x=\"{}\" y=\"{:.1}\" fill=\"#555\">{}</text>\n",
+        SVG_MARGIN,
+        axis_y + 14.0,
+        profile.stamp(session.start)
+    ));
+    out.push_str(&format!(
+        "  <text x=\"{:.1}\" y=\"{:.1}\" fill=\"#555\" text-anchor=\"end\">+{}</text>\n",
+        SVG_WIDTH - SVG_MARGIN,
+        axis_y + 14.0,
+        fmt.format(Duration::from_secs_f64(span))
+    ));
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Nesting depth of split `i`, 0 for top-level subgoals.
+fn depth(session: &Session, mut i: usize) -> usize {
+    let mut depth = 0;
+    while let Some(parent) = session.splits[i].parent {
+        depth += 1;
+        i = parent;
+    }
+    depth
+}
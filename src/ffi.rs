@@ -0,0 +1,230 @@
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    ptr,
+    time::Duration,
+};
+
+use crate::journal;
+use crate::json::Object;
+use crate::stopwatch::{Action, Stopwatch};
+
+/// Bumped whenever a function in `include/stopwatch.h` changes; new
+/// functions can be added without it.
+pub const SW_API_VERSION: u32 = 1;
+
+/// A timer for C callers, behind a pointer from `sw_timer_new`. It isn't
+/// safe to use from two threads at once.
+pub struct SwTimer {
+    sw: Stopwatch,
+    /// The last error, for `sw_timer_last_error`.
+    error: CString,
+}
+
+impl SwTimer {
+    fn fail(&mut self, e: impl Into<Vec<u8>>) -> c_int {
+        // NULs can only come from the caller's own text; drop them
+        let mut bytes = e.into();
+        bytes.retain(|&b| b != 0);
+        self.error = CString::new(bytes).unwrap_or_default();
+        -1
+    }
+
+    fn act(&mut self, action: Action) -> c_int {
+        self.sw.apply(&action);
+        0
+    }
+}
+
+/// The timer's state as JSON: the goal, whether it is running, the time so
+/// far and every split with its own time and that of its direct subgoals.
+pub(crate) fn state(sw: &Stopwatch) -> String {
+    let total = sw.total();
+    let ms = |d: Duration| d.as_millis() as u64;
+    let splits: Vec<String> = sw
+        .splits
+        .iter()
+        .enumerate()
+        .map(|(i, split)| {
+            Object::new()
+                .str("name", &split.name)
+                .opt_num("parent", split.parent.map(|p| p as u64))
+                .opt_num("level", Some(split.level as u64))
+                .opt_num("start_ms", Some(ms(split.start_offset)))
+                .opt_num("end_ms", split.end_offset.map(ms))
+                .opt_num("elapsed_ms", Some(ms(split.length(total))))
+                .opt_num("rollup_ms", sw.rollup(i, total).map(ms))
+                .bool("active", sw.active == Some(i))
+                .finish()
+        })
+        .collect();
+    Object::new()
+        .opt_str("goal", sw.main_goal.as_deref())
+        .opt_str("project", sw.project.as_deref())
+        .bool("running", sw.running)
+        .opt_num("elapsed_ms", Some(ms(total)))
+        .opt_num("paused_ms", Some(ms(sw.paused())))
+        .raw("splits", &format!("[{}]", splits.join(",")))
+        .finish()
+}
+
+/// `s` as a `&str`, or why it can't be.
+unsafe fn text<'a>(s: *const c_char) -> Result<&'a str, &'static str> {
+    if s.is_null() {
+        return Err("null string");
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| "not UTF-8")
+}
+
+#[no_mangle]
+pub extern "C" fn sw_api_version() -> u32 {
+    SW_API_VERSION
+}
+
+/// A new timer, stopped and without a goal. Free it with `sw_timer_free`.
+#[no_mangle]
+pub extern "C" fn sw_timer_new() -> *mut SwTimer {
+    Box::into_raw(Box::new(SwTimer {
+        sw: Stopwatch::new(),
+        error: CString::default(),
+    }))
+}
+
+/// # Safety
+///
+/// `timer` must come from `sw_timer_new` and not be used afterwards. NULL
+/// is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_free(timer: *mut SwTimer) {
+    if !timer.is_null() {
+        drop(Box::from_raw(timer));
+    }
+}
+
+/// Start timing `goal`, dropping any splits of the goal before.
+///
+/// # Safety
+///
+/// `timer` must be a live timer and `goal` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_start(timer: *mut SwTimer, goal: *const c_char) -> c_int {
+    let timer = &mut *timer;
+    match text(goal) {
+        Ok(goal) => timer.act(Action::Start {
+            goal: goal.to_string(),
+            project: None,
+        }),
+        Err(e) => timer.fail(e),
+    }
+}
+
+/// # Safety
+///
+/// `timer` must be a live timer.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_pause(timer: *mut SwTimer) -> c_int {
+    (*timer).act(Action::Pause)
+}
+
+/// # Safety
+///
+/// `timer` must be a live timer.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_resume(timer: *mut SwTimer) -> c_int {
+    (*timer).act(Action::Resume)
+}
+
+/// Start a split named `name` under the split at index `parent`, or at the
+/// top level when `parent` is negative. It becomes the active split.
+///
+/// # Safety
+///
+/// `timer` must be a live timer and `name` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_split(
+    timer: *mut SwTimer,
+    name: *const c_char,
+    parent: i64,
+) -> c_int {
+    let timer = &mut *timer;
+    let name = match text(name) {
+        Ok(name) => name.to_string(),
+        Err(e) => return timer.fail(e),
+    };
+    let parent = usize::try_from(parent).ok();
+    if parent.is_some_and(|p| p >= timer.sw.splits.len()) {
+        return timer.fail(format!("no split {}", parent.unwrap_or_default()));
+    }
+    timer.act(Action::Split { name, parent })
+}
+
+/// Stop the active split; its parent, if any, becomes the active one.
+///
+/// # Safety
+///
+/// `timer` must be a live timer.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_stop(timer: *mut SwTimer) -> c_int {
+    (*timer).act(Action::StopActive)
+}
+
+/// Apply any action as a journal record; see the journal format.
+///
+/// # Safety
+///
+/// `timer` must be a live timer and `record` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_apply(timer: *mut SwTimer, record: *const c_char) -> c_int {
+    let timer = &mut *timer;
+    let result = text(record)
+        .map_err(str::to_string)
+        .and_then(|record| journal::apply(&mut timer.sw, record));
+    match result {
+        Ok(()) => 0,
+        Err(e) => timer.fail(e),
+    }
+}
+
+/// Active time so far, in milliseconds.
+///
+/// # Safety
+///
+/// `timer` must be a live timer.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_elapsed_ms(timer: *const SwTimer) -> u64 {
+    (*timer).sw.total().as_millis() as u64
+}
+
+/// The timer's state as a JSON object, to be freed with `sw_string_free`.
+///
+/// # Safety
+///
+/// `timer` must be a live timer.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_serialize(timer: *const SwTimer) -> *mut c_char {
+    // JSON escapes control characters, so there is no NUL to fail on
+    CString::new(state(&(*timer).sw))
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Why the last call on `timer` that returned -1 failed. The text belongs
+/// to the timer and lasts until its next failure.
+///
+/// # Safety
+///
+/// `timer` must be a live timer.
+#[no_mangle]
+pub unsafe extern "C" fn sw_timer_last_error(timer: *const SwTimer) -> *const c_char {
+    (*timer).error.as_ptr()
+}
+
+/// # Safety
+///
+/// `s` must come from `sw_timer_serialize` and not be used afterwards. NULL
+/// is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn sw_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::Message;
+
+/// How often the repository is checked for a new commit.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watches a git repository and reports each new commit's subject, so the
+/// stopwatch can start a split per commit. It stops when dropped.
+pub struct Watcher {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    pub fn start(repo: &Path, tx: mpsc::Sender<Message>) -> io::Result<Watcher> {
+        // fail now if it isn't a repository rather than silently later
+        let mut last = head(repo)?;
+        log::info!("watching {} for commits", repo.display());
+        let repo: PathBuf = repo.to_path_buf();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                let step = Duration::from_millis(100);
+                let mut waited = Duration::ZERO;
+                while !shutdown.load(Ordering::Relaxed) {
+                    // short sleeps so the shutdown flag is noticed
+                    thread::sleep(step);
+                    waited += step;
+                    if waited < POLL_INTERVAL {
+                        continue;
+                    }
+                    waited = Duration::ZERO;
+                    let commit = match head(&repo) {
+                        Ok(commit) => commit,
+                        Err(e) => {
+                            log::debug!("git: {}", e);
+                            continue;
+                        }
+                    };
+                    if commit.as_ref().map(|c| &c.0) == last.as_ref().map(|c| &c.0) {
+                        continue;
+                    }
+                    if let Some((hash, subject)) = &commit {
+                        log::info!("new commit {} '{}'", hash, subject);
+                        if tx.send(Message::Commit(subject.clone())).is_err() {
+                            break;
+                        }
+                    }
+                    last = commit;
+                }
+            })
+        };
+        Ok(Watcher {
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// The hash and subject of the commit `HEAD` points at, or `None` in a
+/// repository with no commits yet.
+fn head(repo: &Path) -> io::Result<Option<(String, String)>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["log", "-1", "--format=%H%n%s"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("does not have any commits") {
+            return Ok(None);
+        }
+        return Err(io::Error::other(stderr.trim().to_string()));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut lines = text.lines();
+    Ok(lines
+        .next()
+        .map(|hash| (hash.to_string(), lines.next().unwrap_or("").to_string())))
+}
+
+/// Commit `file` as it is now to the repository it is in, with `message`.
+/// Only that file goes into the commit; anything else staged stays staged.
+pub fn commit(file: &Path, message: &str) -> io::Result<()> {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = file.file_name().unwrap_or_default();
+    let run = |args: &[&str]| -> io::Result<()> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .arg("--")
+            .arg(name)
+            .stdin(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            // git commit says what is wrong on stdout
+            let mut text = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            if text.is_empty() {
+                text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            }
+            return Err(io::Error::other(text));
+        }
+        Ok(())
+    };
+    run(&["add"])?;
+    run(&["commit", "--quiet", "--message", message])
+}
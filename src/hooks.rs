@@ -0,0 +1,159 @@
+use std::{process::Stdio, thread};
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+use crate::platform;
+use crate::stopwatch::{Action, Stopwatch};
+
+/// Timer events a hook command can run on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A goal was started or resumed.
+    Start,
+    /// The timer was paused.
+    Stop,
+    SplitStart,
+    SplitEnd,
+    Save,
+    /// The goal's time passed one of the `--alert` marks.
+    Milestone,
+    /// Another window got the focus, with `--focus note`.
+    Focus,
+}
+
+impl Event {
+    pub fn from_name(name: &str) -> Option<Event> {
+        match name {
+            "start" => Some(Event::Start),
+            "stop" => Some(Event::Stop),
+            "split_start" => Some(Event::SplitStart),
+            "split_end" => Some(Event::SplitEnd),
+            "save" => Some(Event::Save),
+            "milestone" => Some(Event::Milestone),
+            "focus" => Some(Event::Focus),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Start => "start",
+            Event::Stop => "stop",
+            Event::SplitStart => "split_start",
+            Event::SplitEnd => "split_end",
+            Event::Save => "save",
+            Event::Milestone => "milestone",
+            Event::Focus => "focus",
+        }
+    }
+}
+
+/// Shell commands to run on timer events, as given by `--hook EVENT=CMD` or
+/// `hooks.EVENT` in the config.
+#[derive(Clone, Debug, Default)]
+pub struct Hooks {
+    pub commands: Vec<(Event, String)>,
+}
+
+impl Hooks {
+    /// The events `action` sets off, each with the split it is about, going
+    /// by the stopwatch before the action is applied.
+    pub fn triggered(&self, action: &Action, sw: &Stopwatch) -> Vec<(Event, Option<usize>)> {
+        if self.commands.is_empty() {
+            return Vec::new();
+        }
+        let open = |i: &usize| sw.splits[*i].end_offset.is_none();
+        match action {
+            Action::Start { .. } => vec![(Event::Start, None)],
+            Action::Resume if !sw.running => vec![(Event::Start, None)],
+            Action::Pause if sw.running => vec![(Event::Stop, None)],
+            Action::Split { .. } => vec![(Event::SplitStart, Some(sw.splits.len()))],
+            Action::StopActive => sw
+                .active
+                .filter(open)
+                .map(|i| (Event::SplitEnd, Some(i)))
+                .into_iter()
+                .collect(),
+            Action::Stop { index } => Some(*index)
+                .filter(|i| *i < sw.splits.len() && open(i))
+                .map(|i| (Event::SplitEnd, Some(i)))
+                .into_iter()
+                .collect(),
+            Action::CloseOpen => (0..sw.splits.len())
+                .filter(open)
+                .map(|i| (Event::SplitEnd, Some(i)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Run the commands for `event` with details of the stopwatch (after the
+    /// action) and of `split`, if any. Commands run through `sh -c` with the
+    /// event name as `$1` (`cmd /C` on Windows, where there is only
+    /// `%SW_EVENT%`) and the details in `SW_*` environment variables;
+    /// they run in the background with no terminal, so a slow or failing
+    /// hook never holds up the timer.
+    pub fn run(
+        &self,
+        event: Event,
+        sw: &Stopwatch,
+        split: Option<usize>,
+        at: DateTime<Local>,
+        extra: &[(&str, String)],
+    ) {
+        let commands: Vec<&String> = self
+            .commands
+            .iter()
+            .filter(|(e, _)| *e == event)
+            .map(|(_, cmd)| cmd)
+            .collect();
+        if commands.is_empty() {
+            return;
+        }
+        let mut vars: Vec<(&str, String)> = vec![
+            ("SW_EVENT", event.name().to_string()),
+            ("SW_TIME", at.to_rfc3339_opts(SecondsFormat::Secs, false)),
+            ("SW_GOAL", sw.main_goal.clone().unwrap_or_default()),
+            ("SW_PROJECT", sw.project.clone().unwrap_or_default()),
+            ("SW_ELAPSED", sw.total().as_secs().to_string()),
+            ("SW_RUNNING", (sw.running as u8).to_string()),
+        ];
+        if let Some(split) = split.and_then(|i| sw.splits.get(i)) {
+            let parent = split.parent.map(|p| sw.splits[p].name.clone());
+            vars.push(("SW_SPLIT", split.name.clone()));
+            vars.push(("SW_SPLIT_PARENT", parent.unwrap_or_default()));
+            vars.push((
+                "SW_SPLIT_ELAPSED",
+                split.length(sw.total()).as_secs().to_string(),
+            ));
+        }
+        vars.extend(extra.iter().cloned());
+        for command in commands {
+            let mut shell = platform::shell(command);
+            if cfg!(unix) {
+                shell.arg("sw").arg(event.name());
+            }
+            let child = shell
+                .envs(vars.iter().map(|(k, v)| (k, v)))
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            let mut child = match child {
+                Ok(child) => child,
+                Err(e) => {
+                    log::warn!("{} hook '{}': {}", event.name(), command, e);
+                    continue;
+                }
+            };
+            let (event, command) = (event.name(), command.clone());
+            thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => {
+                    log::warn!("{} hook '{}' failed: {}", event, command, status)
+                }
+                Err(e) => log::warn!("{} hook '{}': {}", event, command, e),
+                Ok(_) => log::debug!("{} hook '{}' done", event, command),
+            });
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{Message, Press};
+
+/// Minimal HTTP server answering `GET /press/split` and `GET /press/toggle`.
+/// It stops when dropped.
+pub struct Server {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    pub fn start(addr: &str, tx: mpsc::Sender<Message>) -> io::Result<Server> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        log::info!("http control on {}", listener.local_addr()?);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = shutdown.clone();
+            thread::spawn(move || serve(listener, &tx, &shutdown))
+        };
+        Ok(Server {
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn serve(listener: TcpListener, tx: &mpsc::Sender<Message>, shutdown: &AtomicBool) {
+    while !shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                if let Err(e) = handle(stream, tx) {
+                    log::warn!("http request from {} failed: {}", peer, e);
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => log::warn!("http accept failed: {}", e),
+        }
+    }
+}
+
+fn handle(stream: TcpStream, tx: &mpsc::Sender<Message>) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // drain the headers; bodies are never needed
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = target.split('?').next().unwrap_or("");
+    log::debug!("http {} {}", method, target);
+    let press = path
+        .trim_end_matches('/')
+        .strip_prefix("/press/")
+        .and_then(Press::from_name);
+    let (status, body) = match (method, press) {
+        ("GET" | "POST", Some(press)) => {
+            let _ = tx.send(Message::Press(press));
+            ("200 OK", "ok\n")
+        }
+        ("GET" | "POST", None) => ("404 Not Found", "unknown endpoint\n"),
+        _ => ("405 Method Not Allowed", "use GET\n"),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
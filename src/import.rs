@@ -0,0 +1,186 @@
+use std::{fs, io, path::Path};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::append_headings;
+use crate::options::{ImportArgs, Options};
+use crate::orglog::{self, Session};
+
+/// `sw import`: read org files written earlier, by this tool or by hand in
+/// Emacs, or CSV exports from Toggl Track and Clockify, and add their
+/// sessions to the log so reports and exports cover them. Sessions already
+/// in the log (same goal and start) are skipped, so importing the same file
+/// twice is harmless.
+pub fn run(opts: &Options, args: &ImportArgs) -> Result<(), String> {
+    let existing = match orglog::load(Path::new(&opts.log_file), &opts.crypt) {
+        Ok(text) => orglog::parse(&text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("{}: {}", opts.log_file, e)),
+    };
+    let mut seen: Vec<(String, NaiveDateTime)> =
+        existing.iter().map(|s| (s.goal.clone(), s.start)).collect();
+    let mut new: Vec<Session> = Vec::new();
+    let mut skipped = 0;
+    for path in &args.files {
+        let is_csv = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let sessions = if is_csv {
+            read_csv(path)
+        } else {
+            orglog::read(path, &opts.crypt).map_err(|e| e.to_string())
+        }
+        .map_err(|e| format!("{}: {}", path, e))?;
+        log::info!("{}: {} sessions", path, sessions.len());
+        for session in sessions {
+            let key = (session.goal.clone(), session.start);
+            if seen.contains(&key) {
+                skipped += 1;
+                continue;
+            }
+            seen.push(key);
+            new.push(session);
+        }
+    }
+    new.sort_by_key(|s| s.start);
+
+    let stars = opts.log_parent.len() + 1;
+    let headings: Vec<Vec<orglog::Heading>> = new
+        .iter()
+        .map(|s| s.headings(stars, &opts.format))
+        .collect();
+    if !headings.is_empty() {
+        append_headings(&opts.log_file, &headings, opts)
+            .map_err(|e| format!("{}: {}", opts.log_file, e))?;
+    }
+    if !opts.quiet {
+        println!(
+            "Imported {} sessions into {} ({} already there)",
+            new.len(),
+            opts.log_file,
+            skipped
+        );
+    }
+    Ok(())
+}
+
+/// The time entries in a Toggl Track or Clockify CSV export, one session
+/// each. Both name their columns the same way apart from case: the
+/// description becomes the goal, its tags org tags on it, and the project
+/// (or else the client) its project.
+fn read_csv(path: &str) -> Result<Vec<Session>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut records = csv_records(text.trim_start_matches('\u{feff}')).into_iter();
+    let header: Vec<String> = records
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (Some(start_date), Some(start_time), Some(end_date), Some(end_time)) = (
+        column("start date"),
+        column("start time"),
+        column("end date"),
+        column("end time"),
+    ) else {
+        return Err("not a Toggl or Clockify export (no start and end columns)".to_string());
+    };
+    let description = column("description");
+    let task = column("task");
+    let project = column("project");
+    let client = column("client");
+    let tags = column("tags");
+
+    let mut sessions = Vec::new();
+    for (n, record) in records.enumerate() {
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+        let field = |i: Option<usize>| {
+            i.and_then(|i| record.get(i))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+        };
+        let stamp = |date, time| {
+            let date = parse_date(field(Some(date))?)?;
+            let time = parse_time(field(Some(time))?)?;
+            Some(date.and_time(time))
+        };
+        let (Some(start), Some(end)) = (stamp(start_date, start_time), stamp(end_date, end_time))
+        else {
+            return Err(format!("row {}: can't read the start or end time", n + 2));
+        };
+        let mut goal = field(description)
+            .or(field(task))
+            .or(field(project))
+            .unwrap_or("(no description)")
+            .to_string();
+        let tags: Vec<String> = field(tags)
+            .unwrap_or("")
+            .split(',')
+            .map(|t| t.trim().replace([' ', ':'], "_"))
+            .filter(|t| !t.is_empty())
+            .collect();
+        if !tags.is_empty() {
+            goal = format!("{} :{}:", goal, tags.join(":"));
+        }
+        // the log keeps minutes, so seconds would defeat the duplicate check
+        let minute = |t: NaiveDateTime| t.with_second(0).unwrap_or(t);
+        sessions.push(Session {
+            goal,
+            start: minute(start),
+            end: minute(end),
+            total: (end - start).to_std().unwrap_or_default(),
+            splits: Vec::new(),
+            project: field(project).or(field(client)).map(str::to_string),
+        });
+    }
+    Ok(sessions)
+}
+
+/// The records of a CSV file, with quoted fields that may hold commas,
+/// doubled quotes and line breaks.
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// `2024-01-31` as Toggl writes it, or `01/31/2024` and `31.01.2024` as
+/// Clockify does depending on the workspace settings.
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y", "%d-%m-%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+}
+
+/// `14:05:00`, `14:05` or `02:05:00 PM`.
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(s, format).ok())
+}
@@ -0,0 +1,103 @@
+use std::{
+    io,
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// Keeps the machine from suspending while the timer runs. It holds a
+/// sleep lock through `systemd-inhibit` on Linux, or `caffeinate` on macOS,
+/// for as long as the helper process is alive. There is no such helper on
+/// Windows. The lock goes when the timer
+/// stops or this is dropped.
+#[derive(Default)]
+pub struct Inhibitor {
+    child: Option<Child>,
+    /// Set once the helper couldn't be started, so that isn't retried on
+    /// every action.
+    failed: bool,
+}
+
+impl Inhibitor {
+    /// Take the lock while `running`, and let it go otherwise.
+    pub fn set(&mut self, running: bool) {
+        if running == self.child.is_some() || (running && self.failed) {
+            return;
+        }
+        if !running {
+            self.release();
+            return;
+        }
+        match spawn() {
+            Ok(child) => {
+                log::info!("inhibiting sleep");
+                self.child = Some(child);
+            }
+            Err(e) => {
+                log::warn!("can't inhibit sleep: {}", e);
+                self.failed = true;
+            }
+        }
+    }
+
+    fn release(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            // closing stdin ends the `cat` the lock is held for, and with it
+            // the helper; one that doesn't go soon is killed
+            if child.stdin.take().is_some() {
+                for _ in 0..20 {
+                    if !matches!(child.try_wait(), Ok(None)) {
+                        break;
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            log::info!("stopped inhibiting sleep");
+        }
+    }
+}
+
+impl Drop for Inhibitor {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn spawn() -> io::Result<Child> {
+    // -w ends it with this process, should the stopwatch be killed
+    Command::new("caffeinate")
+        .arg("-i")
+        .arg("-w")
+        .arg(std::process::id().to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn spawn() -> io::Result<Child> {
+    // the lock is held for as long as `cat` runs, which is until its stdin,
+    // held by this process, is closed; so a crash can't leave it behind
+    Command::new("systemd-inhibit")
+        .arg("--what=sleep:idle")
+        .arg("--who=stopwatch")
+        .arg("--why=Timing a session")
+        .arg("--mode=block")
+        .arg("cat")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
+#[cfg(windows)]
+fn spawn() -> io::Result<Child> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "not supported on Windows",
+    ))
+}
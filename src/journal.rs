@@ -0,0 +1,304 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+use crate::json::{self, Object, Value};
+use crate::stopwatch::{Action, Stopwatch};
+
+/// One line of the journal.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Action(Action),
+    /// The stopwatch exited normally; there is nothing to recover.
+    Quit,
+}
+
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub at: DateTime<Local>,
+    pub event: Event,
+}
+
+/// Append-only JSONL log of every state change, one record per line:
+///
+/// ```text
+/// {"t":"2024-01-31T10:00:00.000+01:00","event":"start","goal":"Write report"}
+/// {"t":"2024-01-31T10:05:12.345+01:00","event":"split","name":"Outline","parent":null}
+/// ```
+pub struct Journal {
+    pub path: String,
+    file: File,
+}
+
+impl Journal {
+    pub fn open(path: &str) -> io::Result<Journal> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Journal {
+            path: path.to_string(),
+            file,
+        })
+    }
+
+    /// Append `record`. Each record is written with a single call so a crash
+    /// leaves at most one partial line.
+    pub fn append(&mut self, record: &Record) -> io::Result<()> {
+        let line = encode(record) + "\n";
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()
+    }
+}
+
+pub fn encode(record: &Record) -> String {
+    let obj = Object::new().str(
+        "t",
+        &record.at.to_rfc3339_opts(SecondsFormat::Millis, false),
+    );
+    let obj = match &record.event {
+        Event::Action(Action::Start { goal, project }) => obj
+            .str("event", "start")
+            .str("goal", goal)
+            .opt_str("project", project.as_deref()),
+        Event::Action(Action::Pause) => obj.str("event", "pause"),
+        Event::Action(Action::Resume) => obj.str("event", "resume"),
+        Event::Action(Action::Reset) => obj.str("event", "reset"),
+        Event::Action(Action::Split { name, parent }) => obj
+            .str("event", "split")
+            .str("name", name)
+            .opt_num("parent", parent.map(|p| p as u64)),
+        Event::Action(Action::StopActive) => obj.str("event", "stop"),
+        Event::Action(Action::Stop { index }) => obj
+            .str("event", "stop")
+            .opt_num("split", Some(*index as u64)),
+        Event::Action(Action::Reparent { index, parent }) => obj
+            .str("event", "move")
+            .opt_num("split", Some(*index as u64))
+            .opt_num("parent", parent.map(|p| p as u64)),
+        Event::Action(Action::Promote { index }) => obj
+            .str("event", "promote")
+            .opt_num("split", Some(*index as u64)),
+        Event::Action(Action::Demote { index }) => obj
+            .str("event", "demote")
+            .opt_num("split", Some(*index as u64)),
+        Event::Action(Action::Merge { index }) => obj
+            .str("event", "merge")
+            .opt_num("split", Some(*index as u64)),
+        Event::Action(Action::Divide { index, at, name }) => obj
+            .str("event", "divide")
+            .opt_num("split", Some(*index as u64))
+            .opt_num("at_ms", Some(at.as_millis() as u64))
+            .str("name", name),
+        Event::Action(Action::Rename { index, name }) => obj
+            .str("event", "rename")
+            .opt_num("split", Some(*index as u64))
+            .str("name", name),
+        Event::Action(Action::SetGoal { goal }) => obj.str("event", "goal").str("goal", goal),
+        Event::Action(Action::SetProject { project }) => obj
+            .str("event", "project")
+            .opt_str("project", project.as_deref()),
+        Event::Action(Action::Finish) => obj.str("event", "finish"),
+        Event::Action(Action::Ascend) => obj.str("event", "up"),
+        Event::Action(Action::CloseOpen) => obj.str("event", "close"),
+        Event::Quit => obj.str("event", "quit"),
+    };
+    obj.finish()
+}
+
+pub fn decode(line: &str) -> Result<Record, String> {
+    let fields = json::parse_object(line)?;
+    let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+    let str_field = |key: &str| {
+        get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| format!("missing string field '{}'", key))
+    };
+    let split_field = || {
+        get("split")
+            .and_then(Value::as_u64)
+            .map(|i| i as usize)
+            .ok_or_else(|| "missing number field 'split'".to_string())
+    };
+    let t = str_field("t")?;
+    let at = DateTime::parse_from_rfc3339(&t)
+        .map_err(|e| format!("bad timestamp '{}': {}", t, e))?
+        .with_timezone(&Local);
+    let event = match str_field("event")?.as_str() {
+        "start" => Event::Action(Action::Start {
+            goal: str_field("goal")?,
+            project: get("project").and_then(Value::as_str).map(str::to_string),
+        }),
+        "pause" => Event::Action(Action::Pause),
+        "resume" => Event::Action(Action::Resume),
+        "reset" => Event::Action(Action::Reset),
+        "split" => Event::Action(Action::Split {
+            name: str_field("name")?,
+            parent: get("parent").and_then(Value::as_u64).map(|p| p as usize),
+        }),
+        "stop" => Event::Action(match get("split").and_then(Value::as_u64) {
+            Some(index) => Action::Stop {
+                index: index as usize,
+            },
+            None => Action::StopActive,
+        }),
+        "move" => Event::Action(Action::Reparent {
+            index: split_field()?,
+            parent: get("parent").and_then(Value::as_u64).map(|p| p as usize),
+        }),
+        "promote" => Event::Action(Action::Promote {
+            index: split_field()?,
+        }),
+        "demote" => Event::Action(Action::Demote {
+            index: split_field()?,
+        }),
+        "merge" => Event::Action(Action::Merge {
+            index: split_field()?,
+        }),
+        "divide" => Event::Action(Action::Divide {
+            index: split_field()?,
+            at: Duration::from_millis(
+                get("at_ms")
+                    .and_then(Value::as_u64)
+                    .ok_or("missing number field 'at_ms'")?,
+            ),
+            name: str_field("name")?,
+        }),
+        "rename" => Event::Action(Action::Rename {
+            index: split_field()?,
+            name: str_field("name")?,
+        }),
+        "goal" => Event::Action(Action::SetGoal {
+            goal: str_field("goal")?,
+        }),
+        "project" => Event::Action(Action::SetProject {
+            project: get("project").and_then(Value::as_str).map(str::to_string),
+        }),
+        "finish" => Event::Action(Action::Finish),
+        "up" => Event::Action(Action::Ascend),
+        "close" => Event::Action(Action::CloseOpen),
+        "quit" => Event::Quit,
+        other => return Err(format!("unknown event '{}'", other)),
+    };
+    Ok(Record { at, event })
+}
+
+/// Read every record in `path`. A missing file is an empty journal, and a
+/// malformed final line is dropped since it is what a crash mid-write leaves.
+pub fn read(path: &str) -> io::Result<Vec<Record>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .collect();
+    let mut records = Vec::with_capacity(lines.len());
+    for (n, &(i, line)) in lines.iter().enumerate() {
+        match decode(line) {
+            Ok(record) => records.push(record),
+            Err(e) if n + 1 == lines.len() => {
+                log::warn!("{}:{}: dropping partial record: {}", path, i + 1, e);
+            }
+            Err(e) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", i + 1, e),
+                ))
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// The records since the last clean exit.
+pub fn pending(records: &[Record]) -> &[Record] {
+    let from = records
+        .iter()
+        .rposition(|r| r.event == Event::Quit)
+        .map_or(0, |i| i + 1);
+    &records[from..]
+}
+
+/// Rebuild the stopwatch from the pending `records`. Wall-clock timestamps are mapped
+/// back onto the monotonic clock so time that passed while the program was
+/// not running still counts for a session that was running when it died.
+/// `None` means there is nothing to recover.
+pub fn replay(records: &[Record]) -> Option<Stopwatch> {
+    let records = pending(records);
+    if records.is_empty() {
+        return None;
+    }
+    let (now, wall_now) = (Instant::now(), Local::now());
+    let mut sw = Stopwatch::new();
+    for record in records {
+        let Event::Action(action) = &record.event else {
+            continue;
+        };
+        sw.apply_at(action, instant(now, wall_now, record.at), record.at);
+    }
+    Some(sw)
+}
+
+/// Apply an action given as a journal record, like the lines of
+/// `--journal`: `{"t":"2024-05-01T09:00:00Z","event":"split","name":"Tests"}`.
+/// `t` is the wall-clock time it happened, mapped back onto the timer's
+/// clock as `replay` does, so records can arrive late.
+pub fn apply(sw: &mut Stopwatch, text: &str) -> Result<(), String> {
+    let record = decode(text)?;
+    if let Event::Action(action) = record.event {
+        let at = instant(sw.clock.now(), sw.clock.wall(), record.at);
+        sw.apply_at(&action, at, record.at);
+    }
+    Ok(())
+}
+
+/// The instant `wall` was, on a clock that reads `now` at `wall_now`; times
+/// still to come are taken as now.
+fn instant(now: Instant, wall_now: DateTime<Local>, wall: DateTime<Local>) -> Instant {
+    let ago = (wall_now - wall).to_std().unwrap_or_default();
+    now.checked_sub(ago).unwrap_or(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(minutes_ago: i64, action: Action) -> String {
+        encode(&Record {
+            at: Local::now() - chrono::Duration::minutes(minutes_ago),
+            event: Event::Action(action),
+        })
+    }
+
+    #[test]
+    fn records_apply_at_their_own_time() {
+        let mut sw = Stopwatch::new();
+        let start = Action::Start {
+            goal: "Report".to_string(),
+            project: None,
+        };
+        let split = Action::Split {
+            name: "Outline".to_string(),
+            parent: None,
+        };
+        apply(&mut sw, &record(30, start)).unwrap();
+        apply(&mut sw, &record(25, split)).unwrap();
+        apply(&mut sw, &record(10, Action::StopActive)).unwrap();
+
+        let split = &sw.splits[0];
+        let length = split.end_offset.unwrap() - split.start_offset;
+        // the journal keeps times to the millisecond
+        assert!(
+            length.abs_diff(Duration::from_secs(15 * 60)) < Duration::from_millis(10),
+            "{:?}",
+            length
+        );
+        assert!(sw.total() > Duration::from_secs(29 * 60));
+    }
+}
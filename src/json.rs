@@ -0,0 +1,220 @@
+use std::fmt::Write;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Num(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as u64),
+            _ => None,
+        }
+    }
+}
+
+/// `s` as a quoted JSON string.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Builds a single-line JSON object, keeping fields in insertion order.
+#[derive(Default)]
+pub struct Object {
+    out: String,
+}
+
+impl Object {
+    pub fn new() -> Self {
+        Object::default()
+    }
+
+    fn key(&mut self, key: &str) {
+        self.out.push(if self.out.is_empty() { '{' } else { ',' });
+        self.out.push_str(&quote(key));
+        self.out.push(':');
+    }
+
+    pub fn str(mut self, key: &str, value: &str) -> Self {
+        self.key(key);
+        self.out.push_str(&quote(value));
+        self
+    }
+
+    pub fn opt_str(mut self, key: &str, value: Option<&str>) -> Self {
+        self.key(key);
+        match value {
+            Some(v) => self.out.push_str(&quote(v)),
+            None => self.out.push_str("null"),
+        }
+        self
+    }
+
+    pub fn bool(mut self, key: &str, value: bool) -> Self {
+        self.key(key);
+        self.out.push_str(if value { "true" } else { "false" });
+        self
+    }
+
+    pub fn opt_num(mut self, key: &str, value: Option<u64>) -> Self {
+        self.key(key);
+        match value {
+            Some(v) => self.out.push_str(&v.to_string()),
+            None => self.out.push_str("null"),
+        }
+        self
+    }
+
+    /// `value` as it is, for one that is JSON already, such as an array.
+    pub fn raw(mut self, key: &str, value: &str) -> Self {
+        self.key(key);
+        self.out.push_str(value);
+        self
+    }
+
+    pub fn finish(mut self) -> String {
+        if self.out.is_empty() {
+            self.out.push('{');
+        }
+        self.out.push('}');
+        self.out
+    }
+}
+
+/// Parse a flat object of strings, numbers, booleans and nulls, which is all
+/// the journal writes. Nested objects and arrays are rejected.
+pub fn parse_object(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut p = Parser {
+        chars: text.trim().chars().peekable(),
+    };
+    p.expect('{')?;
+    let mut fields = Vec::new();
+    p.skip_ws();
+    if p.eat('}') {
+        return p.end(fields);
+    }
+    loop {
+        p.skip_ws();
+        let key = p.string()?;
+        p.skip_ws();
+        p.expect(':')?;
+        p.skip_ws();
+        let value = p.value()?;
+        fields.push((key, value));
+        p.skip_ws();
+        if p.eat('}') {
+            return p.end(fields);
+        }
+        p.expect(',')?;
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.chars.next_if_eq(&c).is_some()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(got) if got == c => Ok(()),
+            Some(got) => Err(format!("expected '{}', found '{}'", c, got)),
+            None => Err(format!("expected '{}', found end of line", c)),
+        }
+    }
+
+    fn end(&mut self, fields: Vec<(String, Value)>) -> Result<Vec<(String, Value)>, String> {
+        match self.chars.next() {
+            None => Ok(fields),
+            Some(c) => Err(format!("unexpected '{}' after object", c)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next().ok_or("unterminated string")? {
+                '"' => return Ok(out),
+                '\\' => match self.chars.next().ok_or("unterminated string")? {
+                    '"' => out.push('"'),
+                    '\\' => out.push('\\'),
+                    '/' => out.push('/'),
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    't' => out.push('\t'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        let code = u32::from_str_radix(&hex, 16)
+                            .map_err(|_| format!("bad escape '\\u{}'", hex))?;
+                        out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    other => return Err(format!("unknown escape '\\{}'", other)),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.chars.peek() {
+            Some('"') => self.string().map(Value::Str),
+            Some('{') | Some('[') => Err("nested values are not supported".to_string()),
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+                {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "null" => Ok(Value::Null),
+                    _ => word
+                        .parse()
+                        .map(Value::Num)
+                        .map_err(|_| format!("cannot parse value '{}'", word)),
+                }
+            }
+            None => Err("expected a value".to_string()),
+        }
+    }
+}
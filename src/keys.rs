@@ -0,0 +1,317 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::KeyCode;
+
+/// Something a key sequence can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Pause, or start a new goal when stopped.
+    StartPause,
+    /// Start the current goal over from zero, or with none yet, start one
+    /// at once to be named later.
+    Restart,
+    Resume,
+    Reset,
+    Subgoal,
+    /// Start a subgoal under a placeholder name right away and name it
+    /// while it runs.
+    QuickSplit,
+    NestedSubgoal,
+    StopActive,
+    /// Stop the split with this number (as shown, counting from 1), typed
+    /// as a count on its own or before the stop key.
+    StopSplit(usize),
+    Up,
+    /// Move the selected split under another parent.
+    Move,
+    Promote,
+    Demote,
+    /// Fold the selected split into the one before it.
+    Merge,
+    /// Cut the selected split in two.
+    Divide,
+    /// Name the selected split, or the active one, without stopping the
+    /// clock.
+    Rename,
+    /// Give the current goal a new name.
+    Goal,
+    /// Switch to another project.
+    Project,
+    /// Finish the goal and start the next one.
+    NextGoal,
+    Redraw,
+    /// Switch between the detailed and compact split list.
+    ToggleView,
+    /// Hide or show the splits that have ended.
+    OpenOnly,
+    /// List the finished splits longest first, or go back to the tree.
+    Sort,
+    /// Show the time per top-level subgoal as bars, or go back to the list.
+    Breakdown,
+    /// Hide or show the goal and split names.
+    Private,
+    Save,
+    SelectPrev,
+    SelectNext,
+    CopyTime,
+    CopySplit,
+    CopySummary,
+    Quit,
+}
+
+impl KeyAction {
+    /// Name used for the action in the `[keys]` config section.
+    pub fn from_name(name: &str) -> Option<KeyAction> {
+        Some(match name {
+            "start" => KeyAction::StartPause,
+            "restart" => KeyAction::Restart,
+            "continue" => KeyAction::Resume,
+            "reset" => KeyAction::Reset,
+            "subgoal" => KeyAction::Subgoal,
+            "quick" => KeyAction::QuickSplit,
+            "nested" => KeyAction::NestedSubgoal,
+            "stop" => KeyAction::StopActive,
+            "up" => KeyAction::Up,
+            "move" => KeyAction::Move,
+            "promote" => KeyAction::Promote,
+            "demote" => KeyAction::Demote,
+            "merge" => KeyAction::Merge,
+            "divide" => KeyAction::Divide,
+            "rename" => KeyAction::Rename,
+            "goal" => KeyAction::Goal,
+            "project" => KeyAction::Project,
+            "next" => KeyAction::NextGoal,
+            "redraw" => KeyAction::Redraw,
+            "view" => KeyAction::ToggleView,
+            "open_only" => KeyAction::OpenOnly,
+            "sort" => KeyAction::Sort,
+            "breakdown" => KeyAction::Breakdown,
+            "private" => KeyAction::Private,
+            "save" => KeyAction::Save,
+            "select_prev" => KeyAction::SelectPrev,
+            "select_next" => KeyAction::SelectNext,
+            "copy_time" => KeyAction::CopyTime,
+            "copy_split" => KeyAction::CopySplit,
+            "copy_summary" => KeyAction::CopySummary,
+            "quit" => KeyAction::Quit,
+            _ => return None,
+        })
+    }
+}
+
+/// Key sequences and what they do. A key that starts a longer sequence
+/// waits up to `timeout` for the rest before acting on its own.
+pub struct Keymap {
+    bindings: Vec<(Vec<KeyCode>, KeyAction)>,
+    pub timeout: Duration,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let single = [
+            ('s', KeyAction::StartPause),
+            ('c', KeyAction::Resume),
+            ('r', KeyAction::Reset),
+            ('g', KeyAction::Subgoal),
+            ('a', KeyAction::QuickSplit),
+            ('n', KeyAction::NestedSubgoal),
+            ('h', KeyAction::StopActive),
+            ('u', KeyAction::Up),
+            ('m', KeyAction::Move),
+            ('<', KeyAction::Promote),
+            ('>', KeyAction::Demote),
+            ('J', KeyAction::Merge),
+            ('x', KeyAction::Divide),
+            ('e', KeyAction::Rename),
+            ('G', KeyAction::Goal),
+            ('p', KeyAction::Project),
+            ('f', KeyAction::NextGoal),
+            ('d', KeyAction::Redraw),
+            ('v', KeyAction::ToggleView),
+            ('o', KeyAction::OpenOnly),
+            ('S', KeyAction::Sort),
+            ('b', KeyAction::Breakdown),
+            ('P', KeyAction::Private),
+            ('t', KeyAction::Save),
+            ('y', KeyAction::CopyTime),
+            ('Y', KeyAction::CopySplit),
+            ('w', KeyAction::CopySummary),
+            ('q', KeyAction::Quit),
+        ];
+        let mut bindings: Vec<(Vec<KeyCode>, KeyAction)> = single
+            .iter()
+            .map(|&(c, action)| (vec![KeyCode::Char(c)], action))
+            .collect();
+        bindings.push((vec![KeyCode::Up], KeyAction::SelectPrev));
+        bindings.push((vec![KeyCode::Down], KeyAction::SelectNext));
+        bindings.push((
+            vec![KeyCode::Char('g'), KeyCode::Char('n')],
+            KeyAction::NestedSubgoal,
+        ));
+        bindings.push((
+            vec![KeyCode::Char('s'), KeyCode::Char('s')],
+            KeyAction::Restart,
+        ));
+        Keymap {
+            bindings,
+            timeout: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Keys typed so far towards a sequence.
+#[derive(Default)]
+pub struct Chord {
+    /// Digits typed before any other key.
+    count: Option<usize>,
+    keys: Vec<KeyCode>,
+    /// When the latest key was pressed.
+    at: Option<Instant>,
+}
+
+impl Chord {
+    /// The pending keys as typed, for the status line.
+    pub fn pending(&self) -> Option<String> {
+        if self.keys.is_empty() {
+            return self.count.map(|n| n.to_string());
+        }
+        let names: Vec<String> = self.keys.iter().map(|&k| key_name(k)).collect();
+        Some(names.join(" "))
+    }
+}
+
+impl Keymap {
+    /// Bind `keys` to `action`, replacing the action's default bindings.
+    pub fn bind(&mut self, keys: Vec<KeyCode>, action: KeyAction) {
+        self.bindings.retain(|(k, a)| *a != action && *k != keys);
+        self.bindings.push((keys, action));
+    }
+
+    fn exact(&self, keys: &[KeyCode]) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(k, _)| k.as_slice() == keys)
+            .map(|&(_, action)| action)
+    }
+
+    fn extends(&self, keys: &[KeyCode]) -> bool {
+        self.bindings
+            .iter()
+            .any(|(k, _)| k.len() > keys.len() && k.starts_with(keys))
+    }
+
+    /// Add `key`, pressed at `at`, to the chord and return the actions that
+    /// are now decided, each with the time of the key that triggered it.
+    pub fn feed(&self, chord: &mut Chord, key: KeyCode, at: Instant) -> Vec<(KeyAction, Instant)> {
+        let started = chord.at.unwrap_or(at);
+        if chord.keys.is_empty() {
+            // unbound digits build up a count
+            if let Some(digit) = self.count_digit(chord, key) {
+                chord.count = Some(chord.count.unwrap_or(0).saturating_mul(10) + digit);
+                chord.at = Some(at);
+                return Vec::new();
+            }
+            if let Some(count) = chord.count.take() {
+                chord.at = None;
+                if self.exact(&[key]) == Some(KeyAction::StopActive) {
+                    return vec![(KeyAction::StopSplit(count), at)];
+                }
+                let mut actions = vec![(KeyAction::StopSplit(count), started)];
+                actions.extend(self.feed(chord, key, at));
+                return actions;
+            }
+        }
+        chord.keys.push(key);
+        chord.at = Some(at);
+        if self.extends(&chord.keys) {
+            return Vec::new();
+        }
+        let keys = std::mem::take(&mut chord.keys);
+        chord.at = None;
+        if let Some(action) = self.exact(&keys) {
+            return vec![(action, at)];
+        }
+        // a dead end: act on what was typed before, then start over with
+        // the new key
+        let mut actions = Vec::new();
+        if let Some((_, prefix)) = keys.split_last().filter(|(_, p)| !p.is_empty()) {
+            actions.extend(self.exact(prefix).map(|action| (action, started)));
+            actions.extend(self.feed(chord, key, at));
+        }
+        actions
+    }
+
+    fn count_digit(&self, chord: &Chord, key: KeyCode) -> Option<usize> {
+        let KeyCode::Char(c) = key else {
+            return None;
+        };
+        let digit = c.to_digit(10)? as usize;
+        if (digit == 0 && chord.count.is_none())
+            || self.exact(&[key]).is_some()
+            || self.extends(&[key])
+        {
+            return None;
+        }
+        Some(digit)
+    }
+
+    /// Once the timeout has passed, act on the pending keys on their own.
+    pub fn expire(&self, chord: &mut Chord, now: Instant) -> Option<(KeyAction, Instant)> {
+        let at = chord.at?;
+        if now.duration_since(at) < self.timeout {
+            return None;
+        }
+        if let Some(count) = chord.count.take() {
+            chord.at = None;
+            return Some((KeyAction::StopSplit(count), at));
+        }
+        let keys = std::mem::take(&mut chord.keys);
+        chord.at = None;
+        self.exact(&keys).map(|action| (action, at))
+    }
+}
+
+/// Parse a space-separated key sequence such as `g n`. Besides single
+/// characters, `up`, `down`, `left`, `right`, `esc`, `enter`, `tab` and
+/// `space` are understood.
+pub fn parse_sequence(spec: &str) -> Result<Vec<KeyCode>, String> {
+    let keys: Vec<KeyCode> = spec
+        .split_whitespace()
+        .map(|name| match name {
+            "up" => Ok(KeyCode::Up),
+            "down" => Ok(KeyCode::Down),
+            "left" => Ok(KeyCode::Left),
+            "right" => Ok(KeyCode::Right),
+            "esc" => Ok(KeyCode::Esc),
+            "enter" => Ok(KeyCode::Enter),
+            "tab" => Ok(KeyCode::Tab),
+            "space" => Ok(KeyCode::Char(' ')),
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(KeyCode::Char(c)),
+                    _ => Err(format!("unknown key '{}'", name)),
+                }
+            }
+        })
+        .collect::<Result<_, _>>()?;
+    if keys.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+    Ok(keys)
+}
+
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        other => format!("{:?}", other),
+    }
+}
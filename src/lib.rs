@@ -0,0 +1,13 @@
+//! The timer itself: splits, pauses and rollups, the journal they are kept
+//! in, and how durations are written and read. The `sw` program is built on
+//! this, and so are the C interface in [`ffi`], the WebAssembly module in
+//! `wasm` and the Python bindings under `python/`.
+
+pub mod duration;
+pub mod ffi;
+pub mod journal;
+pub mod json;
+pub mod stopwatch;
+// a native library exports the C interface in `ffi` instead
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
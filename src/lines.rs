@@ -0,0 +1,41 @@
+use std::{
+    io::{self, BufRead},
+    sync::mpsc,
+    thread,
+};
+
+use crate::pattern;
+use crate::Message;
+
+/// Read the lines piped into the stopwatch and report each one that matches
+/// `pattern`, named after what its first `*` matched, then the end of the
+/// input. Lines are matched with their surrounding whitespace trimmed. A
+/// read can't be interrupted, so the thread is left to end with the process
+/// rather than joined.
+pub fn watch(pattern: String, tx: mpsc::Sender<Message>) {
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match stdin.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    log::warn!("piped input: {}", e);
+                    break;
+                }
+            }
+            // build logs aren't always valid UTF-8
+            let line = String::from_utf8_lossy(&buf);
+            if let Some(name) = pattern::capture(&pattern, line.trim()) {
+                log::debug!("matched line '{}'", line.trim());
+                if tx.send(Message::Line(name.trim().to_string())).is_err() {
+                    return;
+                }
+            }
+        }
+        log::info!("piped input ended");
+        let _ = tx.send(Message::LinesEnded);
+    });
+}
@@ -0,0 +1,260 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    sync::OnceLock,
+};
+
+/// Interface language. Messages are looked up by their English text, as
+/// with gettext, so untranslated ones show in English.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Es,
+}
+
+impl Lang {
+    /// `es`, `es_ES.UTF-8` or `spanish`.
+    pub fn from_name(name: &str) -> Option<Lang> {
+        let code = name
+            .split(['_', '.', '-', '@'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match code.as_str() {
+            "en" | "english" | "c" | "posix" => Some(Lang::En),
+            "es" | "spanish" | "español" => Some(Lang::Es),
+            _ => None,
+        }
+    }
+
+    /// From `LC_ALL`, `LC_MESSAGES` or `LANG`, the first one set, as
+    /// gettext does.
+    pub fn from_env() -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Lang::from_name(&value))
+            .unwrap_or_default()
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Lang::En => &[],
+            Lang::Es => ES,
+        }
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Pick the language for the rest of the run; only the first call counts.
+pub fn set(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+/// `msg` in the current language.
+pub fn tr(msg: &'static str) -> &'static str {
+    let lang = LANG.get().copied().unwrap_or_default();
+    lang.catalog()
+        .iter()
+        .find(|&&(en, _)| en == msg)
+        .map_or(msg, |&(_, translated)| translated)
+}
+
+/// `msg` in the current language with each `{}` replaced by the next of
+/// `args`, in order.
+pub fn trf(msg: &'static str, args: &[&dyn Display]) -> String {
+    trf_display(msg, args).to_string()
+}
+
+/// `trf`, written out where it is used rather than built up first.
+pub fn trf_display<'a>(msg: &'static str, args: &'a [&'a dyn Display]) -> Translated<'a> {
+    Translated { msg, args }
+}
+
+pub struct Translated<'a> {
+    msg: &'static str,
+    args: &'a [&'a dyn Display],
+}
+
+impl Display for Translated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut args = self.args.iter();
+        let mut parts = tr(self.msg).split("{}");
+        f.write_str(parts.next().unwrap_or(""))?;
+        for part in parts {
+            if let Some(arg) = args.next() {
+                arg.fmt(f)?;
+            }
+            f.write_str(part)?;
+        }
+        Ok(())
+    }
+}
+
+const ES: &[(&str, &str)] = &[
+    // window title
+    ("{} {} (paused)", "{} {} (en pausa)"),
+    ("{} stopwatch", "{} cronómetro"),
+    // screen
+    ("=== Stopwatch ===", "=== Cronómetro ==="),
+    (" ({} goals done)", " ({} metas terminadas)"),
+    ("Goal  : {}", "Meta  : {}"),
+    ("Time  : {}", "Tiempo: {}"),
+    ("Lap   : {}", "Vuelta: {}"),
+    ("Time  : {}  ({} left of {})", "Tiempo: {}  (faltan {} de {})"),
+    ("Time  : {}  ({} over {})", "Tiempo: {}  ({} más de {})"),
+    ("({} left of {})", "(faltan {} de {})"),
+    ("({} over {})", "({} más de {})"),
+    ("Terminal too small", "Terminal demasiado pequeña"),
+    ("(none)", "(ninguna)"),
+    ("Error: {}", "Error: {}"),
+    ("Plan   :", "Plan    :"),
+    ("Next  : {} ({}/{}, {})", "Sigue : {} ({}/{}, {})"),
+    ("Phase : {} ({}/{}), {} left", "Fase  : {} ({}/{}), faltan {}"),
+    ("Phase : {} ({}/{}), {} over", "Fase  : {} ({}/{}), {} de más"),
+    ("Phase : all {} done", "Fase  : las {} terminadas"),
+    ("{} is over; now {} for {}", "{} terminó; ahora {} durante {}"),
+    ("{} is over; that was the last one", "{} terminó; era la última"),
+    ("{} has used its {} estimate", "{} ha agotado sus {} estimados"),
+    ("{} has run {} of {}", "{} lleva {} de {}"),
+    ("Subgoals ({}):", "Submetas ({}):"),
+    ("Open subgoals ({} of {}):", "Submetas abiertas ({} de {}):"),
+    ("Time by subgoal:", "Tiempo por submeta:"),
+    ("Split : {} ({})", "Parcial: {} ({})"),
+    ("Paused", "En pausa"),
+    ("(no subgoal)", "(sin submeta)"),
+    ("Longest finished subgoals ({} of {}):", "Submetas terminadas más largas ({} de {}):"),
+    (
+        "Controls: s=start/stop r=reset c=continue g=subgoal n=nested h=stop u=up d=redraw t=save-log q=quit",
+        "Teclas: s=iniciar/parar r=reiniciar c=continuar g=submeta n=anidada h=detener u=subir d=redibujar t=guardar q=salir",
+    ),
+    (
+        "          up/down=select 1-9=stop-split m=move <>=level J=merge x=cut p=project G=goal f=next-goal v=view o=open-only S=sort b=breakdown P=private y=copy-time Y=copy-split w=copy-summary a=quick-split e=name",
+        "        arriba/abajo=elegir 1-9=detener m=mover <>=nivel J=unir x=cortar p=proyecto G=meta f=siguiente v=vista o=abiertas S=ordenar b=reparto P=privado y=copiar-tiempo Y=copiar-parcial w=copiar-resumen a=parcial-rápido e=nombrar",
+    ),
+    ("Keys: {} ...", "Pulsado: {} ..."),
+    // one line with --inline
+    ("run", "act"),
+    ("({} splits)", "({} parciales)"),
+    ("Name split {}: {}_", "Nombre del parcial {}: {}_"),
+    ("Starting {} in {} (Esc to cancel)", "{} empieza en {} (Esc para cancelar)"),
+    (
+        "Starting {} at {}, in {} (Esc to cancel)",
+        "{} empieza a las {}, en {} (Esc para cancelar)",
+    ),
+    // prompts
+    ("Enter main goal: ", "Meta principal: "),
+    ("Enter subgoal name: ", "Nombre de la submeta: "),
+    ("Enter nested subgoal name: ", "Nombre de la submeta anidada: "),
+    (
+        "Move split {} under (split number, empty for top level): ",
+        "Mover el parcial {} bajo (número de parcial, vacío para el nivel superior): ",
+    ),
+    (
+        "Rename goal (now {}, empty to keep it): ",
+        "Renombrar la meta (ahora {}, vacío para mantenerla): ",
+    ),
+    (
+        "Switch project (now {}, empty for none): ",
+        "Cambiar de proyecto (ahora {}, vacío para ninguno): ",
+    ),
+    ("Next goal (empty to stop here): ", "Siguiente meta (vacío para terminar): "),
+    ("Cut split {} ({}) how far in (e.g. 10m): ", "Cortar el parcial {} ({}) a qué distancia (p. ej. 10m): "),
+    ("Name for the second part: ", "Nombre de la segunda parte: "),
+    // messages
+    ("Saved to {}", "Guardado en {}"),
+    ("Timing; press G to name the goal", "Cronometrando; pulsa G para nombrar la meta"),
+    ("Names hidden", "Nombres ocultos"),
+    ("Names shown", "Nombres visibles"),
+    ("{}, backed up to {}", "{}, con copia en {}"),
+    (
+        "{} conflicting copies of the log; merge them with sw reconcile",
+        "{} copias en conflicto del registro; combínalas con sw reconcile",
+    ),
+    ("Copied {} to clipboard ({})", "Copiado {} al portapapeles ({})"),
+    ("time", "el tiempo"),
+    ("split", "el parcial"),
+    ("summary", "el resumen"),
+    ("Recovered session from journal", "Sesión recuperada del diario"),
+    ("Start cancelled", "Inicio cancelado"),
+    ("The piped input ended", "La entrada por tubería terminó"),
+    ("{} so far", "{} hasta ahora"),
+    ("Window: {}", "Ventana: {}"),
+    ("Paused after {}; press c to carry on", "En pausa tras {}; pulsa c para seguir"),
+    ("No open split {}", "No hay un parcial abierto {}"),
+    ("No split selected (use the arrow keys)", "Ningún parcial elegido (use las flechas)"),
+    ("Can't move split {} under '{}'", "No se puede mover el parcial {} bajo '{}'"),
+    ("Split {} is already at the top level", "El parcial {} ya está en el nivel superior"),
+    ("Split {} has no earlier sibling to go under", "El parcial {} no tiene un hermano anterior bajo el que ir"),
+    ("Split {} has no earlier sibling to merge into", "El parcial {} no tiene un hermano anterior con el que unirse"),
+    ("'{}' isn't inside split {}", "'{}' no está dentro del parcial {}"),
+    ("Split {} is still running", "El parcial {} sigue en marcha"),
+    ("none", "ninguno"),
+    // exit summary
+    ("Goal   : {}", "Meta    : {}"),
+    ("Project: {}", "Proyecto: {}"),
+    ("Active : {}", "Activo  : {}"),
+    ("Paused : {}", "Pausado : {}"),
+    ("Splits : {}", "Parciales: {}"),
+    ("Longest:", "Más largos:"),
+    // sw follow
+    ("Looking for shared timers...", "Buscando cronómetros compartidos..."),
+    ("Following {} at {}", "Siguiendo {} en {}"),
+    ("{} at {}", "{} en {}"),
+    ("Follow which timer? [1-{}]: ", "¿Qué cronómetro seguir? [1-{}]: "),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// The string literal `code` starts with, unescaped.
+    fn literal(code: &str) -> Option<String> {
+        let mut chars = code.trim_start().strip_prefix('"')?.chars();
+        let mut text = String::new();
+        loop {
+            match chars.next()? {
+                '"' => return Some(text),
+                '\\' => match chars.next()? {
+                    'n' => text.push('\n'),
+                    't' => text.push('\t'),
+                    c => text.push(c),
+                },
+                c => text.push(c),
+            }
+        }
+    }
+
+    #[test]
+    fn every_message_has_a_translation() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+        let mut missing = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let code = fs::read_to_string(&path).unwrap();
+            for call in ["tr(", "trf(", "trf_display("] {
+                for (at, _) in code.match_indices(call) {
+                    // `str(`, or the name in a string
+                    if code[..at].ends_with(|c: char| c.is_alphanumeric() || "_\"".contains(c)) {
+                        continue;
+                    }
+                    let Some(msg) = literal(&code[at + call.len()..]) else {
+                        continue;
+                    };
+                    if !ES.iter().any(|&(en, _)| en == msg) {
+                        missing.push(format!("{}: {:?}", path.display(), msg));
+                    }
+                }
+            }
+        }
+        assert!(
+            missing.is_empty(),
+            "no Spanish for:\n{}",
+            missing.join("\n")
+        );
+    }
+}
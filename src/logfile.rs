@@ -0,0 +1,102 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::crypt::{self, Crypt};
+
+/// Add `text` to the end of the log at `path`; see `update`. With
+/// encryption on it goes in as a block of its own, so what is already
+/// there needn't be decrypted.
+pub fn append(path: &Path, text: &[u8], crypt: &Crypt) -> io::Result<()> {
+    let text = crypt.seal(text)?;
+    replace(path, |mut contents| {
+        refuse_plain(&contents, crypt)?;
+        contents.extend_from_slice(&text);
+        Ok(contents)
+    })
+}
+
+/// Replace the log at `path` with `edit` applied to it, without ever leaving
+/// it half-written. The new contents go to a temp file that is renamed over
+/// the log, and an advisory lock on `<path>.lock` keeps other instances from
+/// saving at the same time and losing each other's entries. An encrypted log
+/// is decrypted for `edit` and encrypted again as a whole.
+pub fn update(path: &Path, crypt: &Crypt, edit: impl FnOnce(Vec<u8>) -> Vec<u8>) -> io::Result<()> {
+    replace(path, |contents| {
+        refuse_plain(&contents, crypt)?;
+        crypt.seal(&edit(crypt.open(contents)?))
+    })
+}
+
+/// Keep an encrypted log from having plain text written to it by an
+/// instance without the recipients.
+fn refuse_plain(contents: &[u8], crypt: &Crypt) -> io::Result<()> {
+    if !crypt.enabled() && crypt::sealed(contents) {
+        return Err(io::Error::other(
+            "the log is encrypted; give the recipients with --encrypt-to to add to it",
+        ));
+    }
+    Ok(())
+}
+
+fn replace(path: &Path, edit: impl FnOnce(Vec<u8>) -> io::Result<Vec<u8>>) -> io::Result<()> {
+    let _lock = Lock::acquire(&sibling(path, ".lock"))?;
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    let contents = edit(contents)?;
+
+    let tmp = sibling(path, &format!(".{}.tmp", process::id()));
+    let written = write_synced(&tmp, &contents)
+        .and_then(|()| match fs::metadata(path) {
+            // keep the log's permissions
+            Ok(meta) => fs::set_permissions(&tmp, meta.permissions()),
+            Err(_) => Ok(()),
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    written
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// `path` with `suffix` added to the file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// An exclusive lock on the file, released when dropped.
+struct Lock {
+    file: File,
+}
+
+impl Lock {
+    fn acquire(path: &Path) -> io::Result<Lock> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        file.lock()?;
+        Ok(Lock { file })
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
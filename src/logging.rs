@@ -0,0 +1,90 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use chrono::Local;
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Diagnostics for bug reports, written to a file so they never disturb the
+/// terminal UI.
+struct FileLogger {
+    file: Mutex<File>,
+    level: LevelFilter,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{} {:5} [{}] {}",
+                Local::now().format("%H:%M:%S%.3f"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/stopwatch/debug.log`, falling back to `~/.local/state`
+/// (`%LOCALAPPDATA%` on Windows).
+pub fn default_path() -> PathBuf {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(crate::platform::state_home)
+        .unwrap_or_default()
+        .join("stopwatch")
+        .join("debug.log")
+}
+
+/// Map `-v` counts to a level: info, debug, then trace.
+pub fn level_for(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Install the file logger. Nothing is opened when logging is off.
+pub fn init(verbosity: u8, path: Option<PathBuf>) -> Result<(), String> {
+    let level = level_for(verbosity);
+    if level == LevelFilter::Off {
+        return Ok(());
+    }
+    let path = path.unwrap_or_else(default_path);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(file),
+        level,
+    }))
+    .map_err(|e| e.to_string())?;
+    log::set_max_level(level);
+    Ok(())
+}
@@ -1,385 +1,1848 @@
 use std::{
-    env,
-    fs::OpenOptions,
-    io::{self, Write},
-    sync::mpsc,
-    thread,
+    fs::{self, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
-use chrono::{DateTime, Local};
-use crossterm::cursor::MoveTo;
-use crossterm::event::{poll, read, Event, KeyCode};
+use chrono::{DateTime, Days, Local, NaiveDate, SecondsFormat};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use crossterm::style::Print;
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+use crossterm::terminal::SetTitle;
 use crossterm::ExecutableCommand;
+#[cfg(unix)]
+use signal_hook::consts::{SIGHUP, SIGTERM, SIGUSR1, SIGUSR2};
+#[cfg(unix)]
+use signal_hook::iterator::Signals;
 
-const MAX_SPLITS: usize = 100;
-const TICK_RATE_MS: u64 = 30;
+mod backup;
+mod billing;
+mod check;
+mod clipboard;
+mod compress;
+mod config;
+mod crypt;
+mod demo;
+mod error;
+mod export;
+mod git;
+mod hooks;
+mod http;
+mod import;
+mod inhibit;
+mod keys;
+mod lines;
+mod locale;
+mod logfile;
+mod logging;
+mod mdns;
+mod merge;
+#[cfg(feature = "midi")]
+mod midi;
+mod obs;
+mod options;
+mod orglog;
+mod outlier;
+mod pattern;
+mod platform;
+#[cfg(feature = "plugins")]
+mod plugin;
+mod reconcile;
+mod replay;
+mod report;
+mod script;
+mod sequence;
+mod share;
+mod state;
+mod stress;
+mod ui;
+mod watch;
+mod width;
+#[cfg(feature = "window")]
+mod window;
 
-struct Split {
-    name: String,
-    start_offset: Duration,
-    end_offset: Option<Duration>,
-    start_dt: DateTime<Local>,
-    end_dt: Option<DateTime<Local>>,
-    parent: Option<usize>,
-    level: usize,
-}
+use ::stopwatch::{duration, journal, json, stopwatch};
+use duration::{parse_duration, DurationFormat, Rounding};
+use error::{Error, Result};
+use journal::Journal;
+use keys::{Chord, KeyAction};
+use locale::{tr, trf};
+use obs::Overlay;
+use options::{parse_args, Command, Options};
+use sequence::Progress;
+use stopwatch::{Action, Clock, Goal, Stopwatch, MAX_SPLITS};
+use ui::{
+    draw_dynamic, exit_summary, next_event, plan_summary, prompt, redraw, restore_terminal,
+    session_summary, setup_terminal, split_line, title_text, Countdown, Naming, View, POP_TITLE,
+    PUSH_TITLE,
+};
+
+const TICK_RATE_MS: u64 = 30;
 
 enum Message {
     Tick,
-    Input(Event),
+    /// A terminal event and when it was read, so keys take effect when they
+    /// were pressed rather than when the loop gets to them.
+    Input(Event, Instant),
+    #[cfg(unix)]
+    Signal(i32),
+    Failed(Error),
+    /// A state change from the host being followed.
+    Remote(journal::Record),
+    /// A button pressed remotely, over HTTP, MIDI or with `sw press`.
+    Press(Press),
+    /// A commit was made in the `--git-splits` repository; its subject.
+    Commit(String),
+    /// A piped line matched `--split-on-line`; the name it gives.
+    Line(String),
+    /// The piped input ended.
+    LinesEnded,
+    /// A `--watch` path appeared, or went away when false.
+    Watched(PathBuf, bool),
+    #[cfg(feature = "plugins")]
+    Plugin(plugin::Request),
+    /// Another window got the focus; its title.
+    #[cfg(feature = "window")]
+    Focus(String),
+    /// Move the simulated clock on, for `--input-script`.
+    Advance(Duration),
 }
 
-fn format_time(dur: Duration) -> String {
-    let ms = dur.as_millis() % 1000;
-    let secs = dur.as_secs();
-    let s = secs % 60;
-    let m = (secs / 60) % 60;
-    let h = secs / 3600;
-    format!("{:02}:{:02}:{:02}.{:03}", h, m, s, ms)
+/// A remote button, e.g. a Stream Deck key or a foot pedal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Press {
+    /// End the active split and start the next one.
+    Split,
+    /// Start, pause or resume.
+    Toggle,
 }
 
-fn clear_screen<W: Write>(out: &mut W) -> io::Result<()> {
-    out.execute(Clear(ClearType::All))?;
-    out.execute(MoveTo(0, 0))?;
-    Ok(())
+impl Press {
+    pub fn from_name(name: &str) -> Option<Press> {
+        match name {
+            "split" => Some(Press::Split),
+            "toggle" => Some(Press::Toggle),
+            _ => None,
+        }
+    }
 }
 
-fn draw_static<W: Write>(
+/// Exit status used when the session is interrupted with Ctrl-C (128 + SIGINT).
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// The goal timed when nobody has named one yet.
+const UNTITLED: &str = "Untitled";
+
+/// Where `--simulate` sets the wall clock, the same on every run.
+fn simulation_start() -> DateTime<Local> {
+    let start = NaiveDate::from_ymd_opt(2000, 1, 3)
+        .and_then(|date| date.and_hms_opt(9, 0, 0))
+        .unwrap_or_default();
+    start
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or_else(Local::now)
+}
+
+/// Copy `text` and report the outcome on the status line.
+fn copy_to_clipboard<W: Write>(
     out: &mut W,
-    main_goal: &Option<String>,
-    total: Duration,
-    splits: &[Split],
-) -> io::Result<()> {
-    clear_screen(out)?;
-    out.execute(Print("=== Stopwatch ==="))?;
-    out.execute(MoveTo(0, 1))?;
-    out.execute(Print(format!(
-        "Goal  : {}",
-        main_goal.as_deref().unwrap_or("(none)")
-    )))?;
-    out.execute(MoveTo(0, 2))?;
-    out.execute(Print(format!("Time  : {}", format_time(total))))?;
-    out.execute(MoveTo(0, 3))?;
-    out.execute(Print(format!("Subgoals ({}):", splits.len())))?;
-    out.execute(MoveTo(0, 4))?;
-    for (i, split) in splits.iter().enumerate() {
-        let indent = (split.level * 2) as u16;
-        out.execute(MoveTo(indent, 4 + i as u16))?;
-        let start_str = format_time(split.start_offset);
-        if let Some(end_off) = split.end_offset {
-            let dur = end_off.checked_sub(split.start_offset).unwrap_or_default();
-            out.execute(Print(format!(
-                "{:2}) {} -> {} = {} {}",
-                i + 1,
-                start_str,
-                format_time(end_off),
-                format_time(dur),
-                split.name
-            )))?;
-        } else {
-            out.execute(Print(format!(
-                "{:2}) {} -> --:--:--.--- = --:--:--.--- {}",
-                i + 1,
-                start_str,
-                split.name
-            )))?;
+    opts: &Options,
+    view: &mut View,
+    text: &str,
+    what: &'static str,
+) {
+    match clipboard::copy(out, text, opts.clipboard) {
+        Ok(tool) => view.message = Some(trf("Copied {} to clipboard ({})", &[&tr(what), &tool])),
+        Err(e) => view.show_error(Error::Clipboard(e)),
+    }
+}
+
+/// Everything that hears about state changes besides the stopwatch itself.
+#[derive(Default)]
+struct Sinks {
+    journal: Option<Journal>,
+    share: Option<share::Host>,
+    hooks: hooks::Hooks,
+    state: Option<PathBuf>,
+    inhibit: Option<inhibit::Inhibitor>,
+    /// When the latest action took effect; none is put before it.
+    last_at: Option<Instant>,
+    #[cfg(feature = "plugins")]
+    plugins: Vec<plugin::Plugin>,
+}
+
+impl Sinks {
+    /// Recover the stopwatch from the journal, if one is configured, and open
+    /// the journal and share server.
+    fn open(opts: &Options) -> Result<(Stopwatch, Sinks)> {
+        let mut sinks = Sinks {
+            hooks: opts.hooks.clone(),
+            state: opts.state_file.clone(),
+            inhibit: opts.inhibit_sleep.then(inhibit::Inhibitor::default),
+            ..Sinks::default()
+        };
+        let mut records = Vec::new();
+        if let Some(path) = &opts.journal {
+            let err = |source| Error::Journal {
+                path: path.clone(),
+                source,
+            };
+            records = journal::read(path).map_err(err)?;
+            sinks.journal = Some(Journal::open(path).map_err(err)?);
         }
+        let sw = journal::replay(&records);
+        if sw.is_some() {
+            log::info!("recovered session from journal");
+        }
+        if let Some(addr) = &opts.share {
+            let host = share::Host::bind(addr, journal::pending(&records)).map_err(|source| {
+                Error::Remote {
+                    addr: addr.clone(),
+                    source,
+                }
+            })?;
+            sinks.share = Some(host);
+        }
+        Ok((sw.unwrap_or_default(), sinks))
     }
 
-    let controls_line_row = 4 + splits.len() as u16 + 1;
-    out.execute(MoveTo(0, controls_line_row));
-    out.execute(Print("\nControls: s=start/stop r=reset c=continue g=subgoal n=nested h=stop u=up d=redraw t=save-log q=quit\n"))?;
-    out.flush()?;
-    Ok(())
+    /// Pass `event` on, stamped with `at`.
+    fn record(&mut self, event: journal::Event, at: DateTime<Local>) -> Result<()> {
+        let record = journal::Record { at, event };
+        if let Some(host) = &self.share {
+            host.publish(&record);
+        }
+        #[cfg(feature = "plugins")]
+        self.plugins
+            .retain_mut(|plugin| match plugin.send(&record) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("plugin '{}' stopped: {}", plugin.command, e);
+                    false
+                }
+            });
+        if let (Some(path), journal::Event::Quit) = (&self.state, &record.event) {
+            state::clear(path);
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.append(&record).map_err(|source| Error::Journal {
+                path: journal.path.clone(),
+                source,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Keep the state file and sleep lock in step with `sw`. Failing to is
+    /// only logged, since nothing in the session depends on them.
+    fn update_state(&mut self, sw: &Stopwatch) {
+        if let Some(path) = &self.state {
+            if let Err(e) = state::write(path, &state::State::of(sw)) {
+                log::warn!("{}: {}", path.display(), e);
+            }
+        }
+        if let Some(inhibit) = &mut self.inhibit {
+            inhibit.set(sw.running);
+        }
+    }
 }
 
-fn draw_dynamic<W: Write>(
-    out: &mut W,
-    start_time: Instant,
-    elapsed: Duration,
-    splits: &[Split],
-    main_goal: &Option<String>,
-) -> io::Result<()> {
-    let now = Instant::now();
-    let total = elapsed + now.duration_since(start_time);
-
-    // redraw goal and time
-    out.execute(MoveTo(0, 1))?;
-    out.execute(Print(format!(
-        "Goal  : {}   ",
-        main_goal.as_deref().unwrap_or("(none)")
-    )))?;
-    let cur_time = format_time(total);
-    out.execute(MoveTo(0, 2))?;
-    out.execute(Print(format!("Time  : {}   ", cur_time)))?;
-
-    // redraw running subgoals
-    for (i, split) in splits.iter().enumerate() {
-        if split.end_offset.is_none() {
-            let rel = total.checked_sub(split.start_offset).unwrap_or_default();
-            let row = 4 + i as u16;
-            let indent = (split.level * 2) as u16;
-            let start_str = format_time(split.start_offset);
-            out.execute(MoveTo(indent, row))?;
-            out.execute(Print(format!(
-                "{:2}) {} -> {} = {} {}\n",
-                i + 1,
-                start_str,
-                format_time(total),
-                format_time(rel),
-                split.name
-            )))?;
-        }
-    }
-    out.flush()?;
+/// Apply `action` to the stopwatch and pass it on. A journal that can't be
+/// written is reported but doesn't stop the clock.
+fn act(sw: &mut Stopwatch, sinks: &mut Sinks, view: &mut View, action: Action) {
+    let events = sinks.hooks.triggered(&action, sw);
+    sw.apply(&action);
+    sinks.last_at = Some(sw.clock.now());
+    sinks.update_state(sw);
+    for (event, split) in events {
+        sinks.hooks.run(event, sw, split, sw.clock.wall(), &[]);
+    }
+    if let Err(e) = sinks.record(journal::Event::Action(action), sw.clock.wall()) {
+        view.show_error(e);
+    }
+}
+
+/// Like `act`, for an action that happened at `at` rather than now, such as
+/// a key press from when it was read. Actions keep the order they were
+/// handled in: one captured before the latest is taken to be simultaneous
+/// with it.
+fn act_at(sw: &mut Stopwatch, sinks: &mut Sinks, view: &mut View, action: Action, at: Instant) {
+    let at = sinks.last_at.map_or(at, |last| at.max(last));
+    sinks.last_at = Some(at);
+    // read the monotonic clock first so the wall time doesn't come out a
+    // hair early, as it would at a scheduled start
+    let since = sw.clock.now().saturating_duration_since(at);
+    let wall = sw.clock.wall() - since;
+    let events = sinks.hooks.triggered(&action, sw);
+    sw.apply_at(&action, at, wall);
+    sinks.update_state(sw);
+    for (event, split) in events {
+        sinks.hooks.run(event, sw, split, wall, &[]);
+    }
+    if let Err(e) = sinks.record(journal::Event::Action(action), wall) {
+        view.show_error(e);
+    }
+}
+
+/// Handle a remote button press. Nobody is at the keyboard to type names, so
+/// numbered defaults are used.
+fn press(opts: &Options, sw: &mut Stopwatch, sinks: &mut Sinks, view: &mut View, button: Press) {
+    match button {
+        Press::Toggle if sw.running => act(sw, sinks, view, Action::Pause),
+        Press::Toggle if sw.main_goal.is_some() => act(sw, sinks, view, Action::Resume),
+        Press::Toggle => {
+            let goal = UNTITLED.to_string();
+            let project = opts.project.clone();
+            act(sw, sinks, view, Action::Start { goal, project });
+        }
+        Press::Split if sw.running && sw.splits.len() < MAX_SPLITS => {
+            if sw.active.is_some() {
+                act(sw, sinks, view, Action::StopActive);
+            }
+            let name = format!("Split {}", sw.splits.len() + 1);
+            let parent = sw.active;
+            act(sw, sinks, view, Action::Split { name, parent });
+        }
+        Press::Split => {}
+    }
+}
+
+/// End the active split and start a sibling named `subject`, e.g. after the
+/// commit just made, so the time that follows is tied to it. Ignored while
+/// stopped.
+fn split_named(sw: &mut Stopwatch, sinks: &mut Sinks, view: &mut View, subject: String) {
+    if !sw.running || sw.splits.len() >= MAX_SPLITS {
+        return;
+    }
+    if sw.active.is_some() {
+        act(sw, sinks, view, Action::StopActive);
+    }
+    let name = if subject.is_empty() {
+        format!("Split {}", sw.splits.len() + 1)
+    } else {
+        subject
+    };
+    let parent = sw.active;
+    act(sw, sinks, view, Action::Split { name, parent });
+}
+
+/// Split or take note as another window gets the focus, while the timer
+/// runs; what was done while it was paused needn't be told apart. Returns
+/// whether anything changed.
+#[cfg(feature = "window")]
+fn focus_moved(
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+    title: String,
+) -> bool {
+    if !sw.running {
+        return false;
+    }
+    match opts.focus {
+        Some(window::Follow::Split) => split_named(sw, sinks, view, title),
+        Some(window::Follow::Note) => {
+            sinks.hooks.run(
+                hooks::Event::Focus,
+                sw,
+                sw.active,
+                sw.clock.wall(),
+                &[("SW_WINDOW", title.clone())],
+            );
+            view.message = Some(trf("Window: {}", &[&view.name(&title)]));
+        }
+        None => return false,
+    }
+    true
+}
+
+/// Start a top-level split named after a `--watch` path that just
+/// appeared, starting the clock first if nothing is being timed. It runs
+/// until the path goes away.
+fn watched_appeared(
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+    path: PathBuf,
+) {
+    if sw.main_goal.is_none() {
+        let goal = UNTITLED.to_string();
+        let project = opts.project.clone();
+        act(sw, sinks, view, Action::Start { goal, project });
+    }
+    if !sw.running || sw.splits.len() >= MAX_SPLITS {
+        return;
+    }
+    let name = path.file_name().unwrap_or(path.as_os_str());
+    let name = name.to_string_lossy().into_owned();
+    act(sw, sinks, view, Action::Split { name, parent: None });
+    view.watched.push((path, sw.splits.len() - 1));
+}
+
+/// Set up `--start-at`: the goal, asked for now unless a routine names it,
+/// starts by itself when the time of day next comes round.
+fn arm<W: Write>(stdout: &mut W, opts: &Options, sw: &Stopwatch, view: &mut View) -> Result<()> {
+    let Some(time) = opts.start_at else {
+        return Ok(());
+    };
+    if sw.main_goal.is_some() {
+        // a recovered session is already under way
+        return Ok(());
+    }
+    let now = sw.clock.wall();
+    // a time skipped by a DST change doesn't exist that day; try the next
+    let scheduled = (0..=2)
+        .filter_map(|days| {
+            let date = now.date_naive() + Days::new(days);
+            date.and_time(time).and_local_timezone(Local).earliest()
+        })
+        .find(|&wall| wall > now)
+        .ok_or_else(|| Error::Usage(format!("can't schedule a start at {}", time)))?;
+    let routine = opts.sequence.as_ref();
+    let goal = match routine
+        .and_then(|seq| seq.goal.clone())
+        .or_else(|| opts.goal.clone())
+    {
+        Some(goal) => goal,
+        None => prompt(stdout, opts, tr("Enter main goal: ")).map_err(Error::Terminal)?,
+    };
+    let project = routine
+        .and_then(|seq| seq.project.clone())
+        .or_else(|| opts.project.clone());
+    // the prompt may have taken a while
+    let instant = sw.clock.now();
+    let wait = (scheduled - sw.clock.wall()).to_std().unwrap_or_default();
+    log::info!("start of '{}' scheduled for {}", goal, scheduled);
+    view.countdown = Some(Countdown {
+        goal,
+        project,
+        at: instant + wait,
+        scheduled: Some(scheduled),
+        shown: None,
+    });
     Ok(())
 }
 
-fn save_log(
-    main_goal: &str,
-    start_instant: Instant,
-    splits: &[Split],
+/// Start `goal`, or with `--lead-in` count down to it first.
+fn begin(
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+    goal: String,
+    project: Option<String>,
+) {
+    match opts.lead_in {
+        Some(lead_in) if !lead_in.is_zero() => {
+            view.countdown = Some(Countdown {
+                goal,
+                project,
+                at: sw.clock.now() + lead_in,
+                scheduled: None,
+                shown: None,
+            });
+        }
+        _ => act(sw, sinks, view, Action::Start { goal, project }),
+    }
+}
+
+/// Count down to the goal, beeping for the last three seconds, and start it
+/// on the dot rather than at this tick. Returns whether anything changed.
+fn count_down<W: Write>(
+    stdout: &mut W,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+) -> Result<bool> {
+    let Some(countdown) = &mut view.countdown else {
+        return Ok(false);
+    };
+    let now = sw.clock.now();
+    if now < countdown.at {
+        let left = (countdown.at - now).as_millis().div_ceil(1000);
+        if countdown.shown == Some(left) {
+            return Ok(false);
+        }
+        countdown.shown = Some(left);
+        if left <= 3 {
+            stdout.execute(Print('\x07')).map_err(Error::Terminal)?;
+        }
+        return Ok(true);
+    }
+    if let Some(Countdown {
+        goal, project, at, ..
+    }) = view.countdown.take()
+    {
+        act_at(sw, sinks, view, Action::Start { goal, project }, at);
+        stdout.execute(Print('\x07')).map_err(Error::Terminal)?;
+    }
+    Ok(true)
+}
+
+/// Ring the bell, show a banner and run the milestone hooks as the goal's
+/// time passes each `--alert` mark. The timer carries on. Returns whether a
+/// mark was passed.
+fn ring_alerts<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+) -> Result<bool> {
+    let total = sw.total();
+    let passed = opts.alerts.iter().filter(|&&mark| mark <= total).count();
+    if passed <= view.passed {
+        // fewer after a reset or a new goal
+        view.passed = passed;
+        return Ok(false);
+    }
+    for &mark in &opts.alerts[view.passed..passed] {
+        // in seconds, like SW_ELAPSED
+        let secs = mark.as_secs().to_string();
+        sinks.hooks.run(
+            hooks::Event::Milestone,
+            sw,
+            sw.active,
+            sw.clock.wall(),
+            &[("SW_MILESTONE", secs)],
+        );
+        let mark = opts.format.format(mark);
+        log::info!("passed the {} mark", mark);
+        view.message = Some(trf("{} so far", &[&mark]));
+    }
+    view.passed = passed;
+    stdout.execute(Print('\x07')).map_err(Error::Terminal)?;
+    Ok(true)
+}
+
+/// Pause the goal the moment it reaches `--max-duration`, once; resuming
+/// carries on past it. Returns whether it paused.
+fn enforce_cap<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+) -> Result<bool> {
+    let Some(max) = opts.max_duration else {
+        return Ok(false);
+    };
+    let total = sw.total();
+    if total < max {
+        // a new goal, or the old one reset
+        view.capped = false;
+        return Ok(false);
+    }
+    if !sw.running || view.capped {
+        return Ok(false);
+    }
+    let now = sw.clock.now();
+    let at = now.checked_sub(total - max).unwrap_or(now);
+    act_at(sw, sinks, view, Action::Pause, at);
+    view.capped = true;
+    let max = opts.format.format(max);
+    log::warn!("paused at the {} cap", max);
+    view.message = Some(trf("Paused after {}; press c to carry on", &[&max]));
+    stdout.execute(Print('\x07')).map_err(Error::Terminal)?;
+    Ok(true)
+}
+
+/// Move `--sequence` along: its first phase starts once the timer runs, and
+/// each next one when the phase before has run its length or was stopped by
+/// hand. The terminal bell marks each change and each alert of a routine
+/// step. Returns whether anything changed.
+fn advance_sequence<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+) -> Result<bool> {
+    let Some(seq) = &opts.sequence else {
+        return Ok(false);
+    };
+    // when a phase runs out, the next one starts where it ended rather than
+    // at this tick
+    let mut at = sw.clock.now();
+    let next = match view.progress {
+        Progress::Ready if sw.running && sw.splits.len() < MAX_SPLITS => 0,
+        Progress::At { phase, split, rung } => {
+            let current = &seq.phases[phase];
+            // a reset, cut or merge took the phase's split away; start over
+            let Some(timed) = sw.splits.get(split).filter(|s| s.name == current.name) else {
+                view.progress = Progress::Ready;
+                return Ok(true);
+            };
+            let mut ran = timed.length(sw.total());
+            if timed.end_offset.is_none() {
+                let due = current.alerts[rung..].iter().filter(|&&a| ran >= a).count();
+                if due > 0 {
+                    let alert = current.alerts[rung + due - 1];
+                    view.message = Some(if alert == current.length {
+                        trf(
+                            "{} has used its {} estimate",
+                            &[&view.name(&current.name), &opts.format.format(alert)],
+                        )
+                    } else {
+                        trf(
+                            "{} has run {} of {}",
+                            &[
+                                &view.name(&current.name),
+                                &opts.format.format(alert),
+                                &opts.format.format(current.length),
+                            ],
+                        )
+                    });
+                    stdout.execute(Print('\x07')).map_err(Error::Terminal)?;
+                    view.progress = Progress::At {
+                        phase,
+                        split,
+                        rung: rung + due,
+                    };
+                    return Ok(true);
+                }
+                if !current.stop || ran < current.length {
+                    return Ok(false);
+                }
+                at = at.checked_sub(ran - current.length).unwrap_or(at);
+                act_at(sw, sinks, view, Action::Stop { index: split }, at);
+                ran = current.length;
+            }
+            log::info!(
+                "phase '{}' ran {} of {}",
+                current.name,
+                opts.format.format(ran),
+                opts.format.format(current.length)
+            );
+            stdout.execute(Print('\x07')).map_err(Error::Terminal)?;
+            phase + 1
+        }
+        _ => return Ok(false),
+    };
+    let finished = next.checked_sub(1).map(|i| seq.phases[i].name.as_str());
+    match seq.phases.get(next) {
+        Some(phase) if sw.splits.len() < MAX_SPLITS => {
+            let name = phase.name.clone();
+            act_at(sw, sinks, view, Action::Split { name, parent: None }, at);
+            view.progress = Progress::At {
+                phase: next,
+                split: sw.splits.len() - 1,
+                rung: 0,
+            };
+            if let Some(finished) = finished {
+                let length = opts.format.format(phase.length);
+                view.message = Some(trf(
+                    "{} is over; now {} for {}",
+                    &[&view.name(finished), &view.name(&phase.name), &length],
+                ));
+            }
+        }
+        _ => {
+            view.progress = Progress::Done;
+            if let Some(finished) = finished {
+                view.message = Some(trf(
+                    "{} is over; that was the last one",
+                    &[&view.name(finished)],
+                ));
+            }
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(feature = "plugins")]
+fn plugin_request(
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+    request: plugin::Request,
+) {
+    match request {
+        plugin::Request::Rename { index, name } => {
+            match index.or(sw.active).filter(|&i| i < sw.splits.len()) {
+                Some(index) => act(sw, sinks, view, Action::Rename { index, name }),
+                None => log::warn!("plugin asked to rename a split that isn't there"),
+            }
+        }
+        plugin::Request::Alert(text) => view.message = Some(text),
+        plugin::Request::Press(button) => press(opts, sw, sinks, view, button),
+    }
+}
+
+/// Append the session to the log, reporting failures on the error banner.
+fn save_session(opts: &Options, sw: &Stopwatch, view: &mut View) {
+    if sw.main_goal.is_none() && sw.finished.is_empty() {
+        return;
+    }
+    match save_log(sw, opts) {
+        Ok(place) => {
+            log::info!("saved session to {}", place);
+            view.message = Some(trf("Saved to {}", &[&place]));
+        }
+        Err(e) => view.show_error(e),
+    }
+}
+
+/// Write every goal of the run, the finished ones first: appended to the
+/// log and/or each to its own file in the archive directory, and then
+/// copied to the backup. Returns where they went.
+fn save_log(sw: &Stopwatch, opts: &Options) -> Result<String> {
+    // as `finish` would write it, without closing anything
+    let current = sw.main_goal.as_ref().map(|name| Goal {
+        name: name.clone(),
+        project: sw.project.clone(),
+        start_dt: sw.goal_start,
+        end_dt: sw.clock.wall(),
+        total: sw.total(),
+        splits: sw.splits.clone(),
+    });
+    let goals: Vec<&Goal> = sw.finished.iter().chain(current.as_ref()).collect();
+    let mut places = Vec::new();
+    // the files written, for the backup
+    let mut saved: Vec<PathBuf> = Vec::new();
+    if !opts.archive_only {
+        append_goals(&opts.log_file, &goals, opts).map_err(|source| Error::Save {
+            path: opts.log_file.clone(),
+            source,
+        })?;
+        places.push(opts.log_file.clone());
+        saved.push(opts.log_file.clone().into());
+    }
+    if let Some(dir) = &opts.archive_dir {
+        for goal in &goals {
+            saved.push(archive_goal(dir, goal, opts).map_err(|source| Error::Save {
+                path: dir.display().to_string(),
+                source,
+            })?);
+        }
+        places.push(format!("{}/", dir.display()));
+        if let Some(days) = opts.compress_after {
+            // the sessions are saved either way, so this needn't fail the save
+            if let Err(e) = compress::compress_old(dir, opts.compression, days) {
+                log::warn!("compressing {}: {}", dir.display(), e);
+            }
+        }
+    }
+    if let Some(path) = &opts.jsonl_log {
+        let text: String = goals.iter().map(|goal| goal_json(goal, opts)).collect();
+        logfile::append(Path::new(path), text.as_bytes(), &opts.crypt).map_err(|source| {
+            Error::Save {
+                path: path.clone(),
+                source,
+            }
+        })?;
+        places.push(path.clone());
+        saved.push(path.into());
+    }
+    let mut places = places.join(" and ");
+    opts.hooks.run(
+        hooks::Event::Save,
+        sw,
+        None,
+        sw.clock.wall(),
+        &[("SW_SAVED_TO", places.clone())],
+    );
+    if opts.git_commit {
+        let message = commit_message(&opts.commit_message, &goals, opts);
+        git::commit(Path::new(&opts.log_file), &message).map_err(|source| Error::Commit {
+            path: opts.log_file.clone(),
+            source,
+        })?;
+    }
+    if let Some(backup) = &opts.backup {
+        let files: Vec<&Path> = saved.iter().map(PathBuf::as_path).collect();
+        backup
+            .upload(&files, opts.s3_endpoint.as_deref())
+            .map_err(|source| Error::Backup {
+                url: backup.to_string(),
+                source,
+            })?;
+        places = trf("{}, backed up to {}", &[&places, &backup.to_string()]);
+    }
+    Ok(places)
+}
+
+/// `template` with `{goals}` (their names), `{project}`, `{total}` (their
+/// time together), `{date}` and `{host}` filled in for the goals saved.
+fn commit_message(template: &str, goals: &[&Goal], opts: &Options) -> String {
+    let names: Vec<&str> = goals.iter().map(|g| g.name.as_str()).collect();
+    let mut projects: Vec<&str> = goals.iter().filter_map(|g| g.project.as_deref()).collect();
+    projects.dedup();
+    let total: Duration = goals.iter().map(|g| opts.rounding.apply(g.total)).sum();
+    let date = goals
+        .first()
+        .map_or_else(Local::now, |g| g.start_dt)
+        .format("%Y-%m-%d")
+        .to_string();
+    template
+        .replace("{goals}", &names.join(", "))
+        .replace("{project}", &projects.join(", "))
+        .replace("{total}", &opts.format.format(total))
+        .replace("{date}", &date)
+        .replace("{host}", &platform::hostname().unwrap_or_default())
+}
+
+fn append_goals(log_file: &str, goals: &[&Goal], opts: &Options) -> io::Result<()> {
+    let headings: Vec<Vec<orglog::Heading>> = goals
+        .iter()
+        .map(|goal| goal_headings(goal, opts.log_parent.len() + 1, &opts.format, opts.rounding))
+        .collect();
+    append_headings(log_file, &headings, opts)
+}
+
+/// Add each goal's headings to the log, under the `--under` path if any
+/// (their stars must already count it): as new headings, or with
+/// `--merge-headings` as extra CLOCK lines under headings that are already
+/// there.
+pub fn append_headings(
     log_file: &str,
+    headings: &[Vec<orglog::Heading>],
+    opts: &Options,
+) -> io::Result<()> {
+    if !opts.merge_headings && opts.log_parent.is_empty() {
+        let text: String = headings.iter().map(|h| orglog::render(h)).collect();
+        return logfile::append(Path::new(log_file), text.as_bytes(), &opts.crypt);
+    }
+    logfile::update(Path::new(log_file), &opts.crypt, |old| {
+        add_headings(String::from_utf8_lossy(&old).into_owned(), headings, opts).into_bytes()
+    })
+}
+
+/// `text` with each goal's headings added as `append_headings` would.
+pub fn add_headings(mut text: String, headings: &[Vec<orglog::Heading>], opts: &Options) -> String {
+    for goal in headings {
+        text = if !opts.merge_headings && opts.log_parent.is_empty() {
+            text + &orglog::render(goal)
+        } else {
+            orglog::insert(&text, &opts.log_parent, goal, opts.merge_headings)
+        };
+    }
+    text
+}
+
+/// Write `goal` to a new file in `dir` named after its start time and name,
+/// e.g. `2024-01-31_100000-write-report.org`, or `.org.age` when encrypted.
+/// Returns the file's path.
+fn archive_goal(dir: &Path, goal: &Goal, opts: &Options) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let mut text = Vec::new();
+    write_goal(&mut text, goal, &opts.format, opts.rounding)?;
+    let text = opts.crypt.seal(&text)?;
+    let ext = if opts.crypt.enabled() {
+        "org.age"
+    } else {
+        "org"
+    };
+    let stem = format!(
+        "{}-{}",
+        goal.start_dt.format("%Y-%m-%d_%H%M%S"),
+        slug(&goal.name)
+    );
+    let mut n = 1;
+    loop {
+        let name = match n {
+            1 => format!("{}.{}", stem, ext),
+            n => format!("{}-{}.{}", stem, n, ext),
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => return file.write_all(&text).map(|()| path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Lowercase words joined by dashes, for file names.
+fn slug(name: &str) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let slug: String = words.join("-").chars().take(40).collect();
+    if slug.is_empty() {
+        "session".to_string()
+    } else {
+        slug
+    }
+}
+
+fn write_goal(
+    file: &mut impl Write,
+    goal: &Goal,
+    fmt: &DurationFormat,
+    round: Rounding,
 ) -> io::Result<()> {
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_file)?;
-    let start_dt = Local::now() - (Instant::now() - start_instant);
-    let end_dt = Local::now();
-    let total = end_dt.signed_duration_since(start_dt);
-    let tot_str = format_time(Duration::from_millis(total.num_milliseconds() as u64));
-
-    writeln!(file, "* {}", main_goal)?;
-    writeln!(file, "  :LOGBOOK:")?;
-    writeln!(
-        file,
-        "  CLOCK: [{}]--[{}] => {}",
-        start_dt.format("%Y-%m-%d %H:%M"),
-        end_dt.format("%Y-%m-%d %H:%M"),
-        tot_str
-    )?;
-    writeln!(file, "  :END:\n")?;
-
-    for split in splits {
+    file.write_all(orglog::render(&goal_headings(goal, 1, fmt, round)).as_bytes())
+}
+
+/// The headings `goal` is saved as: the goal itself with `stars`, then each
+/// finished split one level deeper than its parent.
+fn goal_headings(
+    goal: &Goal,
+    stars: usize,
+    fmt: &DurationFormat,
+    round: Rounding,
+) -> Vec<orglog::Heading> {
+    let clock = |start: DateTime<Local>, end: DateTime<Local>, dur: Duration| {
+        format!(
+            "[{}]--[{}] => {}",
+            start.format("%Y-%m-%d %H:%M"),
+            end.format("%Y-%m-%d %H:%M"),
+            fmt.format(round.apply(dur))
+        )
+    };
+    let mut headings = vec![orglog::Heading {
+        stars,
+        title: goal.name.clone(),
+        project: goal.project.clone(),
+        clock: clock(goal.start_dt, goal.end_dt, goal.total),
+    }];
+    for split in &goal.splits {
         if let (Some(end_dt), Some(end_off)) = (split.end_dt, split.end_offset) {
-            let dur = end_off.checked_sub(split.start_offset).unwrap_or_default();
-            let stars = "*".repeat(split.level + 2);
-            writeln!(file, "{} {}", stars, split.name)?;
-            writeln!(file, "  :LOGBOOK:")?;
-            writeln!(
-                file,
-                "  CLOCK: [{}]--[{}] => {}",
-                split.start_dt.format("%Y-%m-%d %H:%M"),
-                end_dt.format("%Y-%m-%d %H:%M"),
-                format_time(dur)
-            )?;
-            writeln!(file, "  :END:\n")?;
+            let dur = split.length(end_off);
+            headings.push(orglog::Heading {
+                stars: stars + split.level + 1,
+                title: split.name.clone(),
+                project: split.project.clone(),
+                clock: clock(split.start_dt, end_dt, dur),
+            });
         }
     }
-    Ok(())
+    headings
 }
 
-fn main() -> crossterm::Result<()> {
-    let log_file = env::args().nth(1).unwrap_or_else(|| "done.org".to_string());
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
+/// `goal` as JSON lines for `--jsonl`: one `"type":"goal"` line, then one
+/// `"type":"split"` line per finished split naming its goal and parent.
+fn goal_json(goal: &Goal, opts: &Options) -> String {
+    let stamp = |t: DateTime<Local>| t.to_rfc3339_opts(SecondsFormat::Secs, false);
+    let line = |kind: &str, name: &str, project: Option<&str>, start, end, dur: Duration| {
+        let dur = opts.rounding.apply(dur);
+        json::Object::new()
+            .str("type", kind)
+            .str("goal", &goal.name)
+            .str("name", name)
+            .opt_str("project", project)
+            .str("start", &stamp(start))
+            .str("end", &stamp(end))
+            .opt_num("seconds", Some(dur.as_secs()))
+            .str("duration", &opts.format.format(dur))
+    };
+    let mut out = line(
+        "goal",
+        &goal.name,
+        goal.project.as_deref(),
+        goal.start_dt,
+        goal.end_dt,
+        goal.total,
+    )
+    .opt_num("splits", Some(goal.splits.len() as u64))
+    .finish();
+    out.push('\n');
+    for split in &goal.splits {
+        if let (Some(end_dt), Some(end_off)) = (split.end_dt, split.end_offset) {
+            let dur = split.length(end_off);
+            let parent = split.parent.map(|p| goal.splits[p].name.as_str());
+            let project = split.project.as_deref().or(goal.project.as_deref());
+            out.push_str(
+                &line("split", &split.name, project, split.start_dt, end_dt, dur)
+                    .opt_str("parent", parent)
+                    .opt_num("level", Some(split.level as u64))
+                    .finish(),
+            );
+            out.push('\n');
+        }
+    }
+    out
+}
 
-    let (tx, rx) = mpsc::channel::<Message>();
-    // ticker thread
-    {
-        let tx = tx.clone();
-        thread::spawn(move || loop {
-            thread::sleep(Duration::from_millis(TICK_RATE_MS));
-            if tx.send(Message::Tick).is_err() {
-                break;
-            }
-        });
+fn main() {
+    let opts = match parse_args() {
+        Ok(opts) => opts,
+        Err(e) => exit_with(Error::Usage(e)),
+    };
+    locale::set(opts.lang);
+    if let Err(e) = logging::init(opts.verbose, opts.debug_log.clone()) {
+        exit_with(Error::Usage(format!("debug log: {}", e)));
+    }
+    log::info!(
+        "starting: log_file={} inline={} title={} autosave={}",
+        opts.log_file,
+        opts.inline,
+        opts.title,
+        opts.autosave
+    );
+    // subcommands report their own failures as plain messages
+    let command =
+        |result: std::result::Result<(), String>| result.map(|()| 0).map_err(Error::Command);
+    let result = match &opts.command {
+        Command::Export(args) => command(export::run(&opts, args)),
+        Command::Report(args) => command(report::run(&opts, args)),
+        Command::Import(args) => command(import::run(&opts, args)),
+        Command::Status(args) => command(state::run(&opts, args)),
+        Command::Press(button) => command(state::press(&opts, *button)),
+        Command::Stress(args) => command(stress::run(&opts, args)),
+        Command::Check(args) => command(check::run(&opts, args)),
+        Command::Merge(args) => command(merge::run(&opts, args)),
+        Command::Reconcile(args) => command(reconcile::run(&opts, args)),
+        Command::Replay(args) => replay::run(&opts, args),
+        Command::Follow(addr) => share::follow(&opts, addr.as_deref()),
+        _ if opts.demo => demo::run(&opts),
+        _ => run(&opts),
+    };
+    match result {
+        Ok(0) => {}
+        Ok(code) => process::exit(code),
+        Err(e) => exit_with(e),
+    }
+}
+
+fn exit_with(e: Error) -> ! {
+    log::error!("{}", e);
+    eprintln!("sw: {}", e);
+    process::exit(e.exit_code());
+}
+
+/// Run the interactive stopwatch. The terminal is restored whether or not
+/// the event loop fails, and the exit status is returned.
+fn run(opts: &Options) -> Result<i32> {
+    let (mut sw, mut sinks) = Sinks::open(opts)?;
+    if opts.simulate {
+        sw.clock = Clock::simulated(simulation_start());
     }
-    // input thread
+    let mut view = View {
+        compact: opts.compact,
+        private: opts.private,
+        // a recovered session doesn't ring for the marks it already passed
+        passed: opts
+            .alerts
+            .iter()
+            .filter(|&&mark| mark <= sw.total())
+            .count(),
+        ..View::default()
+    };
+    if sw.main_goal.is_some() {
+        view.message = Some(tr("Recovered session from journal").to_string());
+        sinks.update_state(&sw);
+    } else if let Some(goal) = opts
+        .sequence
+        .as_ref()
+        .and_then(|seq| seq.goal.clone())
+        .filter(|_| opts.start_at.is_none())
     {
-        let tx = tx.clone();
-        thread::spawn(move || loop {
-            if poll(Duration::from_millis(TICK_RATE_MS)).unwrap_or(false) {
-                if let Ok(evt) = read() {
-                    if tx.send(Message::Input(evt)).is_err() {
+        // a routine starts straight away, or after the lead-in; its first
+        // step follows on the first tick
+        let project = opts.sequence.as_ref().and_then(|seq| seq.project.clone());
+        let project = project.or_else(|| opts.project.clone());
+        begin(opts, &mut sw, &mut sinks, &mut view, goal, project);
+    } else if let Some(goal) = opts.goal.clone().filter(|_| opts.start_at.is_none()) {
+        let project = opts.project.clone();
+        begin(opts, &mut sw, &mut sinks, &mut view, goal, project);
+    }
+    if view.message.is_none() && !opts.archive_only {
+        let copies = reconcile::conflicts(Path::new(&opts.log_file)).len();
+        if copies > 0 {
+            view.message = Some(trf(
+                "{} conflicting copies of the log; merge them with sw reconcile",
+                &[&copies],
+            ));
+        }
+    }
+    // frames are flushed whole, see `redraw`
+    let mut stdout = BufWriter::new(io::stdout());
+    setup_terminal(&mut stdout, opts).map_err(Error::Terminal)?;
+    // the legacy Windows console would print the title stack sequences
+    let title_stack = opts.title && platform::ansi();
+    if title_stack {
+        stdout.execute(Print(PUSH_TITLE)).map_err(Error::Terminal)?;
+    }
+    let result = arm(&mut stdout, opts, &sw, &mut view)
+        .and_then(|()| event_loop(&mut stdout, opts, &mut sw, &mut sinks, &mut view));
+    if title_stack {
+        stdout.execute(Print(POP_TITLE)).map_err(Error::Terminal)?;
+    }
+    restore_terminal(&mut stdout, opts).map_err(Error::Terminal)?;
+    let exit_code = result?;
+
+    log::info!("exiting with status {}", exit_code);
+    if !opts.quiet {
+        if let Some(summary) = exit_summary(&sw, &opts.format) {
+            print!("{}", summary);
+        }
+        if let Some(plan) = opts
+            .sequence
+            .as_ref()
+            .and_then(|seq| plan_summary(&sw, seq, &opts.format))
+        {
+            print!("{}", plan);
+        }
+    }
+    if exit_code != 0 && opts.autosave {
+        if sw.running {
+            // close open splits at the moment of interruption
+            act(&mut sw, &mut sinks, &mut view, Action::CloseOpen);
+        }
+        if sw.main_goal.is_some() || !sw.finished.is_empty() {
+            save_log(&sw, opts)?;
+        }
+    }
+    if let Err(e) = sinks.record(journal::Event::Quit, sw.clock.wall()) {
+        log::warn!("{}", e);
+    }
+    Ok(exit_code)
+}
+
+/// Background threads feeding the event loop. They all stop when the
+/// shutdown flag is set, so `stop` can join them before the terminal is
+/// restored.
+struct Workers {
+    shutdown: Arc<AtomicBool>,
+    #[cfg(unix)]
+    signals: signal_hook::iterator::Handle,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+    fn spawn(tx: mpsc::Sender<Message>, tick: Duration) -> Result<Workers> {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut handles = Vec::new();
+        // ticker thread
+        {
+            let tx = tx.clone();
+            let shutdown = shutdown.clone();
+            handles.push(thread::spawn(move || {
+                while !shutdown.load(Ordering::Relaxed) {
+                    thread::sleep(tick);
+                    if tx.send(Message::Tick).is_err() {
+                        break;
+                    }
+                }
+            }));
+        }
+        // input thread: polls with a timeout so it notices the shutdown flag
+        // instead of sitting in a read that would swallow the next keypress
+        {
+            let timeout = tick.min(Duration::from_millis(250));
+            let tx = tx.clone();
+            let shutdown = shutdown.clone();
+            handles.push(thread::spawn(move || {
+                while !shutdown.load(Ordering::Relaxed) {
+                    let msg = match next_event(timeout) {
+                        Ok(None) => continue,
+                        // Windows reports releases too; only presses act
+                        Ok(Some(Event::Key(key))) if key.kind == KeyEventKind::Release => continue,
+                        Ok(Some(evt)) => Message::Input(evt, Instant::now()),
+                        Err(e) => Message::Failed(Error::Input(e)),
+                    };
+                    let failed = matches!(msg, Message::Failed(_));
+                    if tx.send(msg).is_err() || failed {
+                        break;
+                    }
+                }
+            }));
+        }
+        // signal thread: SIGTERM/SIGHUP take the same shutdown path as
+        // Ctrl-C, and SIGUSR1/SIGUSR2 are the buttons `sw press` sends.
+        // Windows has none of these; Ctrl-C arrives as a key in raw mode.
+        #[cfg(unix)]
+        let signals = {
+            let mut signals =
+                Signals::new([SIGTERM, SIGHUP, SIGUSR1, SIGUSR2]).map_err(Error::Signals)?;
+            let handle = signals.handle();
+            handles.push(thread::spawn(move || {
+                for sig in signals.forever() {
+                    let msg = match sig {
+                        SIGUSR1 => Message::Press(Press::Toggle),
+                        SIGUSR2 => Message::Press(Press::Split),
+                        sig => Message::Signal(sig),
+                    };
+                    if tx.send(msg).is_err() {
                         break;
                     }
                 }
+            }));
+            handle
+        };
+        Ok(Workers {
+            shutdown,
+            #[cfg(unix)]
+            signals,
+            handles,
+        })
+    }
+
+    /// Ask every thread to finish and wait for them.
+    fn stop(self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        #[cfg(unix)]
+        self.signals.close();
+        for handle in self.handles {
+            if handle.join().is_err() {
+                log::warn!("worker thread panicked");
             }
-        });
+        }
+        log::debug!("worker threads stopped");
     }
+}
 
-    let mut running = false;
-    let mut start_time = Instant::now();
-    let mut elapsed = Duration::ZERO;
-    let mut splits: Vec<Split> = Vec::with_capacity(MAX_SPLITS);
-    let mut active: Option<usize> = None;
-    let mut main_goal: Option<String> = None;
+fn event_loop<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+) -> Result<i32> {
+    let (tx, rx) = mpsc::channel::<Message>();
+    let http = match &opts.http {
+        Some(addr) => {
+            Some(
+                http::Server::start(addr, tx.clone()).map_err(|source| Error::Remote {
+                    addr: addr.clone(),
+                    source,
+                })?,
+            )
+        }
+        None => None,
+    };
+    #[cfg(feature = "midi")]
+    let midi = match &opts.midi {
+        Some(path) => Some(
+            midi::Input::start(path, opts.midi_map.clone(), tx.clone()).map_err(|source| {
+                Error::Remote {
+                    addr: path.display().to_string(),
+                    source,
+                }
+            })?,
+        ),
+        None => None,
+    };
+    #[cfg(feature = "plugins")]
+    for command in &opts.plugins {
+        let plugin =
+            plugin::Plugin::start(command, tx.clone()).map_err(|source| Error::Plugin {
+                command: command.clone(),
+                source,
+            })?;
+        sinks.plugins.push(plugin);
+    }
+    let git = match &opts.git_splits {
+        Some(repo) => Some(
+            git::Watcher::start(repo, tx.clone()).map_err(|source| Error::Git {
+                path: repo.display().to_string(),
+                source,
+            })?,
+        ),
+        None => None,
+    };
+    let watcher = (!opts.watch.is_empty()).then(|| watch::Watcher::start(&opts.watch, tx.clone()));
+    #[cfg(feature = "window")]
+    let window = match opts.focus {
+        Some(_) => Some(window::Watcher::start(tx.clone()).map_err(Error::Window)?),
+        None => None,
+    };
+    if let Some(pattern) = &opts.split_on_line {
+        lines::watch(pattern.clone(), tx.clone());
+    }
+    if let Some(steps) = &opts.input_script {
+        script::play(steps.clone(), opts.simulate, tx.clone());
+    }
+    let workers = Workers::spawn(tx, opts.tick)?;
+    let result = handle_events(stdout, opts, sw, sinks, view, &rx);
+    workers.stop();
+    #[cfg(feature = "plugins")]
+    sinks.plugins.clear();
+    drop(http);
+    drop(git);
+    drop(watcher);
+    #[cfg(feature = "window")]
+    drop(window);
+    #[cfg(feature = "midi")]
+    drop(midi);
+    result
+}
 
-    draw_static(&mut stdout, &main_goal, elapsed, &splits)?;
+fn handle_events<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+    rx: &mpsc::Receiver<Message>,
+) -> Result<i32> {
+    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
 
+    let mut last_title = String::new();
+    let mut overlay = opts
+        .obs_file
+        .clone()
+        .map(|path| Overlay::new(path, opts.obs_interval, opts.obs_split));
+    let mut chord = Chord::default();
+    // only known to be false when the terminal reports focus changes
+    let mut focused = true;
     for msg in rx {
+        // a scripted wait is a tick that moves the clock on by its length
+        if let Message::Advance(by) = msg {
+            sw.clock.advance(by);
+        }
         match msg {
-            Message::Tick => {
-                if running {
-                    let _ = draw_dynamic(&mut stdout, start_time, elapsed, &splits, &main_goal);
+            Message::Tick | Message::Advance(_) => {
+                // a script moves the clock itself
+                if opts.simulate && opts.input_script.is_none() {
+                    sw.clock.advance(opts.tick);
                 }
-            }
-            Message::Input(evt) => match evt {
-                Event::Key(key) => match key.code {
-                    KeyCode::Char('s') => {
-                        if running {
-                            elapsed += Instant::now().duration_since(start_time);
-                            running = false;
-                        } else {
-                            disable_raw_mode()?;
-                            print!("\nEnter main goal: ");
-                            io::stdout().flush()?;
-                            let mut input = String::new();
-                            io::stdin().read_line(&mut input)?;
-                            enable_raw_mode()?;
-                            main_goal = Some(input.trim().to_string());
-                            start_time = Instant::now();
-                            elapsed = Duration::ZERO;
-                            splits.clear();
-                            active = None;
-                            running = true;
-                        }
-                        draw_static(&mut stdout, &main_goal, elapsed, &splits)?;
-                    }
-                    KeyCode::Char('c') => {
-                        if !running {
-                            // continue from stopped
-                            start_time = Instant::now();
-                            running = true;
-                        }
+                if let Some(step) = opts.keys.expire(&mut chord, sw.clock.now()) {
+                    if let Some(code) = run_action(stdout, opts, sw, sinks, view, step)? {
+                        return Ok(code);
                     }
-                    KeyCode::Char('r') => {
-                        running = false;
-                        elapsed = Duration::ZERO;
-                        splits.clear();
-                        main_goal = None;
-                        active = None;
-                        draw_static(&mut stdout, &main_goal, elapsed, &splits)?;
+                    show_pending(stdout, opts, sw, view, &chord)?;
+                }
+                if count_down(stdout, sw, sinks, view)? {
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+                if ring_alerts(stdout, opts, sw, sinks, view)? {
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+                if enforce_cap(stdout, opts, sw, sinks, view)? {
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+                if advance_sequence(stdout, opts, sw, sinks, view)? {
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+                if sw.running && focused {
+                    draw_dynamic(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+                if opts.title {
+                    let title = title_text(
+                        sw.total(),
+                        sw.running,
+                        sw.main_goal.as_deref().map(|g| view.name(g)),
+                        &opts.format,
+                    );
+                    if title != last_title {
+                        stdout.execute(SetTitle(&title)).map_err(Error::Terminal)?;
+                        last_title = title;
                     }
-                    KeyCode::Char('g') if running && splits.len() < MAX_SPLITS => {
-                        disable_raw_mode()?;
-                        print!("\nEnter subgoal name: ");
-                        io::stdout().flush()?;
-                        let mut input = String::new();
-                        io::stdin().read_line(&mut input)?;
-                        enable_raw_mode()?;
-                        let name = input.trim().to_string();
-                        let parent = active;
-                        let level = parent.map_or(0, |idx| splits[idx].level + 1);
-                        let now = Instant::now();
-                        let start_off = elapsed + now.duration_since(start_time);
-                        let start_dt = Local::now();
-                        splits.push(Split {
-                            name,
-                            start_offset: start_off,
-                            end_offset: None,
-                            start_dt,
-                            end_dt: None,
-                            parent,
-                            level,
+                }
+                if let Some(obs) = &mut overlay {
+                    if let Err(source) = obs.update(sw, &opts.format, view.private) {
+                        view.show_error(Error::Overlay {
+                            path: obs.path().display().to_string(),
+                            source,
                         });
-                        active = Some(splits.len() - 1);
-                        draw_static(
-                            &mut stdout,
-                            &main_goal,
-                            elapsed + now.duration_since(start_time),
-                            &splits,
-                        )?;
+                        // report once rather than on every tick
+                        overlay = None;
+                        redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
                     }
-                    KeyCode::Char('n')
-                        if running && active.is_some() && splits.len() < MAX_SPLITS =>
+                }
+            }
+            #[cfg(unix)]
+            Message::Signal(sig) => {
+                log::info!("received signal {}", sig);
+                return Ok(128 + sig);
+            }
+            Message::Failed(e) => return Err(e),
+            Message::Remote(_) => {}
+            Message::Press(button) => {
+                log::debug!("remote press {:?}", button);
+                view.dismiss();
+                press(opts, sw, sinks, view, button);
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            Message::Commit(subject) => {
+                log::debug!("commit '{}'", subject);
+                split_named(sw, sinks, view, subject);
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            Message::Line(name) => {
+                // the first phase of a build starts the clock if nothing has
+                if sw.main_goal.is_none() {
+                    let goal = UNTITLED.to_string();
+                    let project = opts.project.clone();
+                    act(sw, sinks, view, Action::Start { goal, project });
+                }
+                split_named(sw, sinks, view, name);
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            Message::Watched(path, true) => {
+                watched_appeared(opts, sw, sinks, view, path);
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            Message::Watched(path, false) => {
+                let timed = view.watched.iter().position(|(p, _)| *p == path);
+                if let Some((path, index)) = timed.map(|i| view.watched.remove(i)) {
+                    // unless it was stopped by hand, or reset, cut or merged
+                    // away
+                    let name = path
+                        .file_name()
+                        .unwrap_or(path.as_os_str())
+                        .to_string_lossy();
+                    if sw
+                        .splits
+                        .get(index)
+                        .is_some_and(|s| s.end_offset.is_none() && s.name == name)
                     {
-                        disable_raw_mode()?;
-                        print!("\nEnter nested subgoal name: ");
-                        io::stdout().flush()?;
-                        let mut input = String::new();
-                        io::stdin().read_line(&mut input)?;
-                        enable_raw_mode()?;
-                        let name = input.trim().to_string();
-                        let parent = active.unwrap();
-                        let level = splits[parent].level + 1;
-                        let now = Instant::now();
-                        let start_off = elapsed + now.duration_since(start_time);
-                        let start_dt = Local::now();
-                        splits.push(Split {
-                            name,
-                            start_offset: start_off,
-                            end_offset: None,
-                            start_dt,
-                            end_dt: None,
-                            parent: Some(parent),
-                            level,
-                        });
-                        active = Some(splits.len() - 1);
-                        draw_static(
-                            &mut stdout,
-                            &main_goal,
-                            elapsed + now.duration_since(start_time),
-                            &splits,
-                        )?;
+                        act(sw, sinks, view, Action::Stop { index });
+                        redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
                     }
-                    KeyCode::Char('h') => {
-                        if let Some(idx) = active {
-                            let now = Instant::now();
-                            let end_off = elapsed + now.duration_since(start_time);
-                            let end_dt = Local::now();
-                            splits[idx].end_offset = Some(end_off);
-                            splits[idx].end_dt = Some(end_dt);
-                            active = splits[idx].parent;
-                            draw_static(
-                                &mut stdout,
-                                &main_goal,
-                                elapsed + now.duration_since(start_time),
-                                &splits,
-                            )?;
-                        }
+                }
+            }
+            Message::LinesEnded => {
+                if sw.running && sw.active.is_some() {
+                    act(sw, sinks, view, Action::StopActive);
+                }
+                view.message = Some(tr("The piped input ended").to_string());
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            #[cfg(feature = "window")]
+            Message::Focus(title) => {
+                if focus_moved(opts, sw, sinks, view, title) {
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+            }
+            #[cfg(feature = "plugins")]
+            Message::Plugin(request) => {
+                log::debug!("plugin request {:?}", request);
+                plugin_request(opts, sw, sinks, view, request);
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            Message::Input(evt, at) => match evt {
+                Event::Key(key) => {
+                    let at = sw.clock.at(at);
+                    log::debug!("key {:?} {:?}", key.code, key.modifiers);
+                    if view.dismiss() {
+                        redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
                     }
-                    KeyCode::Char('u') => {
-                        if let Some(idx) = active {
-                            active = splits[idx].parent;
-                            draw_static(&mut stdout, &main_goal, elapsed, &splits)?;
+                    if key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)
+                    {
+                        return Ok(EXIT_INTERRUPTED);
+                    }
+                    if view.countdown.is_some() {
+                        if key.code == KeyCode::Esc {
+                            view.countdown = None;
+                            view.message = Some(tr("Start cancelled").to_string());
+                            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
                         }
+                        continue;
                     }
-                    KeyCode::Char('d') => {
-                        draw_static(&mut stdout, &main_goal, elapsed, &splits)?;
+                    if view.naming.is_some() {
+                        type_name(sw, sinks, view, key.code);
+                        redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                        continue;
                     }
-                    KeyCode::Char('t') if !running => {
-                        if let Some(goal) = &main_goal {
-                            let _ = save_log(goal, start_time, &splits, &log_file);
+                    let steps = opts.keys.feed(&mut chord, key.code, at);
+                    for step in steps {
+                        if let Some(code) = run_action(stdout, opts, sw, sinks, view, step)? {
+                            return Ok(code);
                         }
                     }
-                    KeyCode::Char('q') => break,
-                    _ => {}
-                },
-                Event::Resize(_, _) => {
+                    show_pending(stdout, opts, sw, view, &chord)?;
+                }
+                Event::Resize(w, h) => {
+                    log::debug!("resize to {}x{}", w, h);
                     // redraw on resize
-                    let total = if running {
-                        let now = Instant::now();
-                        elapsed + now.duration_since(start_time)
-                    } else {
-                        elapsed
-                    };
-                    draw_static(&mut stdout, &main_goal, total, &splits)?;
-                    if running {
-                        let _ = draw_dynamic(&mut stdout, start_time, elapsed, &splits, &main_goal);
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                    if sw.running {
+                        draw_dynamic(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                    }
+                }
+                Event::FocusLost => focused = false,
+                Event::FocusGained => {
+                    focused = true;
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                    if sw.running {
+                        draw_dynamic(stdout, opts, sw, view).map_err(Error::Terminal)?;
                     }
                 }
                 _ => {}
             },
-            _ => {}
         }
     }
+    Ok(0)
+}
+
+/// Show or clear the pending-keys indicator.
+/// A key typed while a split is being named: Enter renames it, Esc keeps
+/// the name it has.
+fn type_name(sw: &mut Stopwatch, sinks: &mut Sinks, view: &mut View, code: KeyCode) {
+    let Some(naming) = &mut view.naming else {
+        return;
+    };
+    match code {
+        KeyCode::Char(c) => naming.text.push(c),
+        KeyCode::Backspace => {
+            naming.text.pop();
+        }
+        KeyCode::Enter => {
+            let index = naming.index;
+            let name = naming.text.trim().to_string();
+            view.naming = None;
+            // a reset may have taken the split away meanwhile
+            if !name.is_empty() && index < sw.splits.len() {
+                act(sw, sinks, view, Action::Rename { index, name });
+            }
+        }
+        KeyCode::Esc => view.naming = None,
+        _ => {}
+    }
+}
+
+fn show_pending<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &Stopwatch,
+    view: &mut View,
+    chord: &Chord,
+) -> Result<()> {
+    let pending = chord.pending();
+    if pending != view.pending {
+        view.pending = pending;
+        redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+    }
+    Ok(())
+}
+
+/// Carry out a bound key action. The step carries the time its key was
+/// pressed, so an action that waited for a possible longer sequence still
+/// takes effect at that moment. Returns the exit status if the action ends
+/// the program.
+fn run_action<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+    (action, at): (KeyAction, Instant),
+) -> Result<Option<i32>> {
+    log::debug!("action {:?}", action);
+    match action {
+        KeyAction::StartPause if sw.running => {
+            act_at(sw, sinks, view, Action::Pause, at);
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::StartPause => {
+            let goal = prompt(stdout, opts, tr("Enter main goal: ")).map_err(Error::Terminal)?;
+            let project = opts.project.clone();
+            begin(opts, sw, sinks, view, goal, project);
+            view.selected = None;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Restart if sw.main_goal.is_none() => {
+            // nothing to start over, so start at once and ask for the name
+            // when it suits
+            let goal = UNTITLED.to_string();
+            let project = opts.project.clone();
+            begin(opts, sw, sinks, view, goal, project);
+            view.message = Some(tr("Timing; press G to name the goal").to_string());
+            view.selected = None;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Restart => {
+            let goal = sw.main_goal.clone().unwrap_or_default();
+            let project = sw.project.clone().or_else(|| opts.project.clone());
+            act_at(sw, sinks, view, Action::Reset, at);
+            act_at(sw, sinks, view, Action::Start { goal, project }, at);
+            view.selected = None;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Resume if !sw.running => {
+            // continue from stopped
+            act_at(sw, sinks, view, Action::Resume, at);
+        }
+        KeyAction::Reset => {
+            act_at(sw, sinks, view, Action::Reset, at);
+            view.selected = None;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Subgoal if sw.running && sw.splits.len() < MAX_SPLITS => {
+            let name = prompt(stdout, opts, tr("Enter subgoal name: ")).map_err(Error::Terminal)?;
+            let parent = sw.active;
+            // from the key press, not from when the name was typed
+            act_at(sw, sinks, view, Action::Split { name, parent }, at);
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::QuickSplit if sw.running && sw.splits.len() < MAX_SPLITS => {
+            let name = format!("Split {}", sw.splits.len() + 1);
+            let parent = sw.active;
+            act_at(sw, sinks, view, Action::Split { name, parent }, at);
+            view.naming = Some(Naming {
+                index: sw.splits.len() - 1,
+                text: String::new(),
+            });
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::NestedSubgoal
+            if sw.running && sw.active.is_some() && sw.splits.len() < MAX_SPLITS =>
+        {
+            let name =
+                prompt(stdout, opts, tr("Enter nested subgoal name: ")).map_err(Error::Terminal)?;
+            let parent = sw.active;
+            act_at(sw, sinks, view, Action::Split { name, parent }, at);
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::StopActive if sw.active.is_some() => {
+            act_at(sw, sinks, view, Action::StopActive, at);
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::StopSplit(n) => {
+            match n.checked_sub(1) {
+                Some(index) if sw.splits.get(index).is_some_and(|s| s.end_offset.is_none()) => {
+                    act_at(sw, sinks, view, Action::Stop { index }, at);
+                }
+                _ => view.message = Some(trf("No open split {}", &[&n])),
+            }
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Up if sw.active.is_some() => {
+            act(sw, sinks, view, Action::Ascend);
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Move => {
+            match view.selected {
+                Some(index) if index < sw.splits.len() => {
+                    let text = trf(
+                        "Move split {} under (split number, empty for top level): ",
+                        &[&(index + 1)],
+                    );
+                    let answer = prompt(stdout, opts, &text).map_err(Error::Terminal)?;
+                    let parent = match answer.as_str() {
+                        "" => Some(None),
+                        n => n
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|n| n.checked_sub(1))
+                            .map(Some),
+                    };
+                    match parent {
+                        Some(parent) if sw.can_reparent(index, parent) => {
+                            let moved_to = sw.reparented_index(index, parent);
+                            act(sw, sinks, view, Action::Reparent { index, parent });
+                            view.selected = Some(moved_to);
+                        }
+                        _ => {
+                            view.message = Some(trf(
+                                "Can't move split {} under '{}'",
+                                &[&(index + 1), &answer],
+                            ))
+                        }
+                    }
+                }
+                _ => view.message = Some(tr("No split selected (use the arrow keys)").to_string()),
+            }
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Promote | KeyAction::Demote => {
+            match view.selected {
+                Some(index) if index < sw.splits.len() => {
+                    let (step, moved_to) = if action == KeyAction::Promote {
+                        (Action::Promote { index }, sw.promoted_index(index))
+                    } else {
+                        let target = sw.previous_sibling(index);
+                        (Action::Demote { index }, target.map(|_| index))
+                    };
+                    match moved_to {
+                        Some(moved_to) => {
+                            act(sw, sinks, view, step);
+                            view.selected = Some(moved_to);
+                        }
+                        None if action == KeyAction::Promote => {
+                            view.message =
+                                Some(trf("Split {} is already at the top level", &[&(index + 1)]))
+                        }
+                        None => {
+                            view.message = Some(trf(
+                                "Split {} has no earlier sibling to go under",
+                                &[&(index + 1)],
+                            ))
+                        }
+                    }
+                }
+                _ => view.message = Some(tr("No split selected (use the arrow keys)").to_string()),
+            }
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Merge => {
+            match view.selected {
+                Some(index) if index < sw.splits.len() => match sw.previous_sibling(index) {
+                    Some(prev) => {
+                        act(sw, sinks, view, Action::Merge { index });
+                        view.selected = Some(prev);
+                    }
+                    None => {
+                        view.message = Some(trf(
+                            "Split {} has no earlier sibling to merge into",
+                            &[&(index + 1)],
+                        ))
+                    }
+                },
+                _ => view.message = Some(tr("No split selected (use the arrow keys)").to_string()),
+            }
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Divide => {
+            match view.selected {
+                Some(index) if index < sw.splits.len() => {
+                    divide(stdout, opts, sw, sinks, view, index)?
+                }
+                _ => view.message = Some(tr("No split selected (use the arrow keys)").to_string()),
+            }
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Rename => {
+            match view.selected.or(sw.active) {
+                Some(index) if index < sw.splits.len() => {
+                    view.naming = Some(Naming {
+                        index,
+                        text: sw.splits[index].name.clone(),
+                    });
+                }
+                _ => view.message = Some(tr("No split selected (use the arrow keys)").to_string()),
+            }
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Goal if sw.main_goal.is_some() => {
+            let current = sw.main_goal.as_deref().map_or("", |g| view.name(g));
+            let text = trf("Rename goal (now {}, empty to keep it): ", &[&current]);
+            let goal = prompt(stdout, opts, &text).map_err(Error::Terminal)?;
+            if !goal.is_empty() {
+                act(sw, sinks, view, Action::SetGoal { goal });
+            }
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Project => {
+            let current = sw.project.as_deref().map_or(tr("none"), |p| view.name(p));
+            let text = trf("Switch project (now {}, empty for none): ", &[&current]);
+            let name = prompt(stdout, opts, &text).map_err(Error::Terminal)?;
+            let project = Some(name).filter(|n| !n.is_empty());
+            act(sw, sinks, view, Action::SetProject { project });
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::NextGoal if sw.main_goal.is_some() => {
+            let goal = prompt(stdout, opts, tr("Next goal (empty to stop here): "))
+                .map_err(Error::Terminal)?;
+            act_at(sw, sinks, view, Action::Finish, at);
+            if !goal.is_empty() {
+                let project = sw.project.clone();
+                act_at(sw, sinks, view, Action::Start { goal, project }, at);
+            }
+            view.selected = None;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Redraw => {
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::ToggleView => {
+            view.compact = !view.compact;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::OpenOnly => {
+            view.open_only = !view.open_only;
+            view.sorted = false;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Sort => {
+            view.sorted = !view.sorted;
+            view.open_only = false;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Breakdown => {
+            view.breakdown = !view.breakdown;
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Private => {
+            view.private = !view.private;
+            view.message = Some(
+                if view.private {
+                    tr("Names hidden")
+                } else {
+                    tr("Names shown")
+                }
+                .to_string(),
+            );
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Save if !sw.running => {
+            save_session(opts, sw, view);
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::SelectPrev => {
+            view.select_prev(&view.shown(sw));
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::SelectNext => {
+            view.select_next(&view.shown(sw));
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::CopyTime => {
+            let text = opts.format.format(sw.total());
+            copy_to_clipboard(stdout, opts, view, &text, "time");
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::CopySplit => {
+            match view.selected {
+                Some(i) if i < sw.splits.len() => {
+                    let live = sw.running.then(|| sw.total());
+                    let text = split_line(i, &sw.splits[i], live, &opts.format);
+                    copy_to_clipboard(stdout, opts, view, text.trim_start(), "split");
+                }
+                _ => view.message = Some(tr("No split selected (use the arrow keys)").to_string()),
+            }
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::CopySummary => {
+            let text = session_summary(sw, &opts.format);
+            copy_to_clipboard(stdout, opts, view, &text, "summary");
+            redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+        }
+        KeyAction::Quit => return Ok(Some(0)),
+        _ => {}
+    }
+    Ok(None)
+}
 
-    disable_raw_mode()?;
+/// Ask where to cut the split at `index` and what to call the second part.
+fn divide<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    sinks: &mut Sinks,
+    view: &mut View,
+    index: usize,
+) -> Result<()> {
+    let split = &sw.splits[index];
+    let Some(end) = split.end_offset else {
+        view.message = Some(trf("Split {} is still running", &[&(index + 1)]));
+        return Ok(());
+    };
+    let length = split.length(end);
+    let text = trf(
+        "Cut split {} ({}) how far in (e.g. 10m): ",
+        &[&(index + 1), &opts.format.format(length)],
+    );
+    let answer = prompt(stdout, opts, &text).map_err(Error::Terminal)?;
+    let at = match parse_duration(&answer) {
+        Ok(at) if !at.is_zero() && at < length => at,
+        Ok(_) => {
+            view.message = Some(trf("'{}' isn't inside split {}", &[&answer, &(index + 1)]));
+            return Ok(());
+        }
+        Err(e) => {
+            view.message = Some(e.to_string());
+            return Ok(());
+        }
+    };
+    let name = prompt(stdout, opts, tr("Name for the second part: ")).map_err(Error::Terminal)?;
+    act(sw, sinks, view, Action::Divide { index, at, name });
     Ok(())
 }
@@ -0,0 +1,298 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::platform;
+
+/// DNS-SD service type that shared timers are advertised under.
+const SERVICE: &str = "_stopwatch._tcp.local";
+const GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const PORT: u16 = 5353;
+const TTL: u32 = 120;
+
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+/// Set on the class of a record that replaces earlier copies.
+const CACHE_FLUSH: u16 = 0x8000;
+
+/// A timer found on the network.
+pub struct Found {
+    pub name: String,
+    pub addr: SocketAddr,
+}
+
+/// Answers mDNS queries for this instance until dropped.
+pub struct Advertiser {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Advertiser {
+    pub fn start(instance: &str, port: u16) -> io::Result<Advertiser> {
+        let socket = platform::bind_shared(PORT)?;
+        socket.join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED)?;
+        socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+        let response = Response {
+            instance: format!("{}.{}", instance, SERVICE),
+            host: format!("{}.local", hostname()),
+            port,
+        };
+        // announce once so browsers already listening see us straight away
+        let _ = socket.send_to(&response.encode(0, &[]), (GROUP, PORT));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = shutdown.clone();
+            thread::spawn(move || respond(&socket, &response, &shutdown))
+        };
+        log::info!("advertising {} via mDNS", instance);
+        Ok(Advertiser {
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+struct Response {
+    instance: String,
+    host: String,
+    port: u16,
+}
+
+impl Response {
+    /// PTR, SRV and TXT records for the instance. Replies to legacy queries
+    /// (not from port 5353) echo the query id and question.
+    fn encode(&self, id: u16, question: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_u16(&mut out, id);
+        put_u16(&mut out, 0x8400); // response, authoritative
+        put_u16(&mut out, u16::from(!question.is_empty()));
+        put_u16(&mut out, 3);
+        put_u16(&mut out, 0);
+        put_u16(&mut out, 0);
+        out.extend_from_slice(question);
+
+        put_name(&mut out, SERVICE);
+        put_record_header(&mut out, TYPE_PTR, CLASS_IN);
+        let mut rdata = Vec::new();
+        put_name(&mut rdata, &self.instance);
+        put_rdata(&mut out, &rdata);
+
+        put_name(&mut out, &self.instance);
+        put_record_header(&mut out, TYPE_SRV, CLASS_IN | CACHE_FLUSH);
+        let mut rdata = Vec::new();
+        put_u16(&mut rdata, 0); // priority
+        put_u16(&mut rdata, 0); // weight
+        put_u16(&mut rdata, self.port);
+        put_name(&mut rdata, &self.host);
+        put_rdata(&mut out, &rdata);
+
+        put_name(&mut out, &self.instance);
+        put_record_header(&mut out, TYPE_TXT, CLASS_IN | CACHE_FLUSH);
+        put_rdata(&mut out, b"\x04v=1");
+        out
+    }
+}
+
+fn respond(socket: &UdpSocket, response: &Response, shutdown: &AtomicBool) {
+    let mut buf = [0u8; 1500];
+    while !shutdown.load(Ordering::Relaxed) {
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => {
+                log::warn!("mDNS responder stopped: {}", e);
+                return;
+            }
+        };
+        let Some(query) = parse(&buf[..len]) else {
+            continue;
+        };
+        if query.response
+            || !query
+                .questions
+                .iter()
+                .any(|q| q.eq_ignore_ascii_case(SERVICE))
+        {
+            continue;
+        }
+        let sent = if from.port() == PORT {
+            socket.send_to(&response.encode(0, &[]), (GROUP, PORT))
+        } else {
+            socket.send_to(&response.encode(query.id, &query.question_bytes), from)
+        };
+        if let Err(e) = sent {
+            log::debug!("mDNS reply to {} failed: {}", from, e);
+        }
+    }
+}
+
+/// Ask the network for shared timers, collecting answers for `wait`.
+pub fn browse(wait: Duration) -> io::Result<Vec<Found>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    let mut query = Vec::new();
+    put_u16(&mut query, 0);
+    put_u16(&mut query, 0);
+    put_u16(&mut query, 1);
+    put_u16(&mut query, 0);
+    put_u16(&mut query, 0);
+    put_u16(&mut query, 0);
+    put_name(&mut query, SERVICE);
+    put_u16(&mut query, TYPE_PTR);
+    put_u16(&mut query, CLASS_IN);
+    socket.send_to(&query, (GROUP, PORT))?;
+
+    // instance name -> address, so repeated answers collapse
+    let mut found = BTreeMap::new();
+    let deadline = Instant::now() + wait;
+    let mut buf = [0u8; 1500];
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if left.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(left))?;
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e),
+        };
+        let Some(msg) = parse(&buf[..len]) else {
+            continue;
+        };
+        for (name, port) in msg.services {
+            let label = name.strip_suffix(SERVICE).unwrap_or(&name);
+            let label = label.trim_end_matches('.').to_string();
+            found.insert(label, SocketAddr::new(from.ip(), port));
+        }
+    }
+    Ok(found
+        .into_iter()
+        .map(|(name, addr)| Found { name, addr })
+        .collect())
+}
+
+struct Packet {
+    id: u16,
+    response: bool,
+    questions: Vec<String>,
+    /// The raw question section, echoed back in legacy unicast replies.
+    question_bytes: Vec<u8>,
+    /// Instance names and ports from SRV records.
+    services: Vec<(String, u16)>,
+}
+
+fn parse(packet: &[u8]) -> Option<Packet> {
+    let id = get_u16(packet, 0)?;
+    let flags = get_u16(packet, 2)?;
+    let qdcount = get_u16(packet, 4)?;
+    let records =
+        get_u16(packet, 6)? as usize + get_u16(packet, 8)? as usize + get_u16(packet, 10)? as usize;
+    let mut pos = 12;
+    let mut questions = Vec::new();
+    for _ in 0..qdcount {
+        let (name, next) = get_name(packet, pos)?;
+        questions.push(name);
+        pos = next + 4;
+    }
+    let question_bytes = packet.get(12..pos)?.to_vec();
+    let mut services = Vec::new();
+    for _ in 0..records {
+        let (name, next) = get_name(packet, pos)?;
+        let rtype = get_u16(packet, next)?;
+        let rdlen = get_u16(packet, next + 8)? as usize;
+        let rdata = next + 10;
+        if rtype == TYPE_SRV && name.ends_with(SERVICE) {
+            services.push((name, get_u16(packet, rdata + 4)?));
+        }
+        pos = rdata + rdlen;
+    }
+    Some(Packet {
+        id,
+        response: flags & 0x8000 != 0,
+        questions,
+        question_bytes,
+        services,
+    })
+}
+
+fn put_u16(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+fn put_name(out: &mut Vec<u8>, name: &str) {
+    for label in name.split('.').filter(|l| !l.is_empty()) {
+        let label = &label.as_bytes()[..label.len().min(63)];
+        out.push(label.len() as u8);
+        out.extend_from_slice(label);
+    }
+    out.push(0);
+}
+
+fn put_record_header(out: &mut Vec<u8>, rtype: u16, class: u16) {
+    put_u16(out, rtype);
+    put_u16(out, class);
+    out.extend_from_slice(&TTL.to_be_bytes());
+}
+
+fn put_rdata(out: &mut Vec<u8>, rdata: &[u8]) {
+    put_u16(out, rdata.len() as u16);
+    out.extend_from_slice(rdata);
+}
+
+fn get_u16(packet: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *packet.get(pos)?,
+        *packet.get(pos + 1)?,
+    ]))
+}
+
+/// Read a possibly compressed name at `pos`, returning it and the position
+/// just past it.
+fn get_name(packet: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    // bounds the number of compression jumps
+    for _ in 0..64 {
+        let len = *packet.get(pos)? as usize;
+        if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(pos + 1)));
+        }
+        if len & 0xc0 == 0xc0 {
+            let target = (get_u16(packet, pos)? & 0x3fff) as usize;
+            end.get_or_insert(pos + 2);
+            pos = target;
+            continue;
+        }
+        let label = packet.get(pos + 1..pos + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        pos += 1 + len;
+    }
+    None
+}
+
+fn hostname() -> String {
+    platform::hostname().unwrap_or_else(|| "stopwatch".to_string())
+}
+
+/// Instance name advertised for this process.
+pub fn instance_name(port: u16) -> String {
+    format!("{}-{}", hostname(), port)
+}
@@ -0,0 +1,53 @@
+use std::{fs, io::Write};
+
+use crate::options::{MergeArgs, Options};
+use crate::orglog::{self, Session};
+
+/// `sw merge`: combine the logs kept on several machines into one, sorted
+/// by start time. A session that appears in more than one file (same goal,
+/// start and end) is written once.
+pub fn run(opts: &Options, args: &MergeArgs) -> Result<(), String> {
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut duplicates = 0;
+    for path in &args.files {
+        for session in orglog::read(path, &opts.crypt).map_err(|e| format!("{}: {}", path, e))? {
+            let same = |s: &Session| {
+                s.goal == session.goal && s.start == session.start && s.end == session.end
+            };
+            if sessions.iter().any(same) {
+                duplicates += 1;
+                continue;
+            }
+            sessions.push(session);
+        }
+    }
+    sessions.sort_by_key(|s| s.start);
+    log::info!(
+        "merged {} sessions, {} duplicates dropped",
+        sessions.len(),
+        duplicates
+    );
+
+    let text: String = sessions
+        .iter()
+        .map(|s| orglog::render(&s.headings(1, &opts.format)))
+        .collect();
+    match &args.output {
+        Some(path) => opts
+            .crypt
+            .seal(text.as_bytes())
+            .and_then(|text| fs::write(path, text))
+            .map_err(|e| format!("{}: {}", path, e))?,
+        None => std::io::stdout()
+            .write_all(text.as_bytes())
+            .map_err(|e| e.to_string())?,
+    }
+    if !opts.quiet && args.output.is_some() {
+        println!(
+            "Merged {} sessions ({} duplicates dropped)",
+            sessions.len(),
+            duplicates
+        );
+    }
+    Ok(())
+}
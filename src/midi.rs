@@ -0,0 +1,214 @@
+use std::{
+    fs::File,
+    io::{self, ErrorKind, Read},
+    os::fd::AsRawFd,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{Message, Press};
+
+#[cfg(not(unix))]
+compile_error!("the midi feature reads a raw MIDI device, which needs Unix");
+
+/// A MIDI message that can be bound to a button, on any channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Note on with a non-zero velocity.
+    Note(u8),
+    /// Controller value crossing into the upper half, as a sustain pedal
+    /// does when pressed.
+    Cc(u8),
+}
+
+/// Middle C toggles, the D above splits, and the sustain pedal splits.
+pub fn default_map() -> Vec<(Trigger, Press)> {
+    vec![
+        (Trigger::Note(60), Press::Toggle),
+        (Trigger::Note(62), Press::Split),
+        (Trigger::Cc(64), Press::Split),
+    ]
+}
+
+/// Parse `note:60=toggle,cc:64=split`.
+pub fn parse_map(spec: &str) -> Result<Vec<(Trigger, Press)>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|binding| {
+            let err = || {
+                format!(
+                    "invalid MIDI binding '{}' (expected e.g. note:60=toggle or cc:64=split)",
+                    binding
+                )
+            };
+            let (trigger, action) = binding.split_once('=').ok_or_else(err)?;
+            let (kind, number) = trigger.split_once(':').ok_or_else(err)?;
+            let number: u8 = number
+                .trim()
+                .parse()
+                .ok()
+                .filter(|n| *n < 128)
+                .ok_or_else(err)?;
+            let trigger = match kind.trim() {
+                "note" => Trigger::Note(number),
+                "cc" => Trigger::Cc(number),
+                _ => return Err(err()),
+            };
+            let press = Press::from_name(action.trim()).ok_or_else(err)?;
+            Ok((trigger, press))
+        })
+        .collect()
+}
+
+/// Reads a raw MIDI device (e.g. ALSA's `/dev/snd/midiC1D0`) and turns
+/// bound messages into button presses. It stops when dropped.
+pub struct Input {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Input {
+    pub fn start(
+        path: &Path,
+        map: Vec<(Trigger, Press)>,
+        tx: mpsc::Sender<Message>,
+    ) -> io::Result<Input> {
+        let device = File::open(path)?;
+        log::info!("reading MIDI from {}", path.display());
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                if let Err(e) = listen(device, &map, &tx, &shutdown) {
+                    log::warn!("MIDI input stopped: {}", e);
+                }
+            })
+        };
+        Ok(Input {
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Input {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn listen(
+    mut device: File,
+    map: &[(Trigger, Press)],
+    tx: &mpsc::Sender<Message>,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    let mut parser = Parser::default();
+    let mut buf = [0u8; 64];
+    while !shutdown.load(Ordering::Relaxed) {
+        // wait with a timeout so the shutdown flag is noticed
+        let mut fds = libc::pollfd {
+            fd: device.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut fds, 1, 200) };
+        if ready < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() == ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(e);
+        }
+        if ready == 0 {
+            continue;
+        }
+        let n = device.read(&mut buf)?;
+        if n == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "device closed"));
+        }
+        for &byte in &buf[..n] {
+            let Some(trigger) = parser.feed(byte) else {
+                continue;
+            };
+            log::debug!("MIDI {:?}", trigger);
+            for &(bound, press) in map {
+                if bound == trigger && tx.send(Message::Press(press)).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Byte-at-a-time MIDI decoder, handling running status.
+struct Parser {
+    status: u8,
+    data: [u8; 2],
+    len: usize,
+    /// Controllers currently in their upper half, so a held pedal triggers
+    /// once.
+    held: [bool; 128],
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            status: 0,
+            data: [0; 2],
+            len: 0,
+            held: [false; 128],
+        }
+    }
+}
+
+impl Parser {
+    fn feed(&mut self, byte: u8) -> Option<Trigger> {
+        match byte {
+            // real-time messages may appear anywhere and don't disturb the
+            // running status
+            0xf8..=0xff => return None,
+            // system messages and sysex cancel the running status
+            0xf0..=0xf7 => {
+                self.status = 0;
+                return None;
+            }
+            0x80..=0xef => {
+                self.status = byte;
+                self.len = 0;
+                return None;
+            }
+            _ if self.status == 0 => return None,
+            _ => {}
+        }
+        self.data[self.len] = byte;
+        self.len += 1;
+        let needed = match self.status & 0xf0 {
+            0xc0 | 0xd0 => 1,
+            _ => 2,
+        };
+        if self.len < needed {
+            return None;
+        }
+        self.len = 0;
+        let [number, value] = self.data;
+        match self.status & 0xf0 {
+            0x90 if value > 0 => Some(Trigger::Note(number)),
+            0xb0 => {
+                let pressed = value >= 64;
+                let was = std::mem::replace(&mut self.held[number as usize], pressed);
+                (pressed && !was).then_some(Trigger::Cc(number))
+            }
+            _ => None,
+        }
+    }
+}
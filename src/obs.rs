@@ -0,0 +1,70 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::duration::DurationFormat;
+use crate::stopwatch::Stopwatch;
+use crate::ui::MASK;
+
+/// Keeps a small text file in sync with the timer, for an OBS text source
+/// (or anything else that polls a file).
+pub struct Overlay {
+    path: PathBuf,
+    interval: Duration,
+    /// Add the active split's name on a second line.
+    split: bool,
+    last_write: Option<Instant>,
+    last_text: String,
+}
+
+impl Overlay {
+    pub fn new(path: PathBuf, interval: Duration, split: bool) -> Overlay {
+        Overlay {
+            path,
+            interval,
+            split,
+            last_write: None,
+            last_text: String::new(),
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Rewrite the file if the interval has passed and the text changed.
+    /// The split's name is masked when `private`.
+    pub fn update(
+        &mut self,
+        sw: &Stopwatch,
+        fmt: &DurationFormat,
+        private: bool,
+    ) -> io::Result<()> {
+        if self
+            .last_write
+            .is_some_and(|last| last.elapsed() < self.interval)
+        {
+            return Ok(());
+        }
+        self.last_write = Some(Instant::now());
+        let mut text = fmt.format(sw.total());
+        if self.split {
+            text.push('\n');
+            if let Some(idx) = sw.active {
+                text.push_str(if private { MASK } else { &sw.splits[idx].name });
+            }
+        }
+        if text == self.last_text {
+            return Ok(());
+        }
+        // write beside the target and rename over it so readers never see a
+        // half-written file
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, &text)?;
+        fs::rename(&tmp, &self.path)?;
+        self.last_text = text;
+        Ok(())
+    }
+}
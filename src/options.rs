@@ -0,0 +1,1116 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::NaiveTime;
+
+use crate::backup::Backup;
+use crate::billing::{self, Accounts, Rates};
+use crate::clipboard;
+use crate::compress::Codec;
+use crate::config::{self, Config};
+use crate::crypt::Crypt;
+use crate::duration::{parse_duration, DurationFormat, Precision, Rounding, Style};
+use crate::export;
+use crate::hooks::{self, Hooks};
+use crate::keys::{self, KeyAction, Keymap};
+use crate::locale::Lang;
+#[cfg(feature = "midi")]
+use crate::midi;
+use crate::replay;
+use crate::report;
+use crate::script;
+use crate::sequence::{self, Sequence};
+use crate::state;
+#[cfg(feature = "window")]
+use crate::window::Follow;
+use crate::Press;
+
+pub enum Command {
+    /// The interactive stopwatch.
+    Run,
+    Export(ExportArgs),
+    Replay(ReplayArgs),
+    Report(ReportArgs),
+    Import(ImportArgs),
+    Merge(MergeArgs),
+    /// Fold sync conflict copies of the log back into it.
+    Reconcile(ReconcileArgs),
+    Check(CheckArgs),
+    Status(StatusArgs),
+    /// Press a button on the running stopwatch.
+    Press(Press),
+    /// Watch a timer shared by another instance, picked via mDNS when no
+    /// address is given.
+    Follow(Option<String>),
+    /// Time the screen code against a large generated session.
+    Stress(StressArgs),
+}
+
+pub struct ExportArgs {
+    pub format: export::Format,
+    pub output: Option<String>,
+    /// 1-based index into the log; the last session when unset.
+    pub session: Option<usize>,
+    /// Invoice line items per tag or per project.
+    pub group: export::Group,
+    /// How timestamps and durations are written.
+    pub profile: export::Profile,
+    /// A plugin program that writes the export instead.
+    #[cfg(feature = "plugins")]
+    pub plugin: Option<String>,
+}
+
+pub struct ReportArgs {
+    /// How many weeks back the heatmap goes, this one included.
+    pub weeks: usize,
+    /// Only goals with this tag.
+    pub tag: Option<String>,
+    /// Only goals whose name matches this pattern (`*` and `?` wildcards).
+    pub goal: Option<String>,
+    /// Only goals at least this long.
+    pub min_duration: Option<Duration>,
+    /// Totals per group instead of the heatmap.
+    pub by: Option<report::GroupBy>,
+    pub format: report::Format,
+}
+
+pub struct ImportArgs {
+    /// Org files, or Toggl or Clockify CSV exports, to read sessions from.
+    pub files: Vec<String>,
+}
+
+pub struct MergeArgs {
+    /// Org logs to combine.
+    pub files: Vec<String>,
+    /// Where to write the result; stdout when unset.
+    pub output: Option<String>,
+}
+
+pub struct StressArgs {
+    pub splits: usize,
+    pub ticks: usize,
+    /// Fail if a `draw_dynamic` takes longer than this on average, to catch
+    /// a slowdown in CI.
+    pub max_tick: Option<Duration>,
+}
+
+pub struct ReconcileArgs {
+    /// Take everything from the copies without asking.
+    pub yes: bool,
+}
+
+pub struct CheckArgs {
+    /// Repair what can be repaired, in place.
+    pub fix: bool,
+}
+
+pub struct StatusArgs {
+    /// One short line for a shell prompt.
+    pub starship: bool,
+    /// A line for polybar, with click actions.
+    pub polybar: bool,
+    /// Print a new line whenever it changes instead of once.
+    pub follow: bool,
+}
+
+pub struct ReplayArgs {
+    /// The journal to play back.
+    pub path: String,
+    pub speed: replay::Speed,
+}
+
+pub struct Options {
+    pub command: Command,
+    pub log_file: String,
+    pub autosave: bool,
+    pub title: bool,
+    pub inline: bool,
+    /// Animate a spinner next to the time while running.
+    pub spinner: bool,
+    /// How often the running time is redrawn.
+    pub tick: Duration,
+    /// Save power: redraw once a second without milliseconds or the
+    /// spinner, and not at all while the terminal is out of focus.
+    pub low_power: bool,
+    /// Color split rows by nesting level; off when `NO_COLOR` is set.
+    pub color: bool,
+    /// Start with the compact split list.
+    pub compact: bool,
+    /// Start with the names hidden, see `View::private`.
+    pub private: bool,
+    /// Show a made-up session instead of timing anything.
+    pub demo: bool,
+    /// Run on a simulated clock that moves on by exactly `tick` each tick,
+    /// however long one really takes, or only with the waits of
+    /// `input_script`.
+    pub simulate: bool,
+    /// Keys and prompt answers to feed in as if typed.
+    pub input_script: Option<Vec<script::Step>>,
+    pub target: Option<Duration>,
+    /// Count down this long, beeping for the last three seconds, before a
+    /// new goal starts.
+    pub lead_in: Option<Duration>,
+    /// Pause by itself once a goal has been timed this long, so a timer left
+    /// running overnight doesn't end up in the log.
+    pub max_duration: Option<Duration>,
+    /// Marks in a goal's time to ring the bell at, in order.
+    pub alerts: Vec<Duration>,
+    /// Start the goal by itself at this time of day, the next time it comes
+    /// round.
+    pub start_at: Option<NaiveTime>,
+    /// Countdowns to run back to back once the timer runs.
+    pub sequence: Option<Sequence>,
+    pub format: DurationFormat,
+    /// Applied to durations in the log and exports.
+    pub rounding: Rounding,
+    pub clipboard: clipboard::Mode,
+    /// Suppress incidental output such as the exit summary.
+    pub quiet: bool,
+    /// Number of `-v` flags; enables the debug log file.
+    pub verbose: u8,
+    pub debug_log: Option<PathBuf>,
+    /// Append every state change here and recover from it on startup.
+    pub journal: Option<String>,
+    /// Serve the timer to spectators on this address.
+    pub share: Option<String>,
+    /// Accept `/press/split` and `/press/toggle` requests on this address.
+    pub http: Option<String>,
+    /// Raw MIDI device to read button presses from.
+    #[cfg(feature = "midi")]
+    pub midi: Option<PathBuf>,
+    #[cfg(feature = "midi")]
+    pub midi_map: Vec<(midi::Trigger, Press)>,
+    pub keys: Keymap,
+    /// Interface language, from `LANG` unless set.
+    pub lang: Lang,
+    /// Project or client recorded with new goals.
+    pub project: Option<String>,
+    /// Goal to start timing as soon as the stopwatch opens, without asking.
+    pub goal: Option<String>,
+    /// Also write each saved goal to its own file here.
+    pub archive_dir: Option<PathBuf>,
+    /// Only write to the archive directory, not the log.
+    pub archive_only: bool,
+    /// Compress archived sessions once they are this many days old.
+    pub compress_after: Option<u64>,
+    pub compression: Codec,
+    /// Also append one JSON line per saved goal and split here.
+    pub jsonl_log: Option<String>,
+    /// Encrypt the log, the archived sessions and the JSON lines.
+    pub crypt: Crypt,
+    /// Commit the log to the git repository it is in after each save.
+    pub git_commit: bool,
+    /// The commit's message, with `{goals}`, `{project}`, `{total}`, `{date}`
+    /// and `{host}` filled in.
+    pub commit_message: String,
+    /// Copy the saved files here after each save.
+    pub backup: Option<Backup>,
+    /// The S3 service for an `s3://` backup other than Amazon's.
+    pub s3_endpoint: Option<String>,
+    /// Add CLOCK lines to matching headings already in the log instead of
+    /// writing new ones.
+    pub merge_headings: bool,
+    /// Heading path in the log to save goals under, outermost first.
+    pub log_parent: Vec<String>,
+    /// Hourly rates; the running amount is shown when one applies.
+    pub rates: Rates,
+    /// Beancount accounts to book time to, per tag.
+    pub accounts: Accounts,
+    /// Shell commands to run on timer events.
+    pub hooks: Hooks,
+    /// Start a new split named after each commit made in this repository.
+    pub git_splits: Option<PathBuf>,
+    /// Time a split for as long as each of these paths is there. They are
+    /// looked at four times a second: a path there for less than that can
+    /// be missed, and one replaced in between ends its split and starts
+    /// another.
+    pub watch: Vec<PathBuf>,
+    /// Start a split for each piped line matching this pattern (`*` and `?`
+    /// wildcards), named after what the first `*` matched.
+    pub split_on_line: Option<String>,
+    /// Split or take note whenever another window gets the focus.
+    #[cfg(feature = "window")]
+    pub focus: Option<Follow>,
+    /// Keep the live state here for `sw status`.
+    pub state_file: Option<PathBuf>,
+    /// Keep the machine from suspending while the timer runs.
+    pub inhibit_sleep: bool,
+    /// Plugin commands to run alongside the stopwatch.
+    #[cfg(feature = "plugins")]
+    pub plugins: Vec<String>,
+    /// Keep the current time in this file for streaming overlays.
+    pub obs_file: Option<PathBuf>,
+    pub obs_interval: Duration,
+    /// Put the active split's name on a second line of the overlay file.
+    pub obs_split: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            command: Command::Run,
+            log_file: "done.org".to_string(),
+            autosave: false,
+            title: false,
+            inline: false,
+            spinner: true,
+            tick: Duration::from_millis(crate::TICK_RATE_MS),
+            low_power: false,
+            compact: false,
+            private: false,
+            demo: false,
+            simulate: false,
+            input_script: None,
+            color: env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+            target: None,
+            lead_in: None,
+            start_at: None,
+            max_duration: None,
+            alerts: Vec::new(),
+            sequence: None,
+            format: DurationFormat::default(),
+            rounding: Rounding::Exact,
+            clipboard: clipboard::Mode::Auto,
+            quiet: false,
+            verbose: 0,
+            debug_log: None,
+            journal: None,
+            share: None,
+            http: None,
+            keys: Keymap::default(),
+            lang: Lang::from_env(),
+            rates: Rates::default(),
+            accounts: Accounts::default(),
+            hooks: Hooks::default(),
+            git_splits: None,
+            split_on_line: None,
+            #[cfg(feature = "window")]
+            focus: None,
+            watch: Vec::new(),
+            state_file: state::default_path(),
+            inhibit_sleep: false,
+            #[cfg(feature = "plugins")]
+            plugins: Vec::new(),
+            project: None,
+            goal: None,
+            archive_dir: None,
+            archive_only: false,
+            compress_after: None,
+            compression: Codec::default(),
+            jsonl_log: None,
+            crypt: Crypt::default(),
+            git_commit: false,
+            commit_message: "Log {goals} ({total})".to_string(),
+            backup: None,
+            s3_endpoint: None,
+            merge_headings: false,
+            log_parent: Vec::new(),
+            #[cfg(feature = "midi")]
+            midi: None,
+            #[cfg(feature = "midi")]
+            midi_map: midi::default_map(),
+            obs_file: None,
+            obs_interval: Duration::from_millis(250),
+            obs_split: false,
+        }
+    }
+}
+
+impl Options {
+    /// The battery-saving profile. Options given after it still apply.
+    fn set_low_power(&mut self) {
+        self.low_power = true;
+        self.tick = Duration::from_secs(1);
+        self.format.precision = Precision::Seconds;
+        self.spinner = false;
+    }
+}
+
+/// Build the options from defaults, then the config file, then the command
+/// line, each overriding the previous.
+pub fn parse_args() -> Result<Options, String> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut opts = Options::default();
+    // the stopwatch guided through a routine; read once the config is in
+    let mut routine = None;
+    match args.first().map(String::as_str) {
+        Some("export") => {
+            args.remove(0);
+            opts.command = Command::Export(ExportArgs {
+                format: export::Format::Html,
+                output: None,
+                session: None,
+                group: export::Group::Tag,
+                profile: export::Profile::Org,
+                #[cfg(feature = "plugins")]
+                plugin: None,
+            });
+        }
+        Some("report") => {
+            args.remove(0);
+            opts.command = Command::Report(ReportArgs {
+                weeks: 12,
+                tag: None,
+                goal: None,
+                min_duration: None,
+                by: None,
+                format: report::Format::Text,
+            });
+        }
+        Some("import") => {
+            args.remove(0);
+            opts.command = Command::Import(ImportArgs { files: Vec::new() });
+        }
+        Some("merge") => {
+            args.remove(0);
+            opts.command = Command::Merge(MergeArgs {
+                files: Vec::new(),
+                output: None,
+            });
+        }
+        Some("status") => {
+            args.remove(0);
+            opts.command = Command::Status(StatusArgs {
+                starship: false,
+                polybar: false,
+                follow: false,
+            });
+        }
+        Some("press") => {
+            args.remove(0);
+            let name = args
+                .first()
+                .ok_or("press needs a button (toggle or split)")?;
+            let button = Press::from_name(name)
+                .ok_or_else(|| format!("unknown button '{}' (expected toggle or split)", name))?;
+            args.remove(0);
+            opts.command = Command::Press(button);
+        }
+        Some("run") => {
+            args.remove(0);
+            if args.is_empty() {
+                return Err("run needs a routine file".to_string());
+            }
+            routine = Some(PathBuf::from(args.remove(0)));
+        }
+        Some("reconcile") => {
+            args.remove(0);
+            opts.command = Command::Reconcile(ReconcileArgs { yes: false });
+        }
+        Some("check") => {
+            args.remove(0);
+            opts.command = Command::Check(CheckArgs { fix: false });
+        }
+        Some("replay") => {
+            args.remove(0);
+            opts.command = Command::Replay(ReplayArgs {
+                path: String::new(),
+                speed: replay::Speed::Times(1),
+            });
+        }
+        _ => {}
+    }
+
+    let explicit = args
+        .iter()
+        .position(|a| a == "--config")
+        .map(|i| {
+            args.get(i + 1)
+                .map(PathBuf::from)
+                .ok_or("--config needs a path")
+        })
+        .transpose()?;
+    let required = explicit.is_some();
+    if let Some(path) = explicit.or_else(config::default_path) {
+        if let Some(cfg) = config::load(path, required)? {
+            apply_config(&mut opts, &cfg)?;
+        }
+    }
+    if let Some(path) = routine {
+        opts.sequence = Some(sequence::routine(&path)?);
+    }
+
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        if let Command::Export(export) = &mut opts.command {
+            match arg.as_str() {
+                "--format" => {
+                    let value = args.next().ok_or("--format needs an export format")?;
+                    export.format = export::Format::from_name(&value)
+                        .ok_or_else(|| format!("unknown export format '{}'", value))?;
+                    continue;
+                }
+                "-o" | "--output" => {
+                    export.output = Some(args.next().ok_or("--output needs a path")?);
+                    continue;
+                }
+                "--group" => {
+                    let value = args.next().ok_or("--group needs tag or project")?;
+                    export.group = export::Group::from_name(&value).ok_or_else(|| {
+                        format!("unknown grouping '{}' (expected tag or project)", value)
+                    })?;
+                    continue;
+                }
+                #[cfg(feature = "plugins")]
+                "--plugin" => {
+                    export.plugin = Some(args.next().ok_or("--plugin needs a command")?);
+                    continue;
+                }
+                "--profile" => {
+                    let value = args.next().ok_or("--profile needs org or iso")?;
+                    export.profile = export::Profile::from_name(&value).ok_or_else(|| {
+                        format!("unknown export profile '{}' (expected org or iso)", value)
+                    })?;
+                    continue;
+                }
+                "--session" => {
+                    let value = args.next().ok_or("--session needs a number")?;
+                    let n = value
+                        .parse()
+                        .map_err(|_| format!("invalid session number '{}'", value))?;
+                    export.session = Some(n);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if let Command::Import(import) = &mut opts.command {
+            match arg.as_str() {
+                "--into" => {
+                    opts.log_file = args.next().ok_or("--into needs the log to import into")?;
+                    continue;
+                }
+                path if !path.starts_with('-') => {
+                    import.files.push(arg);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if let Command::Merge(merge) = &mut opts.command {
+            match arg.as_str() {
+                "-o" | "--output" => {
+                    merge.output = Some(args.next().ok_or("--output needs a path")?);
+                    continue;
+                }
+                path if !path.starts_with('-') => {
+                    merge.files.push(arg);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if let Command::Reconcile(reconcile) = &mut opts.command {
+            if matches!(arg.as_str(), "-y" | "--yes") {
+                reconcile.yes = true;
+                continue;
+            }
+        }
+        if let Command::Status(status) = &mut opts.command {
+            match arg.as_str() {
+                "--starship" => {
+                    status.starship = true;
+                    continue;
+                }
+                "--polybar" => {
+                    status.polybar = true;
+                    continue;
+                }
+                "--follow" => {
+                    status.follow = true;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if let Command::Check(check) = &mut opts.command {
+            if arg == "--fix" {
+                check.fix = true;
+                continue;
+            }
+        }
+        if let Command::Report(report) = &mut opts.command {
+            match arg.as_str() {
+                "--weeks" => {
+                    let value = args.next().ok_or("--weeks needs a number")?;
+                    report.weeks = value
+                        .parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("invalid number of weeks '{}'", value))?;
+                    continue;
+                }
+                "--tag" => {
+                    report.tag = Some(args.next().ok_or("--tag needs a tag")?);
+                    continue;
+                }
+                "--goal" => {
+                    report.goal = Some(args.next().ok_or("--goal needs a pattern")?);
+                    continue;
+                }
+                "--min-duration" => {
+                    let value = args.next().ok_or("--min-duration needs a duration")?;
+                    report.min_duration = Some(parse_duration(&value).map_err(|e| e.to_string())?);
+                    continue;
+                }
+                "--by" | "--group-by" => {
+                    let value = args.next().ok_or("--by needs day, goal or tag")?;
+                    report.by = Some(report::GroupBy::from_name(&value).ok_or_else(|| {
+                        format!("unknown grouping '{}' (expected day, goal or tag)", value)
+                    })?);
+                    continue;
+                }
+                "--format" => {
+                    let value = args.next().ok_or("--format needs text, csv or json")?;
+                    report.format = report::Format::from_name(&value).ok_or_else(|| {
+                        format!(
+                            "unknown report format '{}' (expected text, csv or json)",
+                            value
+                        )
+                    })?;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if let Command::Stress(stress) = &mut opts.command {
+            match arg.as_str() {
+                "--splits" => {
+                    let value = args.next().ok_or("--splits needs a count")?;
+                    stress.splits = value
+                        .parse()
+                        .map_err(|_| format!("invalid split count '{}'", value))?;
+                    continue;
+                }
+                "--ticks" => {
+                    let value = args.next().ok_or("--ticks needs a count")?;
+                    stress.ticks = value
+                        .parse()
+                        .map_err(|_| format!("invalid tick count '{}'", value))?;
+                    continue;
+                }
+                "--max-tick" => {
+                    let value = args.next().ok_or("--max-tick needs a duration")?;
+                    stress.max_tick = Some(parse_duration(&value).map_err(|e| e.to_string())?);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if let Command::Replay(replay) = &mut opts.command {
+            match arg.as_str() {
+                "--speed" => {
+                    let value = args.next().ok_or("--speed needs 1, 5 or max")?;
+                    replay.speed = replay::Speed::from_name(&value)
+                        .ok_or_else(|| format!("invalid replay speed '{}'", value))?;
+                    continue;
+                }
+                path if replay.path.is_empty() && !path.starts_with('-') => {
+                    replay.path = arg;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        match arg.as_str() {
+            "--config" => {
+                args.next();
+            }
+            "--autosave" => opts.autosave = true,
+            "-q" | "--quiet" => opts.quiet = true,
+            "-v" | "--verbose" => opts.verbose += 1,
+            "-vv" => opts.verbose += 2,
+            "-vvv" => opts.verbose += 3,
+            "--debug-log" => {
+                opts.debug_log = Some(args.next().ok_or("--debug-log needs a path")?.into());
+            }
+            "--journal" => opts.journal = Some(args.next().ok_or("--journal needs a path")?),
+            "--share" => opts.share = Some(args.next().ok_or("--share needs an address")?),
+            "--follow" => opts.command = Command::Follow(args.next_if(|a| !a.starts_with('-'))),
+            "--stress" => {
+                opts.command = Command::Stress(StressArgs {
+                    splits: 500,
+                    ticks: 2000,
+                    max_tick: None,
+                })
+            }
+            "--http" => opts.http = Some(args.next().ok_or("--http needs an address")?),
+            #[cfg(feature = "midi")]
+            "--midi" => opts.midi = Some(args.next().ok_or("--midi needs a device path")?.into()),
+            #[cfg(feature = "midi")]
+            "--midi-map" => {
+                opts.midi_map = midi::parse_map(&args.next().ok_or("--midi-map needs bindings")?)?
+            }
+            #[cfg(not(feature = "midi"))]
+            "--midi" | "--midi-map" => {
+                return Err("built without MIDI support (enable the 'midi' feature)".to_string())
+            }
+            #[cfg(feature = "plugins")]
+            "--plugin" => opts
+                .plugins
+                .push(args.next().ok_or("--plugin needs a command")?),
+            #[cfg(not(feature = "plugins"))]
+            "--plugin" => {
+                return Err(
+                    "built without plugin support (enable the 'plugins' feature)".to_string(),
+                )
+            }
+            "--state-file" => {
+                opts.state_file = Some(args.next().ok_or("--state-file needs a path")?.into())
+            }
+            "--inhibit-sleep" => opts.inhibit_sleep = true,
+            "--git-splits" => {
+                opts.git_splits = Some(
+                    args.next_if(|a| !a.starts_with('-'))
+                        .unwrap_or_else(|| ".".to_string())
+                        .into(),
+                )
+            }
+            "--watch" => opts
+                .watch
+                .push(args.next().ok_or("--watch needs a path")?.into()),
+            "--split-on-line" => {
+                opts.split_on_line = Some(args.next().ok_or("--split-on-line needs a pattern")?)
+            }
+            #[cfg(feature = "window")]
+            "--focus" => {
+                opts.focus = Some(parse_follow(
+                    &args.next().ok_or("--focus needs split or note")?,
+                )?)
+            }
+            #[cfg(not(feature = "window"))]
+            "--focus" => {
+                return Err(
+                    "built without window following (enable the 'window' feature)".to_string(),
+                )
+            }
+            "--archive" => {
+                opts.archive_dir = Some(args.next().ok_or("--archive needs a directory")?.into())
+            }
+            "--archive-only" => opts.archive_only = true,
+            "--compress-after" => {
+                opts.compress_after = Some(parse_days(
+                    &args
+                        .next()
+                        .ok_or("--compress-after needs a number of days")?,
+                )?)
+            }
+            "--compression" => {
+                opts.compression =
+                    parse_codec(&args.next().ok_or("--compression needs gzip or zstd")?)?
+            }
+            "--hook" => {
+                let value = args.next().ok_or("--hook needs EVENT=COMMAND")?;
+                let (event, command) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid hook '{}' (expected EVENT=COMMAND)", value))?;
+                opts.hooks
+                    .commands
+                    .push((parse_hook_event(event)?, command.to_string()));
+            }
+            "--jsonl" => opts.jsonl_log = Some(args.next().ok_or("--jsonl needs a path")?),
+            "--encrypt-to" => opts
+                .crypt
+                .recipients
+                .push(args.next().ok_or("--encrypt-to needs a public key")?),
+            "--git-commit" => opts.git_commit = true,
+            "--commit-message" => {
+                opts.commit_message = args.next().ok_or("--commit-message needs a message")?
+            }
+            "--backup" => {
+                opts.backup = Some(Backup::parse(&args.next().ok_or("--backup needs a URL")?)?)
+            }
+            "--s3-endpoint" => {
+                opts.s3_endpoint = Some(args.next().ok_or("--s3-endpoint needs a URL")?)
+            }
+            "--identity" => {
+                opts.crypt.identity = Some(args.next().ok_or("--identity needs a key file")?.into())
+            }
+            "--merge-headings" => opts.merge_headings = true,
+            "--under" => {
+                opts.log_parent =
+                    parse_heading_path(&args.next().ok_or("--under needs a heading path")?)
+            }
+            "--project" => opts.project = Some(args.next().ok_or("--project needs a name")?),
+            "--goal" => opts.goal = Some(args.next().ok_or("--goal needs a name")?),
+            "--lang" => {
+                let value = args.next().ok_or("--lang needs a language")?;
+                opts.lang = parse_lang(&value)?;
+            }
+            "--rate" => {
+                let value = args.next().ok_or("--rate needs an hourly rate")?;
+                opts.rates.default = Some(billing::parse_rate(&value)?);
+            }
+            "--currency" => opts.rates.currency = args.next().ok_or("--currency needs a symbol")?,
+            "--chord-timeout" => {
+                let value = args.next().ok_or("--chord-timeout needs a duration")?;
+                opts.keys.timeout = parse_duration(&value).map_err(|e| e.to_string())?;
+            }
+            "--obs-file" => {
+                opts.obs_file = Some(args.next().ok_or("--obs-file needs a path")?.into())
+            }
+            "--obs-interval" => {
+                let value = args.next().ok_or("--obs-interval needs a duration")?;
+                opts.obs_interval = parse_duration(&value).map_err(|e| e.to_string())?;
+            }
+            "--obs-split" => opts.obs_split = true,
+            "--title" => opts.title = true,
+            "--inline" => opts.inline = true,
+            "--no-spinner" => opts.spinner = false,
+            "--low-power" => opts.set_low_power(),
+            "--no-color" => opts.color = false,
+            "--compact" => opts.compact = true,
+            "--private" => opts.private = true,
+            "--demo" => opts.demo = true,
+            "--simulate" => opts.simulate = true,
+            "--input-script" => {
+                let path = args.next().ok_or("--input-script needs a file")?;
+                opts.input_script = Some(script::load(path.as_ref())?);
+            }
+            "--trim-zeros" => opts.format.trim_zeros = true,
+            "--target" => {
+                let value = args.next().ok_or("--target needs a duration")?;
+                opts.target = Some(parse_duration(&value).map_err(|e| e.to_string())?);
+            }
+            "--lead-in" => {
+                let value = args.next().ok_or("--lead-in needs a duration")?;
+                opts.lead_in = Some(parse_duration(&value).map_err(|e| e.to_string())?);
+            }
+            "--max-duration" => {
+                let value = args.next().ok_or("--max-duration needs a duration")?;
+                opts.max_duration = Some(parse_duration(&value).map_err(|e| e.to_string())?);
+            }
+            "--alert" => {
+                let value = args.next().ok_or("--alert needs durations")?;
+                opts.alerts.extend(parse_alerts(&value)?);
+            }
+            "--start-at" => {
+                let value = args.next().ok_or("--start-at needs a time of day")?;
+                opts.start_at = Some(parse_time_of_day(&value)?);
+            }
+            "--sequence" => {
+                let path = args.next().ok_or("--sequence needs a file")?;
+                opts.sequence = Some(Sequence::load(path.as_ref())?);
+            }
+            "--format" | "--time-format" => {
+                let value = args.next().ok_or("--format needs a style")?;
+                opts.format.style = parse_style(&value)?;
+            }
+            "--round" => {
+                let value = args
+                    .next()
+                    .ok_or("--round needs a step such as 15m or up:15m")?;
+                opts.rounding = Rounding::parse(&value)?;
+            }
+            "--precision" => {
+                let value = args.next().ok_or("--precision needs ms, cs, frames or s")?;
+                opts.format.precision = parse_precision(&value)?;
+            }
+            "--fps" => {
+                let value = args.next().ok_or("--fps needs a frame rate")?;
+                opts.format.fps = parse_fps(value.parse().unwrap_or(0))?;
+            }
+            "--clipboard" => {
+                let value = args
+                    .next()
+                    .ok_or("--clipboard needs auto, system or osc52")?;
+                opts.clipboard = parse_clipboard(&value)?;
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option '{}'", arg))
+            }
+            _ => opts.log_file = arg,
+        }
+    }
+    if let Command::Import(import) = &opts.command {
+        if import.files.is_empty() {
+            return Err("import needs org or CSV files to read".to_string());
+        }
+    }
+    if let Command::Merge(merge) = &opts.command {
+        if merge.files.is_empty() {
+            return Err("merge needs org files to combine".to_string());
+        }
+    }
+    if let Command::Replay(replay) = &opts.command {
+        if replay.path.is_empty() {
+            return Err("replay needs a journal file".to_string());
+        }
+    }
+    opts.alerts.sort();
+    opts.alerts.dedup();
+    if opts.split_on_line.is_some() && io::stdin().is_terminal() {
+        return Err("--split-on-line reads piped input, but nothing is piped in".to_string());
+    }
+    if opts.archive_only && opts.archive_dir.is_none() {
+        return Err("--archive-only needs an archive directory (--archive DIR)".to_string());
+    }
+    if opts.git_commit && opts.archive_only {
+        return Err("--git-commit commits the log, which --archive-only leaves alone".to_string());
+    }
+    if opts.compress_after.is_some() && opts.archive_dir.is_none() {
+        return Err("--compress-after needs an archive directory (--archive DIR)".to_string());
+    }
+    Ok(opts)
+}
+
+/// `Work / 2024 / Time log`, outermost first.
+fn parse_heading_path(spec: &str) -> Vec<String> {
+    spec.split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `30m, 1h, 2h`.
+fn parse_alerts(list: &str) -> Result<Vec<Duration>, String> {
+    list.split(',')
+        .map(|mark| parse_duration(mark.trim()).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// `09:00` or `09:00:30`.
+fn parse_time_of_day(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+        .map_err(|_| format!("invalid time of day '{}' (expected HH:MM or HH:MM:SS)", s))
+}
+
+fn parse_lang(name: &str) -> Result<Lang, String> {
+    Lang::from_name(name).ok_or_else(|| format!("unknown language '{}' (expected en or es)", name))
+}
+
+fn parse_style(name: &str) -> Result<Style, String> {
+    Style::from_name(name).ok_or_else(|| {
+        format!(
+            "unknown duration format '{}' (expected clock, human, decimal or iso)",
+            name
+        )
+    })
+}
+
+fn parse_hook_event(name: &str) -> Result<hooks::Event, String> {
+    hooks::Event::from_name(name).ok_or_else(|| {
+        format!(
+            "unknown hook event '{}' (expected start, stop, split_start, split_end, save, milestone or focus)",
+            name
+        )
+    })
+}
+
+#[cfg(feature = "window")]
+fn parse_follow(name: &str) -> Result<Follow, String> {
+    Follow::from_name(name)
+        .ok_or_else(|| format!("unknown focus action '{}' (expected split or note)", name))
+}
+
+/// Days before archived sessions are compressed, up to a hundred years.
+fn parse_days(value: &str) -> Result<u64, String> {
+    const MAX_DAYS: u64 = 36_500;
+    let days: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid number of days '{}'", value))?;
+    if days > MAX_DAYS {
+        return Err(format!("{} days is too long (at most {})", days, MAX_DAYS));
+    }
+    Ok(days)
+}
+
+fn parse_codec(name: &str) -> Result<Codec, String> {
+    Codec::from_name(name)
+        .ok_or_else(|| format!("unknown compression '{}' (expected gzip or zstd)", name))
+}
+
+fn config_rate(cfg: &Config, entry: &config::Entry) -> Result<f64, String> {
+    let rate = cfg.number(entry)?;
+    billing::parse_rate(&rate.to_string()).map_err(|e| cfg.error(entry, &e))
+}
+
+fn parse_precision(name: &str) -> Result<Precision, String> {
+    Precision::from_name(name).ok_or_else(|| {
+        format!(
+            "unknown precision '{}' (expected ms, cs, frames or s)",
+            name
+        )
+    })
+}
+
+fn parse_fps(fps: i64) -> Result<u32, String> {
+    match fps {
+        1..=1000 => Ok(fps as u32),
+        _ => Err("fps must be a whole number between 1 and 1000".to_string()),
+    }
+}
+
+fn parse_clipboard(name: &str) -> Result<clipboard::Mode, String> {
+    clipboard::Mode::from_name(name).ok_or_else(|| {
+        format!(
+            "unknown clipboard mode '{}' (expected auto, system or osc52)",
+            name
+        )
+    })
+}
+
+fn apply_config(opts: &mut Options, cfg: &Config) -> Result<(), String> {
+    for entry in &cfg.entries {
+        match entry.key.as_str() {
+            "log_file" => opts.log_file = cfg.string(entry)?,
+            "autosave" => opts.autosave = cfg.bool(entry)?,
+            "title" => opts.title = cfg.bool(entry)?,
+            "spinner" => opts.spinner = cfg.bool(entry)?,
+            "low_power" => {
+                if cfg.bool(entry)? {
+                    opts.set_low_power();
+                }
+            }
+            "color" => opts.color = cfg.bool(entry)?,
+            "compact" => opts.compact = cfg.bool(entry)?,
+            "private" => opts.private = cfg.bool(entry)?,
+            "inline" => opts.inline = cfg.bool(entry)?,
+            "target" => {
+                let value = cfg.string(entry)?;
+                let target =
+                    parse_duration(&value).map_err(|e| cfg.error(entry, &e.to_string()))?;
+                opts.target = Some(target);
+            }
+            "lead_in" => {
+                let value = cfg.string(entry)?;
+                let lead_in =
+                    parse_duration(&value).map_err(|e| cfg.error(entry, &e.to_string()))?;
+                opts.lead_in = Some(lead_in);
+            }
+            "max_duration" => {
+                let value = cfg.string(entry)?;
+                let max = parse_duration(&value).map_err(|e| cfg.error(entry, &e.to_string()))?;
+                opts.max_duration = Some(max);
+            }
+            "alerts" => {
+                opts.alerts = parse_alerts(&cfg.string(entry)?).map_err(|e| cfg.error(entry, &e))?
+            }
+            "sequence" => {
+                let path = PathBuf::from(cfg.string(entry)?);
+                opts.sequence = Some(Sequence::load(&path).map_err(|e| cfg.error(entry, &e))?);
+            }
+            "format" => {
+                let value = cfg.string(entry)?;
+                opts.format.style = parse_style(&value).map_err(|e| cfg.error(entry, &e))?;
+            }
+            "trim_leading_zeros" => opts.format.trim_zeros = cfg.bool(entry)?,
+            "round" => {
+                let value = cfg.string(entry)?;
+                opts.rounding = Rounding::parse(&value).map_err(|e| cfg.error(entry, &e))?;
+            }
+            "precision" => {
+                let value = cfg.string(entry)?;
+                opts.format.precision =
+                    parse_precision(&value).map_err(|e| cfg.error(entry, &e))?;
+            }
+            "fps" => {
+                opts.format.fps = parse_fps(cfg.int(entry)?).map_err(|e| cfg.error(entry, &e))?
+            }
+            "quiet" => opts.quiet = cfg.bool(entry)?,
+            "verbose" => opts.verbose = cfg.int(entry)?.clamp(0, 3) as u8,
+            "debug_log" => opts.debug_log = Some(cfg.string(entry)?.into()),
+            "journal" => opts.journal = Some(cfg.string(entry)?),
+            "share" => opts.share = Some(cfg.string(entry)?),
+            "http" => opts.http = Some(cfg.string(entry)?),
+            #[cfg(feature = "midi")]
+            "midi" => opts.midi = Some(cfg.string(entry)?.into()),
+            #[cfg(feature = "midi")]
+            "midi_map" => {
+                opts.midi_map =
+                    midi::parse_map(&cfg.string(entry)?).map_err(|e| cfg.error(entry, &e))?
+            }
+            #[cfg(feature = "plugins")]
+            "plugin" => opts.plugins.push(cfg.string(entry)?),
+            "state_file" => opts.state_file = Some(cfg.string(entry)?.into()),
+            "git_splits" => opts.git_splits = Some(cfg.string(entry)?.into()),
+            "watch" => opts.watch.push(cfg.string(entry)?.into()),
+            #[cfg(feature = "window")]
+            "focus" => {
+                opts.focus =
+                    Some(parse_follow(&cfg.string(entry)?).map_err(|e| cfg.error(entry, &e))?)
+            }
+            "inhibit_sleep" => opts.inhibit_sleep = cfg.bool(entry)?,
+            "archive_dir" => opts.archive_dir = Some(cfg.string(entry)?.into()),
+            "archive_only" => opts.archive_only = cfg.bool(entry)?,
+            "compress_after" => {
+                opts.compress_after = Some(
+                    parse_days(&cfg.int(entry)?.to_string()).map_err(|e| cfg.error(entry, &e))?,
+                )
+            }
+            "compression" => {
+                opts.compression =
+                    parse_codec(&cfg.string(entry)?).map_err(|e| cfg.error(entry, &e))?
+            }
+            "jsonl_log" => opts.jsonl_log = Some(cfg.string(entry)?),
+            "encrypt_to" => opts.crypt.recipients.push(cfg.string(entry)?),
+            "identity" => opts.crypt.identity = Some(cfg.string(entry)?.into()),
+            "git_commit" => opts.git_commit = cfg.bool(entry)?,
+            "commit_message" => opts.commit_message = cfg.string(entry)?,
+            "backup" => {
+                opts.backup =
+                    Some(Backup::parse(&cfg.string(entry)?).map_err(|e| cfg.error(entry, &e))?)
+            }
+            "s3_endpoint" => opts.s3_endpoint = Some(cfg.string(entry)?),
+            "merge_headings" => opts.merge_headings = cfg.bool(entry)?,
+            "under" => opts.log_parent = parse_heading_path(&cfg.string(entry)?),
+            "project" => opts.project = Some(cfg.string(entry)?),
+            "goal" => opts.goal = Some(cfg.string(entry)?),
+            "language" => {
+                opts.lang = parse_lang(&cfg.string(entry)?).map_err(|e| cfg.error(entry, &e))?
+            }
+            "rate" => opts.rates.default = Some(config_rate(cfg, entry)?),
+            "currency" => opts.rates.currency = cfg.string(entry)?,
+            key if key.starts_with("rates.") => {
+                let rate = config_rate(cfg, entry)?;
+                opts.rates
+                    .tags
+                    .push((key["rates.".len()..].to_string(), rate));
+            }
+            "account" => opts.accounts.default = cfg.string(entry)?,
+            "unbilled_account" => opts.accounts.unbilled = cfg.string(entry)?,
+            key if key.starts_with("accounts.") => {
+                let account = cfg.string(entry)?;
+                opts.accounts
+                    .tags
+                    .push((key["accounts.".len()..].to_string(), account));
+            }
+            key if key.starts_with("hooks.") => {
+                let event =
+                    parse_hook_event(&key["hooks.".len()..]).map_err(|e| cfg.error(entry, &e))?;
+                opts.hooks.commands.push((event, cfg.string(entry)?));
+            }
+            "chord_timeout" => {
+                let value = cfg.string(entry)?;
+                opts.keys.timeout =
+                    parse_duration(&value).map_err(|e| cfg.error(entry, &e.to_string()))?;
+            }
+            key if key.starts_with("keys.") => {
+                let action = KeyAction::from_name(&key["keys.".len()..])
+                    .ok_or_else(|| cfg.error(entry, &format!("unknown key action '{}'", key)))?;
+                let keys =
+                    keys::parse_sequence(&cfg.string(entry)?).map_err(|e| cfg.error(entry, &e))?;
+                opts.keys.bind(keys, action);
+            }
+            "obs_file" => opts.obs_file = Some(cfg.string(entry)?.into()),
+            "obs_interval" => {
+                let value = cfg.string(entry)?;
+                opts.obs_interval =
+                    parse_duration(&value).map_err(|e| cfg.error(entry, &e.to_string()))?;
+            }
+            "obs_split" => opts.obs_split = cfg.bool(entry)?,
+            "clipboard" => {
+                let value = cfg.string(entry)?;
+                opts.clipboard = parse_clipboard(&value).map_err(|e| cfg.error(entry, &e))?;
+            }
+            other => return Err(cfg.error(entry, &format!("unknown setting '{}'", other))),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,423 @@
+use std::{fs, io, ops::Range, path::Path, time::Duration};
+
+use chrono::NaiveDateTime;
+
+use crate::compress;
+use crate::crypt::Crypt;
+use crate::duration::{parse_duration, DurationFormat};
+
+/// A heading read back from the org log, with all its CLOCK entries
+/// combined.
+pub struct Entry {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub duration: Duration,
+    /// From a `:PROJECT:` property, else the session's.
+    pub project: Option<String>,
+}
+
+/// One saved session: a top-level goal heading and the subgoal headings
+/// nested under it.
+pub struct Session {
+    pub goal: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub total: Duration,
+    pub splits: Vec<Entry>,
+    pub project: Option<String>,
+}
+
+impl Session {
+    /// The headings `save_log` would write for this session, the goal at
+    /// `stars`, with one CLOCK line each.
+    pub fn headings(&self, stars: usize, fmt: &DurationFormat) -> Vec<Heading> {
+        let clock = |start: NaiveDateTime, end: NaiveDateTime, dur: Duration| {
+            format!(
+                "[{}]--[{}] => {}",
+                start.format("%Y-%m-%d %H:%M"),
+                end.format("%Y-%m-%d %H:%M"),
+                fmt.format(dur)
+            )
+        };
+        let mut headings = vec![Heading {
+            stars,
+            title: self.goal.clone(),
+            project: self.project.clone(),
+            clock: clock(self.start, self.end, self.total),
+        }];
+        let mut depth: Vec<usize> = Vec::with_capacity(self.splits.len());
+        for split in &self.splits {
+            let level = split.parent.map_or(0, |p| depth[p] + 1);
+            depth.push(level);
+            headings.push(Heading {
+                stars: stars + level + 1,
+                title: split.name.clone(),
+                // only where it differs from what it would inherit
+                project: split
+                    .project
+                    .clone()
+                    .filter(|p| Some(p) != self.project.as_ref()),
+                clock: clock(split.start, split.end, split.duration),
+            });
+        }
+        headings
+    }
+}
+
+/// The sessions in the log at `path`. A directory, such as the archive, is
+/// read file by file in name order, which is also start order.
+pub fn read(path: &str, crypt: &Crypt) -> io::Result<Vec<Session>> {
+    let path = Path::new(path);
+    if !path.is_dir() {
+        return Ok(parse(&load(path, crypt)?));
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let file = entry?.path();
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        // x.org, and x.org.gz, x.org.zst or x.org.age
+        if name.ends_with(".org") || name.contains(".org.") {
+            files.push(file);
+        }
+    }
+    files.sort();
+    let mut sessions = Vec::new();
+    for file in files {
+        let text = load(&file, crypt)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file.display(), e)))?;
+        sessions.extend(parse(&text));
+    }
+    Ok(sessions)
+}
+
+/// The text of the log at `path`, decrypted and uncompressed.
+pub fn load(path: &Path, crypt: &Crypt) -> io::Result<String> {
+    let contents = compress::decompress(crypt.open(fs::read(path)?)?)?;
+    String::from_utf8(contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// A heading and its clocked time, if any.
+struct Node {
+    stars: usize,
+    title: String,
+    project: Option<String>,
+    clock: Option<(NaiveDateTime, NaiveDateTime, Duration)>,
+}
+
+/// Parse the sessions in an org file, as written by `save_log` or by hand
+/// in Emacs. A heading clocked more than once counts from its first start
+/// to its last end, with the durations added up. Headings without a closed
+/// CLOCK line are skipped; the first clocked heading outside a session
+/// starts one, so goals saved under a parent heading are found at any
+/// depth.
+pub fn parse(text: &str) -> Vec<Session> {
+    let mut nodes: Vec<Node> = Vec::new();
+    for line in text.lines() {
+        if let Some((stars, title)) = parse_heading(line) {
+            nodes.push(Node {
+                stars,
+                title: strip_keywords(title).to_string(),
+                project: None,
+                clock: None,
+            });
+            continue;
+        }
+        let Some(node) = nodes.last_mut() else {
+            continue;
+        };
+        if let Some(value) = line.trim().strip_prefix(":PROJECT:") {
+            node.project = Some(value.trim().to_string()).filter(|p| !p.is_empty());
+            continue;
+        }
+        let Some((start, end, duration)) = line.trim().strip_prefix("CLOCK:").and_then(parse_clock)
+        else {
+            continue;
+        };
+        node.clock = Some(match node.clock {
+            Some((s, e, d)) => (s.min(start), e.max(end), d + duration),
+            None => (start, end, duration),
+        });
+    }
+
+    let mut sessions: Vec<Session> = Vec::new();
+    // stars of the current session's heading
+    let mut base: Option<usize> = None;
+    // parent candidates for the current session, indexed by level
+    let mut stack: Vec<usize> = Vec::new();
+    for node in nodes {
+        if base.is_some_and(|base| node.stars <= base) {
+            base = None;
+        }
+        let Some((start, end, duration)) = node.clock else {
+            continue;
+        };
+        let Some(base) = base else {
+            base = Some(node.stars);
+            sessions.push(Session {
+                goal: node.title,
+                start,
+                end,
+                total: duration,
+                splits: Vec::new(),
+                project: node.project,
+            });
+            stack.clear();
+            continue;
+        };
+        let Some(session) = sessions.last_mut() else {
+            continue;
+        };
+        let level = node.stars - base - 1;
+        stack.truncate(level);
+        let parent = stack.last().copied();
+        session.splits.push(Entry {
+            name: node.title,
+            parent,
+            start,
+            end,
+            duration,
+            project: node.project.or_else(|| session.project.clone()),
+        });
+        stack.push(session.splits.len() - 1);
+    }
+    sessions
+}
+
+/// A heading as `save_log` writes it, with one CLOCK entry.
+pub struct Heading {
+    pub stars: usize,
+    pub title: String,
+    pub project: Option<String>,
+    /// `[start]--[end] => duration`
+    pub clock: String,
+}
+
+impl Heading {
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} {}", "*".repeat(self.stars), self.title)];
+        if let Some(project) = &self.project {
+            lines.push("  :PROPERTIES:".to_string());
+            lines.push(format!("  :PROJECT: {}", project));
+            lines.push("  :END:".to_string());
+        }
+        lines.push("  :LOGBOOK:".to_string());
+        lines.push(self.clock_line());
+        lines.push("  :END:".to_string());
+        lines.push(String::new());
+        lines
+    }
+
+    fn clock_line(&self) -> String {
+        format!("  CLOCK: {}", self.clock)
+    }
+}
+
+/// The text for `headings`, each followed by a blank line.
+pub fn render(headings: &[Heading]) -> String {
+    let mut out = String::new();
+    for heading in headings {
+        for line in heading.lines() {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Add `headings` (a goal and its subgoals) to `text` under the heading
+/// path `parents`, creating any part of the path that is missing. The
+/// headings' stars must already count the parents. With `merge`, a heading
+/// already there under the same parent gets the new CLOCK line at the top of
+/// its LOGBOOK, as org-mode does when clocking in again; the rest are added
+/// at the end of their parent's subtree.
+pub fn insert(text: &str, parents: &[String], headings: &[Heading], merge: bool) -> String {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    // line index of the heading matched or added at each level
+    let mut path: Vec<usize> = Vec::new();
+    for title in parents {
+        let stars = path.len() + 1;
+        let range = children(&lines, &path);
+        let at = find(&lines, range.clone(), stars, title).unwrap_or_else(|| {
+            lines.insert(range.end, format!("{} {}", "*".repeat(stars), title));
+            range.end
+        });
+        path.push(at);
+    }
+    for heading in headings {
+        path.truncate(heading.stars - 1);
+        let range = children(&lines, &path);
+        let existing = find(&lines, range.clone(), heading.stars, &heading.title).filter(|_| merge);
+        let at = match existing {
+            Some(i) => {
+                add_clock(&mut lines, i, heading);
+                i
+            }
+            None => {
+                lines.splice(range.end..range.end, heading.lines());
+                range.end
+            }
+        };
+        path.push(at);
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// `text` without the session `goal` that starts at `start`: the first
+/// heading of that title whose earliest CLOCK line starts then, with
+/// everything under it.
+pub fn remove(text: &str, goal: &str, start: NaiveDateTime) -> String {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let found = (0..lines.len()).find(|&i| {
+        let Some((_, title)) = parse_heading(&lines[i]) else {
+            return false;
+        };
+        let body_end = (i + 1..lines.len())
+            .find(|&j| parse_heading(&lines[j]).is_some())
+            .unwrap_or(lines.len());
+        let first = lines[i + 1..body_end]
+            .iter()
+            .filter_map(|l| l.trim().strip_prefix("CLOCK:").and_then(parse_clock))
+            .map(|(s, _, _)| s)
+            .min();
+        strip_keywords(title) == goal && first == Some(start)
+    });
+    if let Some(i) = found {
+        let end = subtree_end(&lines, i);
+        lines.drain(i..end);
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// The lines below the last heading of `path`, or the whole file.
+fn children(lines: &[String], path: &[usize]) -> Range<usize> {
+    match path.last() {
+        Some(&parent) => parent + 1..subtree_end(lines, parent),
+        None => 0..lines.len(),
+    }
+}
+
+/// The last heading in `range` with these stars and title.
+fn find(lines: &[String], range: Range<usize>, stars: usize, title: &str) -> Option<usize> {
+    range
+        .rev()
+        .find(|&i| parse_heading(&lines[i]) == Some((stars, title)))
+}
+
+/// The line after the subtree of the heading at `idx`.
+fn subtree_end(lines: &[String], idx: usize) -> usize {
+    let stars = parse_heading(&lines[idx]).map_or(0, |(stars, _)| stars);
+    (idx + 1..lines.len())
+        .find(|&i| parse_heading(&lines[i]).is_some_and(|(s, _)| s <= stars))
+        .unwrap_or(lines.len())
+}
+
+/// Put the heading's CLOCK line first in the LOGBOOK of the heading at
+/// `idx`, creating the drawer after any property drawer if there is none.
+fn add_clock(lines: &mut Vec<String>, idx: usize, heading: &Heading) {
+    let body_end = (idx + 1..lines.len())
+        .find(|&i| parse_heading(&lines[i]).is_some())
+        .unwrap_or(lines.len());
+    if let Some(drawer) = (idx + 1..body_end).find(|&i| lines[i].trim() == ":LOGBOOK:") {
+        lines.insert(drawer + 1, heading.clock_line());
+        return;
+    }
+    let mut at = idx + 1;
+    if lines.get(at).is_some_and(|l| l.trim() == ":PROPERTIES:") {
+        if let Some(end) = (at..body_end).find(|&i| lines[i].trim() == ":END:") {
+            at = end + 1;
+        }
+    }
+    let drawer = [
+        "  :LOGBOOK:".to_string(),
+        heading.clock_line(),
+        "  :END:".to_string(),
+    ];
+    lines.splice(at..at, drawer);
+}
+
+/// The stars and title of a heading line.
+pub fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let stars = line.len() - line.trim_start_matches('*').len();
+    if stars == 0 {
+        return None;
+    }
+    let title = line[stars..].strip_prefix(' ')?;
+    Some((stars, title.trim()))
+}
+
+/// The title without a leading TODO keyword or priority cookie.
+fn strip_keywords(title: &str) -> &str {
+    const KEYWORDS: &[&str] = &[
+        "TODO",
+        "NEXT",
+        "STARTED",
+        "WAITING",
+        "HOLD",
+        "DONE",
+        "CANCELLED",
+        "CANCELED",
+    ];
+    let mut title = title;
+    if let Some((first, rest)) = title.split_once(' ') {
+        if KEYWORDS.contains(&first) {
+            title = rest.trim_start();
+        }
+    }
+    if let Some(rest) = title.strip_prefix("[#") {
+        if let Some((_, rest)) = rest.split_once("] ") {
+            title = rest.trim_start();
+        }
+    }
+    title
+}
+
+/// A CLOCK duration: org's own `1:05` (hours and minutes, maybe after
+/// `2d`), or anything `parse_duration` reads.
+fn parse_clock_duration(s: &str) -> Option<Duration> {
+    let days = s
+        .split_once(' ')
+        .and_then(|(d, hm)| Some((d.strip_suffix('d')?.parse::<u64>().ok()?, hm.trim())));
+    let (days, hm) = days.unwrap_or((0, s));
+    let org = hm.split_once(':').and_then(|(h, m)| {
+        let two_digits = m.len() == 2 && m.bytes().all(|b| b.is_ascii_digit());
+        let (h, m) = (h.parse::<u64>().ok()?, m.parse::<u64>().ok()?);
+        (two_digits && m < 60).then(|| Duration::from_secs(((days * 24 + h) * 60 + m) * 60))
+    });
+    match org {
+        Some(d) => Some(d),
+        None if days == 0 => parse_duration(s).ok(),
+        None => None,
+    }
+}
+
+/// `[start]--[end] => duration`. The duration is taken as written when it
+/// parses, otherwise it is derived from the timestamps.
+pub fn parse_clock(clock: &str) -> Option<(NaiveDateTime, NaiveDateTime, Duration)> {
+    let (range, written) = match clock.split_once("=>") {
+        Some((range, dur)) => (range, Some(dur.trim())),
+        None => (clock, None),
+    };
+    let (start, end) = range.trim().split_once("--")?;
+    let start = parse_timestamp(start)?;
+    let end = parse_timestamp(end)?;
+    let duration = written
+        .and_then(parse_clock_duration)
+        .or_else(|| (end - start).to_std().ok())
+        .unwrap_or_default();
+    Some((start, end, duration))
+}
+
+/// `[2024-01-31 10:00]`, also accepting org's `[2024-01-31 Wed 10:00]`.
+pub fn parse_timestamp(s: &str) -> Option<NaiveDateTime> {
+    let inner = s.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let mut parts = inner.split_whitespace();
+    let date = parts.next()?;
+    let time = parts.find(|p| p.contains(':'))?;
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").ok()
+}
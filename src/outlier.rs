@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+/// A finished split that took longer, or less time, than its siblings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outlier {
+    Longest,
+    Shortest,
+}
+
+/// Siblings needed before their outliers stand out.
+const MIN_GROUP: usize = 3;
+
+/// The outlier mark for each split, given its parent and, once finished,
+/// its duration. Splits are only compared with their finished siblings, so
+/// a parent is never measured against its own subgoals.
+pub fn outliers(splits: &[(Option<usize>, Option<Duration>)]) -> Vec<Option<Outlier>> {
+    let mut marks = vec![None; splits.len()];
+    let mut parents: Vec<Option<usize>> = splits.iter().map(|&(parent, _)| parent).collect();
+    parents.sort_unstable();
+    parents.dedup();
+    for parent in parents {
+        let group: Vec<(usize, Duration)> = splits
+            .iter()
+            .enumerate()
+            .filter(|(_, &(p, _))| p == parent)
+            .filter_map(|(i, &(_, duration))| Some((i, duration?)))
+            .collect();
+        if group.len() < MIN_GROUP {
+            continue;
+        }
+        let longest = group.iter().max_by_key(|&&(_, d)| d);
+        let shortest = group.iter().min_by_key(|&&(_, d)| d);
+        if let (Some(&(long, max)), Some(&(short, min))) = (longest, shortest) {
+            // all the same length; nothing stands out
+            if max > min {
+                marks[long] = Some(Outlier::Longest);
+                marks[short] = Some(Outlier::Shortest);
+            }
+        }
+    }
+    marks
+}
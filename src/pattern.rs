@@ -0,0 +1,51 @@
+/// Whether `name` matches `pattern`, where `*` stands for any run of
+/// characters and `?` for any one.
+pub fn glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // where the last `*` was and how much of the name it has taken
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// What the first `*` of `pattern` took when `text` matches it, as little
+/// as it could, or all of `text` when the pattern has no `*`.
+pub fn capture(pattern: &str, text: &str) -> Option<String> {
+    let Some(star) = pattern.find('*') else {
+        return glob(pattern, text).then(|| text.to_string());
+    };
+    let (head, rest) = (&pattern[..star], &pattern[star + 1..]);
+    // no `*` in the head, so it takes exactly one character per character
+    let len = head.chars().count();
+    let split = text.char_indices().nth(len).map_or(text.len(), |(i, _)| i);
+    if text[..split].chars().count() < len || !glob(head, &text[..split]) {
+        return None;
+    }
+    let tail = &text[split..];
+    tail.char_indices()
+        .map(|(i, _)| i)
+        .chain([tail.len()])
+        .find(|&end| glob(rest, &tail[end..]))
+        .map(|end| tail[..end].to_string())
+}
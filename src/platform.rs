@@ -0,0 +1,229 @@
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    net::{Ipv4Addr, SocketAddrV4, UdpSocket},
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::Press;
+
+/// Where config goes when `$XDG_CONFIG_HOME` isn't set: `~/.config`, or
+/// `%APPDATA%` on Windows.
+pub fn config_home() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("HOME").map(|h| PathBuf::from(h).join(".config"))
+    }
+}
+
+/// Where state and logs go when `$XDG_STATE_HOME` isn't set:
+/// `~/.local/state`, or `%LOCALAPPDATA%` on Windows.
+pub fn state_home() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("state"))
+    }
+}
+
+/// Whether the terminal understands escape sequences that crossterm has no
+/// command for, like the title stack and OSC 52. Windows Terminal does; the
+/// legacy console prints them.
+#[cfg(windows)]
+pub fn ansi() -> bool {
+    crossterm::ansi_support::supports_ansi()
+}
+
+#[cfg(not(windows))]
+pub fn ansi() -> bool {
+    true
+}
+
+/// The terminal, for reading what is typed while stdin is piped:
+/// `/dev/tty`, or `CONIN$` on Windows.
+pub fn tty() -> io::Result<File> {
+    let path = if cfg!(windows) { "CONIN$" } else { "/dev/tty" };
+    OpenOptions::new().read(true).open(path)
+}
+
+/// `command` run by the shell: `sh -c`, or `cmd /C` on Windows.
+#[cfg(unix)]
+pub fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+pub fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// What `program` prints for `input` on its stdin, or what it said on
+/// stderr when it fails.
+pub fn filter(program: &str, args: &[String], input: &[u8]) -> io::Result<Vec<u8>> {
+    let mut command = Command::new(program);
+    command.args(args);
+    feed(command, program, input)
+}
+
+/// What the shell `command` prints for `input`, as `filter` runs a program.
+#[cfg(feature = "plugins")]
+pub fn shell_filter(command: &str, input: &[u8]) -> io::Result<Vec<u8>> {
+    feed(shell(command), command, input)
+}
+
+fn feed(mut command: Command, program: &str, input: &[u8]) -> io::Result<Vec<u8>> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_vec();
+    // fed from a thread so a large log can't fill both pipes
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    // a program stops reading when it fails, which breaks the pipe; its own
+    // error says more
+    let written = writer.join().unwrap_or(Ok(()));
+    if !output.status.success() {
+        // most name themselves in their messages
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(match stderr.trim() {
+            "" => format!("{} failed", program),
+            stderr => stderr.to_string(),
+        }));
+    }
+    written?;
+    Ok(output.stdout)
+}
+
+#[cfg(unix)]
+pub fn alive(pid: u32) -> bool {
+    match process(pid) {
+        Some(pid) => unsafe { libc::kill(pid, 0) == 0 },
+        None => false,
+    }
+}
+
+/// `pid` as a single process to signal; `kill` takes 0 and negative ids
+/// as process groups.
+#[cfg(unix)]
+fn process(pid: u32) -> Option<libc::pid_t> {
+    libc::pid_t::try_from(pid).ok().filter(|&pid| pid > 0)
+}
+
+#[cfg(windows)]
+pub fn alive(pid: u32) -> bool {
+    // there is no way to ask without the Win32 API, short of asking tasklist
+    let filter = format!("PID eq {}", pid);
+    match Command::new("tasklist")
+        .args(["/FI", &filter, "/NH", "/FO", "CSV"])
+        .output()
+    {
+        Ok(out) => String::from_utf8_lossy(&out.stdout).contains(&format!("\"{}\"", pid)),
+        // can't tell, so don't throw the state away
+        Err(_) => true,
+    }
+}
+
+/// Press `button` on the stopwatch running as `pid`, with SIGUSR1 or
+/// SIGUSR2.
+#[cfg(unix)]
+pub fn press(pid: u32, button: Press) -> io::Result<()> {
+    let signal = match button {
+        Press::Toggle => libc::SIGUSR1,
+        Press::Split => libc::SIGUSR2,
+    };
+    let pid = process(pid)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("bad pid {}", pid)))?;
+    if unsafe { libc::kill(pid, signal) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn press(_pid: u32, _button: Press) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "there are no signals on Windows; run the stopwatch with --http and use its /press endpoint",
+    ))
+}
+
+#[cfg(unix)]
+pub fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if rc != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[cfg(windows)]
+pub fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME").ok()
+}
+
+/// A UDP socket on `port` that other mDNS responders on this machine can
+/// share.
+#[cfg(unix)]
+pub fn bind_shared(port: u16) -> io::Result<UdpSocket> {
+    use std::os::fd::FromRawFd;
+
+    unsafe {
+        let fd = libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // owned from here on, so it is closed on every error path
+        let socket = UdpSocket::from_raw_fd(fd);
+        let one: libc::c_int = 1;
+        for opt in [libc::SO_REUSEADDR, libc::SO_REUSEPORT] {
+            let rc = libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                opt,
+                &one as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&one) as libc::socklen_t,
+            );
+            if rc != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
+        let sin = libc::sockaddr_in {
+            sin_family: libc::AF_INET as libc::sa_family_t,
+            sin_port: addr.port().to_be(),
+            sin_addr: libc::in_addr {
+                s_addr: u32::from(*addr.ip()).to_be(),
+            },
+            sin_zero: [0; 8],
+        };
+        let rc = libc::bind(
+            fd,
+            &sin as *const _ as *const libc::sockaddr,
+            std::mem::size_of_val(&sin) as libc::socklen_t,
+        );
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(socket)
+    }
+}
+
+/// Sharing the port needs `SO_REUSEADDR`, which std can't set on Windows,
+/// so this fails while the system's own responder holds it.
+#[cfg(windows)]
+pub fn bind_shared(port: u16) -> io::Result<UdpSocket> {
+    UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))
+}
@@ -0,0 +1,188 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use crate::duration::DurationFormat;
+use crate::journal::{self, Record};
+use crate::json::{self, Object, Value};
+use crate::orglog::Session;
+use crate::platform;
+use crate::{Message, Press};
+
+/// What a plugin can ask of the stopwatch, one JSON object per line:
+///
+/// ```text
+/// {"cmd":"rename","split":0,"name":"Outline"}
+/// {"cmd":"alert","text":"Stand up and stretch"}
+/// {"cmd":"press","button":"split"}
+/// ```
+#[derive(Debug)]
+pub enum Request {
+    /// Rename a split by index, or the active one when none is given.
+    Rename { index: Option<usize>, name: String },
+    /// Show a message on the status line.
+    Alert(String),
+    /// Act as if a remote button was pressed.
+    Press(Press),
+}
+
+impl Request {
+    fn parse(line: &str) -> Result<Request, String> {
+        let fields = json::parse_object(line)?;
+        let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+        let str_field = |key: &str| {
+            get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| format!("missing string field '{}'", key))
+        };
+        match str_field("cmd")?.as_str() {
+            "rename" => Ok(Request::Rename {
+                index: get("split").and_then(Value::as_u64).map(|i| i as usize),
+                name: str_field("name")?,
+            }),
+            "alert" => Ok(Request::Alert(str_field("text")?)),
+            "press" => {
+                let button = str_field("button")?;
+                Press::from_name(&button)
+                    .map(Request::Press)
+                    .ok_or_else(|| format!("unknown button '{}'", button))
+            }
+            other => Err(format!("unknown command '{}'", other)),
+        }
+    }
+}
+
+/// A program run alongside the stopwatch that follows its state changes
+/// and can call back into it. It gets every change on stdin as a journal
+/// line (see `journal::encode`) and writes requests to stdout; stdin
+/// closing means the stopwatch has exited. Plugins can be written in any
+/// language; see `export` for custom export formats.
+pub struct Plugin {
+    pub command: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl Plugin {
+    /// Start `command` with `sh -c` (`cmd /C` on Windows), passing its
+    /// requests to `tx`.
+    pub fn start(command: &str, tx: mpsc::Sender<Message>) -> io::Result<Plugin> {
+        let mut child = platform::shell(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        log::info!("started plugin '{}'", command);
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().ok_or(io::ErrorKind::BrokenPipe)?;
+        let name = command.to_string();
+        // not joined: a plugin's own children can keep its output open
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        log::warn!("plugin '{}': {}", name, e);
+                        break;
+                    }
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match Request::parse(&line) {
+                    Ok(request) => {
+                        if tx.send(Message::Plugin(request)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => log::warn!("plugin '{}': {}: {}", name, e, line),
+                }
+            }
+            log::debug!("plugin '{}' closed its output", name);
+        });
+        Ok(Plugin {
+            command: command.to_string(),
+            child,
+            stdin,
+        })
+    }
+
+    pub fn send(&mut self, record: &Record) -> io::Result<()> {
+        let stdin = self.stdin.as_mut().ok_or(io::ErrorKind::BrokenPipe)?;
+        writeln!(stdin, "{}", journal::encode(record))?;
+        stdin.flush()
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        // closing stdin asks the plugin to finish; one that doesn't soon is
+        // killed
+        self.stdin.take();
+        for _ in 0..20 {
+            if !matches!(self.child.try_wait(), Ok(None)) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// `sw export --plugin`: the export written by `command`. It reads
+/// `sessions` on stdin as JSON lines, as `--jsonl` writes them: for each, a
+/// `"type":"goal"` line and then a `"type":"split"` line per subgoal, and
+/// prints the export.
+pub fn export(command: &str, sessions: &[Session], fmt: &DurationFormat) -> Result<String, String> {
+    let stamp = |t: chrono::NaiveDateTime| t.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let mut input = String::new();
+    for session in sessions {
+        let line = |kind: &str, name: &str, project: Option<&str>, start, end, dur: Duration| {
+            Object::new()
+                .str("type", kind)
+                .str("goal", &session.goal)
+                .str("name", name)
+                .opt_str("project", project)
+                .str("start", &stamp(start))
+                .str("end", &stamp(end))
+                .opt_num("seconds", Some(dur.as_secs()))
+                .str("duration", &fmt.format(dur))
+        };
+        let goal = line(
+            "goal",
+            &session.goal,
+            session.project.as_deref(),
+            session.start,
+            session.end,
+            session.total,
+        );
+        input += &goal
+            .opt_num("splits", Some(session.splits.len() as u64))
+            .finish();
+        input.push('\n');
+        for entry in &session.splits {
+            let parent = entry.parent.map(|p| session.splits[p].name.as_str());
+            let project = entry.project.as_deref();
+            let split = line(
+                "split",
+                &entry.name,
+                project,
+                entry.start,
+                entry.end,
+                entry.duration,
+            );
+            input += &split.opt_str("parent", parent).finish();
+            input.push('\n');
+        }
+    }
+    let output = platform::shell_filter(command, input.as_bytes())
+        .map_err(|e| format!("plugin '{}': {}", command, e))?;
+    String::from_utf8(output)
+        .map_err(|_| format!("plugin '{}' wrote something other than UTF-8", command))
+}
@@ -0,0 +1,219 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+
+use crate::add_headings;
+use crate::duration::DurationFormat;
+use crate::logfile;
+use crate::options::{Options, ReconcileArgs};
+use crate::orglog::{self, Session};
+
+/// The copies of the log that file sync tools leave when two machines
+/// changed it at once: Syncthing's `done.sync-conflict-20240131-101500-ABC1234.org`,
+/// and Dropbox's or Nextcloud's `done (conflicted copy 2024-01-31).org`.
+pub fn conflicts(log: &Path) -> Vec<PathBuf> {
+    let dir = match log.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let stem = log.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = log
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut copies: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| log.with_file_name(e.file_name())))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let Some(middle) = name
+                .strip_prefix(stem.as_ref())
+                .and_then(|rest| rest.strip_suffix(&ext))
+            else {
+                return false;
+            };
+            middle.starts_with(".sync-conflict-")
+                || middle.to_lowercase().contains("conflicted copy")
+        })
+        .collect();
+    copies.sort();
+    copies
+}
+
+/// `sw reconcile`: fold the sync conflict copies of the log back into it.
+/// Sessions only a copy has are added, after asking; when the log and a
+/// copy disagree about a session, you pick which to keep. With `--yes`
+/// nothing is asked: every session is added and the longer version of each
+/// disputed one kept. The copies are removed once merged.
+pub fn run(opts: &Options, args: &ReconcileArgs) -> Result<(), String> {
+    let log = Path::new(&opts.log_file);
+    let copies = conflicts(log);
+    if copies.is_empty() {
+        if !opts.quiet {
+            println!("No conflicting copies of {}", opts.log_file);
+        }
+        return Ok(());
+    }
+    let ours = match orglog::read(&opts.log_file, &opts.crypt) {
+        Ok(sessions) => sessions,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("{}: {}", opts.log_file, e)),
+    };
+    let mut plan = Plan::default();
+    let mut ask_each = !args.yes;
+    for copy in &copies {
+        let theirs = orglog::read(&copy.to_string_lossy(), &opts.crypt)
+            .map_err(|e| format!("{}: {}", copy.display(), e))?;
+        println!("{}: {} sessions", copy.display(), theirs.len());
+        for session in theirs {
+            // the same as one turned down in an earlier copy
+            if plan.declined.iter().any(|s| identical(s, &session)) {
+                continue;
+            }
+            let same = |s: &Session| s.goal == session.goal && s.start == session.start;
+            let existing = plan
+                .add
+                .iter()
+                .position(same)
+                .map(Known::Added)
+                .or_else(|| ours.iter().find(|s| same(s)).map(Known::Logged));
+            let Some(known) = existing else {
+                let keep = !ask_each
+                    || match ask(
+                        &format!("Add {}? [Y/n/a/q]", describe(&session, &opts.format)),
+                        'y',
+                    )? {
+                        'y' => true,
+                        'a' => {
+                            ask_each = false;
+                            true
+                        }
+                        'q' => return Err("nothing was changed".to_string()),
+                        _ => false,
+                    };
+                if keep {
+                    plan.add.push(session);
+                } else {
+                    plan.declined.push(session);
+                }
+                continue;
+            };
+            let (other, seen_in) = match known {
+                Known::Added(i) => (&plan.add[i], "an earlier copy"),
+                Known::Logged(s) => (s, "the log"),
+            };
+            if identical(other, &session) {
+                continue;
+            }
+            let take = if ask_each {
+                println!("{} has it differently:", copy.display());
+                println!("  before: {} in {}", describe(other, &opts.format), seen_in);
+                println!("  copy:   {}", describe(&session, &opts.format));
+                match ask("Keep the one [b]efore or the [c]opy's? [B/c/q]", 'b')? {
+                    'c' => true,
+                    'q' => return Err("nothing was changed".to_string()),
+                    _ => false,
+                }
+            } else {
+                session.total > other.total
+            };
+            if !take {
+                plan.declined.push(session);
+                continue;
+            }
+            match known {
+                Known::Added(i) => plan.add[i] = session,
+                Known::Logged(s) => {
+                    plan.remove.push((s.goal.clone(), s.start));
+                    plan.add.push(session);
+                }
+            }
+        }
+    }
+
+    if !plan.add.is_empty() {
+        plan.add.sort_by_key(|s| s.start);
+        let stars = opts.log_parent.len() + 1;
+        let headings: Vec<Vec<orglog::Heading>> = plan
+            .add
+            .iter()
+            .map(|s| s.headings(stars, &opts.format))
+            .collect();
+        logfile::update(log, &opts.crypt, |old| {
+            let mut text = String::from_utf8_lossy(&old).into_owned();
+            for (goal, start) in &plan.remove {
+                text = orglog::remove(&text, goal, *start);
+            }
+            add_headings(text, &headings, opts).into_bytes()
+        })
+        .map_err(|e| format!("{}: {}", opts.log_file, e))?;
+    }
+    for copy in &copies {
+        fs::remove_file(copy).map_err(|e| format!("{}: {}", copy.display(), e))?;
+    }
+    if !opts.quiet {
+        println!(
+            "Added {} sessions to {} ({} replaced) and removed {} copies",
+            plan.add.len(),
+            opts.log_file,
+            plan.remove.len(),
+            copies.len()
+        );
+    }
+    Ok(())
+}
+
+/// What reconciling will do to the log.
+#[derive(Default)]
+struct Plan {
+    add: Vec<Session>,
+    /// The goal and start of sessions in the log to replace.
+    remove: Vec<(String, NaiveDateTime)>,
+    declined: Vec<Session>,
+}
+
+fn identical(a: &Session, b: &Session) -> bool {
+    a.goal == b.goal && a.start == b.start && a.end == b.end && a.total == b.total
+}
+
+/// Where a session was seen before.
+enum Known<'a> {
+    /// Already picked from an earlier copy, at this index.
+    Added(usize),
+    Logged(&'a Session),
+}
+
+fn describe(session: &Session, fmt: &DurationFormat) -> String {
+    format!(
+        "'{}' from {} to {} ({})",
+        session.goal,
+        session.start.format("%Y-%m-%d %H:%M"),
+        session.end.format("%H:%M"),
+        fmt.format(session.total)
+    )
+}
+
+/// The first letter of the answer, lowercased, or `default` for none.
+fn ask(question: &str, default: char) -> Result<char, String> {
+    print!("{} ", question);
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let mut answer = String::new();
+    let read = io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| e.to_string())?;
+    if read == 0 {
+        return Err("no answer; run with --yes to merge without asking".to_string());
+    }
+    Ok(answer
+        .trim()
+        .chars()
+        .next()
+        .map_or(default, |c| c.to_ascii_lowercase()))
+}
@@ -0,0 +1,201 @@
+use std::{
+    io::{self, BufWriter, Write},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+use crate::error::{Error, Result};
+use crate::journal::{self, Record};
+use crate::options::{Options, ReplayArgs};
+use crate::stopwatch::{Action, Clock, Stopwatch};
+use crate::ui::{draw_dynamic, redraw, restore_terminal, setup_terminal, View};
+use crate::{Message, Workers, EXIT_INTERRUPTED};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Speed {
+    /// Real time multiplied by this factor.
+    Times(u32),
+    /// Jump straight to the end.
+    Max,
+}
+
+impl Speed {
+    /// `max`, or a whole factor such as `1`, `5` or `5x`.
+    pub fn from_name(name: &str) -> Option<Speed> {
+        if name == "max" {
+            return Some(Speed::Max);
+        }
+        match name.trim_end_matches(['x', '×']).parse() {
+            Ok(0) | Err(_) => None,
+            Ok(n) => Some(Speed::Times(n)),
+        }
+    }
+}
+
+/// Play back the journal at `args.path`, applying each recorded change when
+/// its time comes round.
+pub fn run(opts: &Options, args: &ReplayArgs) -> Result<i32> {
+    let records = journal::read(&args.path).map_err(|source| Error::Journal {
+        path: args.path.clone(),
+        source,
+    })?;
+    if records.is_empty() {
+        return Err(Error::Command(format!("{}: nothing to replay", args.path)));
+    }
+    // frames are flushed whole, see `redraw`
+    let mut stdout = BufWriter::new(io::stdout());
+    setup_terminal(&mut stdout, opts).map_err(Error::Terminal)?;
+    let result = play(&mut stdout, opts, &records, args.speed);
+    restore_terminal(&mut stdout, opts).map_err(Error::Terminal)?;
+    result
+}
+
+/// Offset of each record from the start of the replay. The idle time between
+/// a clean exit and the next run is skipped.
+fn timeline(records: &[Record]) -> Vec<Duration> {
+    let mut offsets = Vec::with_capacity(records.len());
+    let mut offset = Duration::ZERO;
+    for (i, record) in records.iter().enumerate() {
+        if i > 0 && records[i - 1].event != journal::Event::Quit {
+            offset += (record.at - records[i - 1].at).to_std().unwrap_or_default();
+        }
+        offsets.push(offset);
+    }
+    offsets
+}
+
+struct Playback<'a> {
+    records: &'a [Record],
+    offsets: Vec<Duration>,
+    base: Instant,
+    next: usize,
+    speed: Speed,
+}
+
+impl Playback<'_> {
+    /// Apply every record that is due. Returns true if anything changed.
+    fn advance(&mut self, sw: &mut Stopwatch) -> bool {
+        let now = sw.clock.now();
+        let start = self.next;
+        while self.next < self.records.len() {
+            let at = self.base + self.offsets[self.next];
+            if self.speed != Speed::Max && at > now {
+                break;
+            }
+            let record = &self.records[self.next];
+            if let journal::Event::Action(action) = &record.event {
+                sw.apply_at(action, at, record.at);
+            }
+            self.next += 1;
+        }
+        if self.next == self.records.len() && start < self.next && sw.running {
+            // freeze the clock where the recording stopped
+            let last = self.records.len() - 1;
+            let at = self.base + self.offsets[last];
+            sw.apply_at(&Action::Pause, at, self.records[last].at);
+        }
+        start < self.next
+    }
+
+    fn status(&self) -> String {
+        let speed = match self.speed {
+            Speed::Times(n) => format!("{}x", n),
+            Speed::Max => "max".to_string(),
+        };
+        let state = if self.next == self.records.len() {
+            "finished".to_string()
+        } else {
+            format!("{}/{} events", self.next, self.records.len())
+        };
+        format!("Replay {} {} | 1/5/m=speed q=quit", speed, state)
+    }
+}
+
+fn play<W: Write>(stdout: &mut W, opts: &Options, records: &[Record], speed: Speed) -> Result<i32> {
+    let base = Instant::now();
+    let mut sw = Stopwatch::new();
+    sw.clock = Clock::starting_at(base, multiplier(speed));
+    let mut playback = Playback {
+        records,
+        offsets: timeline(records),
+        base,
+        next: 0,
+        speed,
+    };
+    let mut view = View {
+        compact: opts.compact,
+        ..View::default()
+    };
+    playback.advance(&mut sw);
+    view.message = Some(playback.status());
+    redraw(stdout, opts, &sw, &view).map_err(Error::Terminal)?;
+
+    let (tx, rx) = mpsc::channel::<Message>();
+    let workers = Workers::spawn(tx, opts.tick)?;
+    let result = handle_events(stdout, opts, &mut sw, &mut view, &mut playback, &rx);
+    workers.stop();
+    result
+}
+
+fn handle_events<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    view: &mut View,
+    playback: &mut Playback,
+    rx: &mpsc::Receiver<Message>,
+) -> Result<i32> {
+    for msg in rx {
+        match msg {
+            Message::Tick => {
+                if playback.advance(sw) {
+                    view.message = Some(playback.status());
+                    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                } else if sw.running {
+                    draw_dynamic(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+            }
+            #[cfg(unix)]
+            Message::Signal(sig) => return Ok(128 + sig),
+            Message::Failed(e) => return Err(e),
+            Message::Remote(_) | Message::Press(_) => {}
+            Message::Commit(_) | Message::Line(_) | Message::LinesEnded => {}
+            Message::Watched(..) | Message::Advance(_) => {}
+            #[cfg(feature = "plugins")]
+            Message::Plugin(_) => {}
+            #[cfg(feature = "window")]
+            Message::Focus(_) => {}
+            Message::Input(Event::Key(key), _) => {
+                let speed = match key.code {
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(EXIT_INTERRUPTED);
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(0),
+                    KeyCode::Char('1') => Speed::Times(1),
+                    KeyCode::Char('5') => Speed::Times(5),
+                    KeyCode::Char('m') => Speed::Max,
+                    _ => continue,
+                };
+                playback.speed = speed;
+                sw.clock.set_speed(multiplier(speed));
+                playback.advance(sw);
+                view.message = Some(playback.status());
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            Message::Input(Event::Resize(..), _) => {
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?
+            }
+            Message::Input(..) => {}
+        }
+    }
+    Ok(0)
+}
+
+fn multiplier(speed: Speed) -> u32 {
+    match speed {
+        Speed::Times(n) => n,
+        Speed::Max => 1,
+    }
+}
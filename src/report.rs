@@ -0,0 +1,296 @@
+use std::{
+    collections::HashMap,
+    io::{self, IsTerminal, Write},
+    time::Duration,
+};
+
+use chrono::{Datelike, Days, Local, NaiveDate};
+use crossterm::style::{Color, Stylize};
+
+use crate::billing;
+use crate::export;
+use crate::json;
+use crate::options::{Options, ReportArgs};
+use crate::orglog::{self, Session};
+use crate::pattern::glob;
+
+/// What `--by` totals the filtered goals per.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    Day,
+    Goal,
+    /// Each of a goal's tags; goals without one count as `(untagged)`.
+    Tag,
+}
+
+impl GroupBy {
+    pub fn from_name(name: &str) -> Option<GroupBy> {
+        match name {
+            "day" => Some(GroupBy::Day),
+            "goal" => Some(GroupBy::Goal),
+            "tag" => Some(GroupBy::Tag),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Csv,
+    /// One object per line.
+    Json,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "csv" => Some(Format::Csv),
+            "json" | "jsonl" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Hours a day needs to reach each shade after the empty one.
+const LEVELS: &[(f64, char, Color)] = &[
+    (0.0, '░', Color::DarkGreen),
+    (1.0, '▒', Color::DarkGreen),
+    (2.0, '▓', Color::Green),
+    (4.0, '█', Color::Green),
+];
+const EMPTY: char = '·';
+
+/// `sw report`: the goals in the log that pass the filters, as a heatmap
+/// of the hours per day over the last `--weeks` weeks (one column per week,
+/// one row per weekday), or totalled per day, goal or tag with `--by`.
+pub fn run(opts: &Options, args: &ReportArgs) -> Result<(), String> {
+    let sessions = orglog::read(&opts.log_file, &opts.crypt)
+        .map_err(|e| format!("{}: {}", opts.log_file, e))?;
+    let picked: Vec<&Session> = sessions.iter().filter(|s| matches(s, args)).collect();
+    let text = match (args.by, args.format) {
+        (None, Format::Text) => {
+            let mut per_day: HashMap<NaiveDate, Duration> = HashMap::new();
+            for session in &picked {
+                *per_day.entry(session.start.date()).or_default() +=
+                    opts.rounding.apply(session.total);
+            }
+            let today = Local::now().date_naive();
+            let color = opts.color && io::stdout().is_terminal();
+            heatmap(&per_day, today, args.weeks, color)
+        }
+        (by, format) => {
+            let rows = totals(&picked, by.unwrap_or(GroupBy::Day), opts);
+            match format {
+                Format::Text => {
+                    // a goal with several tags is in several rows, so sum
+                    // the goals rather than the rows
+                    let total = picked.iter().map(|s| opts.rounding.apply(s.total)).sum();
+                    table(&rows, total, opts)
+                }
+                Format::Csv => csv(&rows, opts),
+                Format::Json => jsonl(&rows, opts),
+            }
+        }
+    };
+    io::stdout()
+        .write_all(text.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+fn matches(session: &Session, args: &ReportArgs) -> bool {
+    args.tag
+        .as_deref()
+        .is_none_or(|tag| billing::tags(&session.goal).any(|t| t == tag))
+        && args
+            .goal
+            .as_deref()
+            .is_none_or(|pattern| glob(pattern, &session.goal))
+        && args.min_duration.is_none_or(|min| session.total >= min)
+}
+
+/// One group's total.
+struct Row {
+    group: String,
+    total: Duration,
+    goals: usize,
+}
+
+/// Totals per group, days in order and the rest longest first.
+fn totals(sessions: &[&Session], by: GroupBy, opts: &Options) -> Vec<Row> {
+    let mut rows: Vec<Row> = Vec::new();
+    for session in sessions {
+        let groups: Vec<String> = match by {
+            GroupBy::Day => vec![session.start.date().to_string()],
+            GroupBy::Goal => vec![session.goal.clone()],
+            GroupBy::Tag => {
+                let tags: Vec<String> = billing::tags(&session.goal).map(str::to_string).collect();
+                if tags.is_empty() {
+                    vec!["(untagged)".to_string()]
+                } else {
+                    tags
+                }
+            }
+        };
+        for group in groups {
+            let row = match rows.iter().position(|r| r.group == group) {
+                Some(i) => &mut rows[i],
+                None => {
+                    rows.push(Row {
+                        group,
+                        total: Duration::ZERO,
+                        goals: 0,
+                    });
+                    rows.last_mut().unwrap()
+                }
+            };
+            row.total += opts.rounding.apply(session.total);
+            row.goals += 1;
+        }
+    }
+    match by {
+        GroupBy::Day => rows.sort_by(|a, b| a.group.cmp(&b.group)),
+        _ => rows.sort_by_key(|r| std::cmp::Reverse(r.total)),
+    }
+    rows
+}
+
+fn table(rows: &[Row], total: Duration, opts: &Options) -> String {
+    let width = rows
+        .iter()
+        .map(|r| r.group.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for row in rows {
+        out.push_str(&format!(
+            "{:<width$}  {}  {:>3} goal{}\n",
+            row.group,
+            opts.format.format(row.total),
+            row.goals,
+            if row.goals == 1 { "" } else { "s" },
+            width = width
+        ));
+    }
+    out.push_str(&format!(
+        "{:<width$}  {}\n",
+        "total",
+        opts.format.format(total),
+        width = width
+    ));
+    out
+}
+
+fn csv(rows: &[Row], opts: &Options) -> String {
+    let mut out = String::from("group,duration,hours,goals\n");
+    for row in rows {
+        out.push_str(&format!(
+            "{},{},{:.4},{}\n",
+            export::csv_field(&row.group),
+            opts.format.format(row.total),
+            row.total.as_secs_f64() / 3600.0,
+            row.goals
+        ));
+    }
+    out
+}
+
+fn jsonl(rows: &[Row], opts: &Options) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let line = json::Object::new()
+            .str("group", &row.group)
+            .str("duration", &opts.format.format(row.total))
+            .opt_num("seconds", Some(row.total.as_secs()))
+            .opt_num("goals", Some(row.goals as u64))
+            .finish();
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+fn heatmap(
+    per_day: &HashMap<NaiveDate, Duration>,
+    today: NaiveDate,
+    weeks: usize,
+    color: bool,
+) -> String {
+    let this_monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    let first = this_monday - Days::new(7 * (weeks.max(1) as u64 - 1));
+    let mondays: Vec<NaiveDate> = (0..weeks.max(1))
+        .map(|w| first + Days::new(7 * w as u64))
+        .collect();
+
+    // month names over the week their first day falls in
+    let mut out = String::from("     ");
+    let mut labels = String::new();
+    for (w, &monday) in mondays.iter().enumerate() {
+        let first_of_month = (0..7)
+            .map(|d| monday + Days::new(d))
+            .find(|day| day.day() == 1);
+        let label = match first_of_month {
+            Some(day) => day,
+            None if w == 0 => monday,
+            None => continue,
+        };
+        // leave a space after the previous name
+        if w > 0 && labels.len() >= w * 2 {
+            continue;
+        }
+        labels.push_str(&" ".repeat(w * 2 - labels.len()));
+        labels.push_str(&label.format("%b").to_string());
+    }
+    out.push_str(labels.trim_end());
+    out.push('\n');
+
+    let mut total = Duration::ZERO;
+    let mut days = 0;
+    for (weekday, name) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+        .iter()
+        .enumerate()
+    {
+        out.push_str(&format!("{} ", name));
+        for monday in &mondays {
+            let day = *monday + Days::new(weekday as u64);
+            if day > today {
+                break;
+            }
+            let logged = per_day.get(&day).copied().unwrap_or_default();
+            if !logged.is_zero() {
+                total += logged;
+                days += 1;
+            }
+            out.push(' ');
+            out.push_str(&cell(logged, color));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("\n    less ");
+    out.push_str(&cell(Duration::ZERO, color));
+    for &(hours, _, _) in LEVELS {
+        out.push(' ');
+        out.push_str(&cell(Duration::from_secs_f64(hours * 3600.0 + 1.0), color));
+    }
+    out.push_str(&format!(
+        " more    {:.1}h on {} days\n",
+        total.as_secs_f64() / 3600.0,
+        days
+    ));
+    out
+}
+
+fn cell(logged: Duration, color: bool) -> String {
+    let hours = logged.as_secs_f64() / 3600.0;
+    let level = LEVELS
+        .iter()
+        .rev()
+        .find(|&&(min, _, _)| !logged.is_zero() && hours >= min);
+    match level {
+        Some(&(_, c, shade)) if color => c.with(shade).to_string(),
+        Some(&(_, c, _)) => c.to_string(),
+        None => EMPTY.to_string(),
+    }
+}
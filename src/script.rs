@@ -0,0 +1,97 @@
+use std::{
+    fs,
+    path::Path,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::duration::parse_duration;
+use crate::keys::parse_sequence;
+use crate::ui;
+use crate::Message;
+
+/// One line of an `--input-script`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Let this much time pass.
+    Wait(Duration),
+    /// Press these keys, one after the other.
+    Press(Vec<KeyCode>),
+    /// Answer the next prompt with this text.
+    Type(String),
+}
+
+/// Parse a script of keys to press, prompts to answer and time to let pass,
+/// to run the stopwatch without a keyboard, for end-to-end tests and to
+/// reproduce a bug. Keys are named as in the `[keys]` config section.
+///
+/// ```text
+/// # a goal with one ten-minute subgoal, saved
+/// press s
+/// type Write the report
+/// wait 1s
+/// press g
+/// type Outline
+/// wait 10m
+/// press h s
+/// wait 1s
+/// press t q
+/// ```
+pub fn parse(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let step = match word {
+            "wait" => parse_duration(rest)
+                .map(Step::Wait)
+                .map_err(|e| e.to_string()),
+            "press" => parse_sequence(rest).map(Step::Press),
+            "type" => Ok(Step::Type(rest.to_string())),
+            _ => Err(format!("unknown step '{}'", word)),
+        };
+        steps.push(step.map_err(|e| format!("line {}: {}", i + 1, e))?);
+    }
+    Ok(steps)
+}
+
+pub fn load(path: &Path) -> Result<Vec<Step>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Play `steps` into the event loop from a thread of their own: keys go to
+/// `tx` as if typed, and answers to the prompts that come up. Waits take
+/// that long, or on a `simulated` clock move it on straight away, so a run
+/// comes out the same every time. The keyboard still works alongside.
+pub fn play(steps: Vec<Step>, simulated: bool, tx: mpsc::Sender<Message>) {
+    let (answer_tx, answer_rx) = mpsc::channel();
+    ui::answer_prompts_from(answer_rx);
+    thread::spawn(move || {
+        for step in steps {
+            let sent = match step {
+                Step::Wait(by) if simulated => tx.send(Message::Advance(by)).is_ok(),
+                Step::Wait(by) => {
+                    thread::sleep(by);
+                    true
+                }
+                Step::Press(keys) => keys.into_iter().all(|code| {
+                    let key = Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+                    tx.send(Message::Input(key, Instant::now())).is_ok()
+                }),
+                Step::Type(text) => answer_tx.send(text).is_ok(),
+            };
+            if !sent {
+                return;
+            }
+        }
+        log::info!("input script done");
+    });
+}
@@ -0,0 +1,187 @@
+use std::{fs, path::Path, time::Duration};
+
+use crate::config::{self, Config, Entry};
+use crate::duration::parse_duration;
+
+/// Named countdowns run one after the other, such as the rundown of a talk.
+/// Each phase is timed as a top-level split, so it is logged like any other.
+#[derive(Clone, Debug, Default)]
+pub struct Sequence {
+    /// Started on its own when nothing is being timed yet; set by routines.
+    pub goal: Option<String>,
+    pub project: Option<String>,
+    pub phases: Vec<Phase>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Phase {
+    pub name: String,
+    /// How long the phase runs, or for a routine step the estimate.
+    pub length: Duration,
+    /// Whether the phase ends by itself once `length` is up. Routine steps
+    /// last until the split is stopped.
+    pub stop: bool,
+    /// Times into the phase to ring the bell at, in order.
+    pub alerts: Vec<Duration>,
+}
+
+/// How far the sequence has got.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Progress {
+    /// Waiting for the timer to run.
+    #[default]
+    Ready,
+    /// Phase `phase` is being timed by the split at `split`; the first
+    /// `rung` of its alerts have gone off.
+    At {
+        phase: usize,
+        split: usize,
+        rung: usize,
+    },
+    Done,
+}
+
+impl Sequence {
+    /// One phase per line, its name followed by its length:
+    ///
+    /// ```text
+    /// # conference talk
+    /// Intro 5m
+    /// Demo 15m
+    /// Q&A 10m
+    /// ```
+    pub fn parse(text: &str) -> Result<Sequence, String> {
+        let mut phases = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, length) = line
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(|| format!("line {}: expected a name and a length", i + 1))?;
+            let length = parse_duration(length).map_err(|e| format!("line {}: {}", i + 1, e))?;
+            if length.is_zero() {
+                return Err(format!("line {}: a phase can't be empty", i + 1));
+            }
+            phases.push(Phase {
+                name: name.trim_end().to_string(),
+                length,
+                stop: true,
+                alerts: Vec::new(),
+            });
+        }
+        if phases.is_empty() {
+            return Err("no phases".to_string());
+        }
+        Ok(Sequence {
+            phases,
+            ..Sequence::default()
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Sequence, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Sequence::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Load a routine: a goal and the steps to reach it, in the config file's
+/// TOML subset.
+///
+/// ```toml
+/// goal = "Release"
+/// project = "stopwatch"
+///
+/// [[step]]
+/// name = "Changelog"
+/// estimate = "10m"
+/// alert = "8m"    # a reminder before the estimate runs out
+///
+/// [[step]]
+/// name = "Tag and publish"
+/// estimate = "5m"
+/// ```
+///
+/// Each step is timed until its split is stopped, and the bell rings at the
+/// alert and again when the estimate is used up.
+pub fn routine(path: &Path) -> Result<Sequence, String> {
+    let Some(cfg) = config::load(path.to_path_buf(), true)? else {
+        return Err(format!("{}: not found", path.display()));
+    };
+    let mut seq = Sequence::default();
+    let mut steps: Vec<Step> = Vec::new();
+    for entry in &cfg.entries {
+        match entry.key.as_str() {
+            "goal" => seq.goal = Some(cfg.string(entry)?),
+            "project" => seq.project = Some(cfg.string(entry)?),
+            key if key.starts_with("step.") => {
+                let (n, field) = key["step.".len()..]
+                    .split_once('.')
+                    .ok_or_else(|| cfg.error(entry, "steps are written as [[step]]"))?;
+                let n: usize = n
+                    .parse()
+                    .map_err(|_| cfg.error(entry, "steps are written as [[step]]"))?;
+                if steps.len() < n {
+                    steps.resize(
+                        n,
+                        Step {
+                            line: entry.line,
+                            ..Step::default()
+                        },
+                    );
+                }
+                let step = &mut steps[n - 1];
+                match field {
+                    "name" => step.name = Some(cfg.string(entry)?),
+                    "estimate" => step.estimate = Some(duration(&cfg, entry)?),
+                    "alert" => step.alert = Some(duration(&cfg, entry)?),
+                    other => {
+                        return Err(cfg.error(entry, &format!("unknown step setting '{}'", other)))
+                    }
+                }
+            }
+            other => return Err(cfg.error(entry, &format!("unknown routine setting '{}'", other))),
+        }
+    }
+    if seq.goal.is_none() {
+        return Err(format!("{}: the routine has no goal", path.display()));
+    }
+    for step in steps {
+        let at = |msg: &str| format!("{}:{}: {}", path.display(), step.line, msg);
+        let name = step.name.ok_or_else(|| at("the step has no name"))?;
+        let length = step
+            .estimate
+            .ok_or_else(|| at(&format!("'{}' has no estimate", name)))?;
+        let mut alerts: Vec<Duration> = step.alert.into_iter().filter(|a| *a < length).collect();
+        alerts.push(length);
+        seq.phases.push(Phase {
+            name,
+            length,
+            stop: false,
+            alerts,
+        });
+    }
+    if seq.phases.is_empty() {
+        return Err(format!("{}: the routine has no steps", path.display()));
+    }
+    Ok(seq)
+}
+
+/// A `[[step]]` as read, with the line of its first setting.
+#[derive(Clone, Default)]
+struct Step {
+    line: usize,
+    name: Option<String>,
+    estimate: Option<Duration>,
+    alert: Option<Duration>,
+}
+
+fn duration(cfg: &Config, entry: &Entry) -> Result<Duration, String> {
+    let value = cfg.string(entry)?;
+    let length = parse_duration(&value).map_err(|e| cfg.error(entry, &e.to_string()))?;
+    if length.is_zero() {
+        return Err(cfg.error(entry, "must be longer than zero"));
+    }
+    Ok(length)
+}
@@ -0,0 +1,356 @@
+use std::{
+    io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+use crate::error::{Error, Result};
+use crate::journal::{self, Record};
+use crate::json::{self, Object};
+use crate::locale::{tr, trf};
+use crate::mdns;
+use crate::options::Options;
+use crate::stopwatch::{Action, Stopwatch};
+use crate::ui::{draw_dynamic, redraw, restore_terminal, setup_terminal, View};
+use crate::{Message, Workers, EXIT_INTERRUPTED};
+
+/// How long to collect mDNS answers when looking for timers.
+const DISCOVERY_WAIT: Duration = Duration::from_millis(1500);
+
+/// Slow spectators are dropped rather than allowed to stall the timer.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+struct Shared {
+    /// Every record so far, sent to spectators when they join.
+    history: Vec<String>,
+    /// One queue per spectator, drained by that spectator's writer thread so
+    /// nothing here ever waits on a socket.
+    clients: Vec<mpsc::Sender<String>>,
+}
+
+/// Serves the timer to spectators. The protocol is the journal format: on
+/// connect a spectator gets a `sync` line carrying the host's clock, then
+/// every record so far, then new records as they happen.
+pub struct Host {
+    shared: Arc<Mutex<Shared>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    _advertiser: Option<mdns::Advertiser>,
+}
+
+impl Host {
+    pub fn bind(addr: &str, history: &[Record]) -> io::Result<Host> {
+        let listener = TcpListener::bind(addr)?;
+        // non-blocking so the accept loop notices the shutdown flag
+        listener.set_nonblocking(true)?;
+        let local = listener.local_addr()?;
+        log::info!("sharing on {}", local);
+        // discovery is a convenience; sharing works without it
+        let advertiser = mdns::Advertiser::start(&mdns::instance_name(local.port()), local.port())
+            .map_err(|e| log::warn!("not advertising via mDNS: {}", e))
+            .ok();
+        let shared = Arc::new(Mutex::new(Shared {
+            history: history.iter().map(journal::encode).collect(),
+            clients: Vec::new(),
+        }));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shared = shared.clone();
+            let shutdown = shutdown.clone();
+            thread::spawn(move || accept_loop(listener, &shared, &shutdown))
+        };
+        Ok(Host {
+            shared,
+            shutdown,
+            handle: Some(handle),
+            _advertiser: advertiser,
+        })
+    }
+
+    /// Queue `record` for every spectator, dropping the ones whose writer
+    /// has given up.
+    pub fn publish(&self, record: &Record) {
+        let line = journal::encode(record);
+        let mut shared = self.shared.lock().unwrap();
+        shared
+            .clients
+            .retain(|client| client.send(line.clone()).is_ok());
+        shared.history.push(line);
+    }
+}
+
+impl Drop for Host {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn accept_loop(listener: TcpListener, shared: &Mutex<Shared>, shutdown: &AtomicBool) {
+    while !shutdown.load(Ordering::Relaxed) {
+        let client = match listener.accept() {
+            Ok((client, peer)) => {
+                log::info!("spectator connected from {}", peer);
+                client
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(e) => {
+                log::warn!("accept failed: {}", e);
+                continue;
+            }
+        };
+        let setup = client
+            .set_nonblocking(false)
+            .and_then(|()| client.set_write_timeout(Some(WRITE_TIMEOUT)));
+        if let Err(e) = setup {
+            log::warn!("dropping spectator: {}", e);
+            continue;
+        }
+        let sync = Object::new()
+            .str(
+                "t",
+                &Local::now().to_rfc3339_opts(SecondsFormat::Millis, false),
+            )
+            .str("event", "sync")
+            .finish();
+        // take the history and join the clients in one go, so every record
+        // reaches the spectator exactly once
+        let (tx, rx) = mpsc::channel();
+        let history = {
+            let mut shared = shared.lock().unwrap();
+            shared.clients.push(tx);
+            shared.history.clone()
+        };
+        thread::spawn(move || write_loop(client, sync, history, &rx));
+    }
+}
+
+/// Feeds one spectator: the `sync` line and history first, then records as
+/// they are published. Returns, dropping the queue, once a write fails or the
+/// host goes away.
+fn write_loop(
+    mut client: TcpStream,
+    sync: String,
+    history: Vec<String>,
+    rx: &mpsc::Receiver<String>,
+) {
+    let result = send(&mut client, &[sync])
+        .and_then(|()| send(&mut client, &history))
+        .and_then(|()| {
+            for line in rx {
+                send(&mut client, &[line])?;
+            }
+            Ok(())
+        });
+    if let Err(e) = result {
+        log::warn!("dropping spectator: {}", e);
+    }
+}
+
+fn send(client: &mut TcpStream, lines: &[String]) -> io::Result<()> {
+    let mut buf = String::new();
+    for line in lines {
+        buf.push_str(line);
+        buf.push('\n');
+    }
+    client.write_all(buf.as_bytes())
+}
+
+/// Browse the local network for shared timers and let the user pick one.
+fn discover() -> Result<String> {
+    eprintln!("{}", tr("Looking for shared timers..."));
+    let found = mdns::browse(DISCOVERY_WAIT).map_err(|source| Error::Remote {
+        addr: "mDNS".to_string(),
+        source,
+    })?;
+    match found.as_slice() {
+        [] => Err(Error::Command(
+            "no shared timers found on the local network".to_string(),
+        )),
+        [only] => {
+            eprintln!("{}", trf("Following {} at {}", &[&only.name, &only.addr]));
+            Ok(only.addr.to_string())
+        }
+        _ => {
+            for (i, timer) in found.iter().enumerate() {
+                let timer = trf("{} at {}", &[&timer.name, &timer.addr]);
+                eprintln!("{:2}) {}", i + 1, timer);
+            }
+            eprint!("{}", trf("Follow which timer? [1-{}]: ", &[&found.len()]));
+            let mut line = String::new();
+            io::stdin().read_line(&mut line).map_err(Error::Input)?;
+            line.trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| found.get(n.checked_sub(1)?))
+                .map(|timer| timer.addr.to_string())
+                .ok_or_else(|| Error::Usage(format!("no timer numbered '{}'", line.trim())))
+        }
+    }
+}
+
+/// Watch the timer served at `addr`, or one picked from those advertised on
+/// the network. Keys other than quit and redraw are ignored; the spectator
+/// has no say over the host's clock.
+pub fn follow(opts: &Options, addr: Option<&str>) -> Result<i32> {
+    let addr = match addr {
+        Some(addr) => addr.to_string(),
+        None => discover()?,
+    };
+    let addr = addr.as_str();
+    let network = |source| Error::Remote {
+        addr: addr.to_string(),
+        source,
+    };
+    let stream = TcpStream::connect(addr).map_err(network)?;
+    let closer = stream.try_clone().map_err(network)?;
+    let mut lines = BufReader::new(stream).lines();
+    let sync = lines
+        .next()
+        .unwrap_or_else(|| Err(io::Error::new(ErrorKind::UnexpectedEof, "no greeting")))
+        .map_err(network)?;
+    let host_now =
+        parse_sync(&sync).map_err(|e| network(io::Error::new(ErrorKind::InvalidData, e)))?;
+    // how far the host's clock is ahead of ours
+    let skew = host_now - Local::now();
+
+    let (tx, rx) = mpsc::channel::<Message>();
+    let workers = Workers::spawn(tx.clone(), opts.tick)?;
+    let reader = {
+        let addr = addr.to_string();
+        thread::spawn(move || {
+            let mut error = None;
+            for line in lines {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                };
+                match journal::decode(&line) {
+                    Ok(record) => {
+                        if tx.send(Message::Remote(record)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => log::warn!("{}: ignoring bad record: {}", addr, e),
+                }
+            }
+            let source = error.unwrap_or_else(|| {
+                io::Error::new(ErrorKind::UnexpectedEof, "host closed the connection")
+            });
+            let _ = tx.send(Message::Failed(Error::Remote { addr, source }));
+        })
+    };
+
+    // frames are flushed whole, see `redraw`
+    let mut stdout = BufWriter::new(io::stdout());
+    setup_terminal(&mut stdout, opts).map_err(Error::Terminal)?;
+    let mut sw = Stopwatch::new();
+    let mut view = View {
+        message: Some(format!("Following {} (read-only, q=quit)", addr)),
+        compact: opts.compact,
+        ..View::default()
+    };
+    let result = spectate(&mut stdout, opts, &mut sw, &mut view, skew, &rx);
+    restore_terminal(&mut stdout, opts).map_err(Error::Terminal)?;
+    workers.stop();
+    // unblocks the reader thread
+    let _ = closer.shutdown(Shutdown::Both);
+    let _ = reader.join();
+    result
+}
+
+fn parse_sync(line: &str) -> std::result::Result<DateTime<Local>, String> {
+    let fields = json::parse_object(line)?;
+    let field = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .and_then(|(_, v)| v.as_str())
+    };
+    if field("event") != Some("sync") {
+        return Err("not a stopwatch host".to_string());
+    }
+    let t = field("t").ok_or("sync without a timestamp")?;
+    DateTime::parse_from_rfc3339(t)
+        .map(|t| t.with_timezone(&Local))
+        .map_err(|e| format!("bad timestamp '{}': {}", t, e))
+}
+
+fn spectate<W: Write>(
+    stdout: &mut W,
+    opts: &Options,
+    sw: &mut Stopwatch,
+    view: &mut View,
+    skew: chrono::Duration,
+    rx: &mpsc::Receiver<Message>,
+) -> Result<i32> {
+    redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+    let mut ended = false;
+    for msg in rx {
+        match msg {
+            Message::Tick => {
+                if sw.running {
+                    draw_dynamic(stdout, opts, sw, view).map_err(Error::Terminal)?;
+                }
+            }
+            Message::Remote(record) => {
+                let wall = record.at - skew;
+                let ago = (Local::now() - wall).to_std().unwrap_or_default();
+                let now = Instant::now();
+                let now = now.checked_sub(ago).unwrap_or(now);
+                match &record.event {
+                    journal::Event::Action(action) => sw.apply_at(action, now, wall),
+                    journal::Event::Quit => {
+                        // keep showing where the host stopped
+                        sw.apply_at(&Action::Pause, now, wall);
+                        view.message = Some("The host has quit (q=quit)".to_string());
+                        ended = true;
+                    }
+                }
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?;
+            }
+            Message::Press(_) => {}
+            Message::Commit(_) | Message::Line(_) | Message::LinesEnded => {}
+            Message::Watched(..) | Message::Advance(_) => {}
+            #[cfg(feature = "plugins")]
+            Message::Plugin(_) => {}
+            #[cfg(feature = "window")]
+            Message::Focus(_) => {}
+            #[cfg(unix)]
+            Message::Signal(sig) => return Ok(128 + sig),
+            // the connection closing after a clean exit is expected
+            Message::Failed(_) if ended => {}
+            Message::Failed(e) => return Err(e),
+            Message::Input(Event::Key(key), _) => match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(EXIT_INTERRUPTED);
+                }
+                KeyCode::Char('q') => return Ok(0),
+                KeyCode::Char('d') => redraw(stdout, opts, sw, view).map_err(Error::Terminal)?,
+                _ => {}
+            },
+            Message::Input(Event::Resize(..), _) => {
+                redraw(stdout, opts, sw, view).map_err(Error::Terminal)?
+            }
+            Message::Input(..) => {}
+        }
+    }
+    Ok(0)
+}
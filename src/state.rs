@@ -0,0 +1,255 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process, thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+use crate::json::{self, Object, Value};
+use crate::locale::{tr, trf};
+use crate::options::{Options, StatusArgs};
+use crate::platform;
+use crate::stopwatch::Stopwatch;
+use crate::Press;
+
+/// How often `sw status --follow` looks at the state file.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Where the running stopwatch keeps its state for `sw status`:
+/// `$XDG_RUNTIME_DIR/stopwatch/state.json`, or under `~/.local/state`
+/// (`%LOCALAPPDATA%` on Windows) when there is no runtime directory.
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_RUNTIME_DIR")
+        .or_else(|| env::var_os("XDG_STATE_HOME"))
+        .map(PathBuf::from)
+        .or_else(platform::state_home)?;
+    Some(base.join("stopwatch").join("state.json"))
+}
+
+/// `sw status`: what the running stopwatch is timing, from its state file.
+/// With `--starship` it is one short line for a shell prompt, such as
+/// `⏱ 01:23:45 build`, and nothing at all when no timer is running;
+/// `--polybar` is the same with click actions for `sw press`. With
+/// `--follow` a new line is printed each time the text changes, for
+/// polybar's `tail = true` script modules.
+pub fn run(opts: &Options, args: &StatusArgs) -> Result<(), String> {
+    let path = state_path(opts)?;
+    let live = || -> Result<Option<State>, String> {
+        Ok(read(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .filter(|s| s.alive() && s.goal.is_some()))
+    };
+    if !args.follow {
+        let text = match (live()?, args.starship || args.polybar) {
+            (Some(state), true) => bar_line(&state, args.polybar),
+            (None, true) => return Ok(()),
+            (Some(state), false) => details(&state, opts),
+            (None, false) => return Err("no stopwatch running".to_string()),
+        };
+        println!("{}", text);
+        return Ok(());
+    }
+    let mut last = None;
+    let mut out = io::stdout();
+    loop {
+        let text = match live() {
+            Ok(Some(state)) if args.starship || args.polybar => bar_line(&state, args.polybar),
+            Ok(Some(state)) => details(&state, opts).replace('\n', "  "),
+            // keep going through a stopwatch restarting or a bad read
+            Ok(None) | Err(_) => String::new(),
+        };
+        if last.as_ref() != Some(&text) {
+            // a closed pipe means the bar has gone away
+            if writeln!(out, "{}", text)
+                .and_then(|()| out.flush())
+                .is_err()
+            {
+                return Ok(());
+            }
+            last = Some(text);
+        }
+        thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+/// `sw press toggle|split`: press a button on the running stopwatch, as
+/// the HTTP and MIDI inputs do, by sending it SIGUSR1 or SIGUSR2.
+/// Windows has no signals to send, so there it says to use `--http`.
+pub fn press(opts: &Options, button: Press) -> Result<(), String> {
+    let path = state_path(opts)?;
+    let state = read(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?
+        .filter(State::alive)
+        .ok_or("no stopwatch running")?;
+    platform::press(state.pid, button)
+        .map_err(|e| format!("can't signal process {}: {}", state.pid, e))
+}
+
+fn state_path(opts: &Options) -> Result<&Path, String> {
+    opts.state_file
+        .as_deref()
+        .ok_or_else(|| "no state file (set state_file or --state-file)".to_string())
+}
+
+/// `⏱ 01:23:45 build`, paused `⏸`. For polybar, clicking it toggles and a
+/// right click splits.
+fn bar_line(state: &State, polybar: bool) -> String {
+    let symbol = if state.running { '⏱' } else { '⏸' };
+    let name = state
+        .split
+        .as_deref()
+        .or(state.goal.as_deref())
+        .unwrap_or("");
+    let text = format!("{} {} {}", symbol, clock(state.elapsed_now()), name);
+    if !polybar {
+        return text;
+    }
+    // polybar ends an action at an unescaped ':'
+    format!(
+        "%{{A1:sw press toggle:}}%{{A3:sw press split:}}{}%{{A}}%{{A}}",
+        text.replace(':', "\\:")
+    )
+}
+
+fn details(state: &State, opts: &Options) -> String {
+    let mut lines = vec![
+        trf("Goal  : {}", &[&state.goal.as_deref().unwrap_or("")]),
+        trf("Time  : {}", &[&opts.format.format(state.elapsed_now())]),
+    ];
+    if let (Some(split), Some(elapsed)) = (&state.split, state.split_elapsed_now()) {
+        lines.push(trf(
+            "Split : {} ({})",
+            &[split, &opts.format.format(elapsed)],
+        ));
+    }
+    if !state.running {
+        lines.push(tr("Paused").to_string());
+    }
+    lines.join("\n")
+}
+
+/// `01:23:45`, with no fraction to keep a prompt steady.
+fn clock(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// A snapshot of the stopwatch as of `at`, from which the live time can be
+/// worked out without talking to the process.
+pub struct State {
+    pub at: DateTime<Local>,
+    pub pid: u32,
+    pub running: bool,
+    pub goal: Option<String>,
+    pub elapsed: Duration,
+    pub split: Option<String>,
+    pub split_elapsed: Option<Duration>,
+}
+
+impl State {
+    pub fn of(sw: &Stopwatch) -> State {
+        let elapsed = sw.total();
+        let active = sw.active.map(|i| &sw.splits[i]);
+        State {
+            at: Local::now(),
+            pid: process::id(),
+            running: sw.running,
+            goal: sw.main_goal.clone(),
+            elapsed,
+            split: active.map(|s| s.name.clone()),
+            split_elapsed: active.map(|s| s.length(elapsed)),
+        }
+    }
+
+    /// The goal's time now, counting on from the snapshot while running.
+    pub fn elapsed_now(&self) -> Duration {
+        self.elapsed + self.since()
+    }
+
+    pub fn split_elapsed_now(&self) -> Option<Duration> {
+        self.split_elapsed.map(|d| d + self.since())
+    }
+
+    fn since(&self) -> Duration {
+        if self.running {
+            (Local::now() - self.at).to_std().unwrap_or_default()
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Whether the process that wrote this is still around; a crash leaves
+    /// its last state behind.
+    pub fn alive(&self) -> bool {
+        platform::alive(self.pid)
+    }
+}
+
+/// Replace the state file, without readers ever seeing half of it.
+pub fn write(path: &Path, state: &State) -> io::Result<()> {
+    let text = Object::new()
+        .str("t", &state.at.to_rfc3339_opts(SecondsFormat::Millis, false))
+        .opt_num("pid", Some(state.pid as u64))
+        .bool("running", state.running)
+        .opt_str("goal", state.goal.as_deref())
+        .opt_num("elapsed_ms", Some(state.elapsed.as_millis() as u64))
+        .opt_str("split", state.split.as_deref())
+        .opt_num(
+            "split_elapsed_ms",
+            state.split_elapsed.map(|d| d.as_millis() as u64),
+        )
+        .finish();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, text + "\n")?;
+    fs::rename(&tmp, path)
+}
+
+/// The state in `path`, or `None` when no stopwatch has left one.
+pub fn read(path: &Path) -> io::Result<Option<State>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let fields = json::parse_object(&text).map_err(invalid)?;
+    let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+    let string = |key: &str| get(key).and_then(Value::as_str).map(str::to_string);
+    let millis = |key: &str| get(key).and_then(Value::as_u64).map(Duration::from_millis);
+    let at = string("t")
+        .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+        .ok_or_else(|| invalid("missing or bad field 't'".to_string()))?
+        .with_timezone(&Local);
+    // 0 and anything past a pid_t would signal whole process groups
+    let pid = get("pid")
+        .and_then(Value::as_u64)
+        .filter(|pid| (1..=i32::MAX as u64).contains(pid))
+        .ok_or_else(|| invalid("missing or bad field 'pid'".to_string()))?;
+    Ok(Some(State {
+        at,
+        pid: pid as u32,
+        running: get("running") == Some(&Value::Bool(true)),
+        goal: string("goal"),
+        elapsed: millis("elapsed_ms").unwrap_or_default(),
+        split: string("split"),
+        split_elapsed: millis("split_elapsed_ms"),
+    }))
+}
+
+/// Remove the state file if this process wrote it.
+pub fn clear(path: &Path) {
+    match read(path) {
+        Ok(Some(state)) if state.pid == process::id() => {
+            if let Err(e) = fs::remove_file(path) {
+                log::warn!("{}: {}", path.display(), e);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,831 @@
+use std::{
+    cmp::Ordering,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+
+pub const MAX_SPLITS: usize = 100;
+
+/// A change to the stopwatch state. Every change goes through
+/// [`Stopwatch::apply`] so it can be journaled and replayed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Start {
+        goal: String,
+        project: Option<String>,
+    },
+    Pause,
+    Resume,
+    Reset,
+    Split {
+        name: String,
+        parent: Option<usize>,
+    },
+    StopActive,
+    /// Stop the split at `index`, whether or not it is the active one.
+    Stop {
+        index: usize,
+    },
+    /// Move the split at `index` and everything under it to `parent`.
+    Reparent {
+        index: usize,
+        parent: Option<usize>,
+    },
+    /// Make a split a sibling of its parent.
+    Promote {
+        index: usize,
+    },
+    /// Make a split a child of its previous sibling.
+    Demote {
+        index: usize,
+    },
+    /// Fold a split into its previous sibling.
+    Merge {
+        index: usize,
+    },
+    /// Cut a finished split in two, `at` into it. The second part becomes
+    /// a new sibling called `name`.
+    Divide {
+        index: usize,
+        at: Duration,
+        name: String,
+    },
+    /// Give the split at `index` a new name.
+    Rename {
+        index: usize,
+        name: String,
+    },
+    /// Give the current goal a new name, which its log heading takes.
+    SetGoal {
+        goal: String,
+    },
+    /// Switch the project that splits from now on are recorded under.
+    SetProject {
+        project: Option<String>,
+    },
+    /// Put the current goal aside as done, ready for the next one.
+    Finish,
+    /// Move the active split up to its parent without stopping it.
+    Ascend,
+    /// Stop every open split, as done before saving an interrupted session.
+    CloseOpen,
+}
+
+/// Where the stopwatch gets the current time. Normally the real clock; session
+/// replays run it faster, and a simulated clock stands still until moved on.
+#[derive(Clone, Copy)]
+pub struct Clock {
+    real_origin: Instant,
+    origin: Instant,
+    speed: u32,
+    /// The wall time of a simulated clock when it was made.
+    simulated: Option<DateTime<Local>>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        let now = Instant::now();
+        Clock {
+            real_origin: now,
+            origin: now,
+            speed: 1,
+            simulated: None,
+        }
+    }
+}
+
+impl Clock {
+    /// A clock reading `origin` right now and running `speed` times faster
+    /// than real time from then on.
+    pub fn starting_at(origin: Instant, speed: u32) -> Clock {
+        Clock {
+            real_origin: Instant::now(),
+            origin,
+            speed,
+            simulated: None,
+        }
+    }
+
+    /// A clock reading `wall` on the wall clock that only moves with
+    /// `advance`, so a run comes out the same however long it really takes.
+    pub fn simulated(wall: DateTime<Local>) -> Clock {
+        // both origins the same instant, or the wall time comes out a hair
+        // short of each step
+        let now = Instant::now();
+        Clock {
+            real_origin: now,
+            origin: now,
+            speed: 0,
+            simulated: Some(wall),
+        }
+    }
+
+    /// Move the clock on by `by`, on top of however fast it runs.
+    pub fn advance(&mut self, by: Duration) {
+        self.origin += by;
+    }
+
+    pub fn now(&self) -> Instant {
+        if self.speed == 1 && self.origin == self.real_origin {
+            return Instant::now();
+        }
+        self.origin + self.real_origin.elapsed() * self.speed
+    }
+
+    /// The local time now by this clock.
+    pub fn wall(&self) -> DateTime<Local> {
+        match self.simulated {
+            Some(wall) => wall + (self.origin - self.real_origin),
+            None => Local::now(),
+        }
+    }
+
+    /// What this clock read at the real instant `real`, such as when a key
+    /// was pressed.
+    pub fn at(&self, real: Instant) -> Instant {
+        if self.speed == 1 && self.origin == self.real_origin {
+            return real;
+        }
+        self.origin + real.saturating_duration_since(self.real_origin) * self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: u32) {
+        *self = Clock::starting_at(self.now(), speed);
+    }
+}
+
+#[derive(Clone)]
+pub struct Split {
+    pub name: String,
+    pub start_offset: Duration,
+    pub end_offset: Option<Duration>,
+    pub start_dt: DateTime<Local>,
+    pub end_dt: Option<DateTime<Local>>,
+    pub parent: Option<usize>,
+    pub level: usize,
+    pub project: Option<String>,
+    /// Stretches between `start_offset` and `end_offset` that don't count
+    /// towards it: the time between the parts of a merged split.
+    pub gaps: Vec<Range<Duration>>,
+}
+
+impl Split {
+    /// Time spent in the split, up to `now` while it is running.
+    pub fn length(&self, now: Duration) -> Duration {
+        let gaps: Duration = self.gaps.iter().map(|gap| gap.end - gap.start).sum();
+        self.end_offset
+            .unwrap_or(now)
+            .saturating_sub(self.start_offset)
+            .saturating_sub(gaps)
+    }
+}
+
+/// A main goal finished earlier in the run, kept until the session is saved.
+pub struct Goal {
+    pub name: String,
+    pub project: Option<String>,
+    pub start_dt: DateTime<Local>,
+    pub end_dt: DateTime<Local>,
+    pub total: Duration,
+    pub splits: Vec<Split>,
+}
+
+pub struct Stopwatch {
+    pub running: bool,
+    pub start_time: Instant,
+    /// When the current goal was started, for wall-clock vs active time.
+    pub session_start: Instant,
+    pub elapsed: Duration,
+    pub splits: Vec<Split>,
+    pub active: Option<usize>,
+    pub main_goal: Option<String>,
+    /// When the current goal was started, in local time.
+    pub goal_start: DateTime<Local>,
+    /// Goals finished earlier in this run, oldest first.
+    pub finished: Vec<Goal>,
+    /// Project or client the time is for, copied onto each split.
+    pub project: Option<String>,
+    pub clock: Clock,
+}
+
+impl Default for Stopwatch {
+    fn default() -> Self {
+        Stopwatch::new()
+    }
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Stopwatch {
+            running: false,
+            start_time: Instant::now(),
+            session_start: Instant::now(),
+            elapsed: Duration::ZERO,
+            splits: Vec::with_capacity(MAX_SPLITS),
+            active: None,
+            main_goal: None,
+            goal_start: Local::now(),
+            finished: Vec::new(),
+            project: None,
+            clock: Clock::default(),
+        }
+    }
+
+    /// Elapsed time including the currently running stretch.
+    pub fn total(&self) -> Duration {
+        self.total_at(self.clock.now())
+    }
+
+    fn total_at(&self, now: Instant) -> Duration {
+        if self.running {
+            self.elapsed + now.saturating_duration_since(self.start_time)
+        } else {
+            self.elapsed
+        }
+    }
+
+    /// Apply `action` as happening right now.
+    pub fn apply(&mut self, action: &Action) {
+        self.apply_at(action, self.clock.now(), self.clock.wall());
+    }
+
+    /// Apply `action` as having happened at `now` (monotonic) / `wall`
+    /// (local time). Used when replaying the journal.
+    pub fn apply_at(&mut self, action: &Action, now: Instant, wall: DateTime<Local>) {
+        match action {
+            Action::Start { goal, project } => {
+                self.start(goal.clone(), project.clone(), now);
+                self.goal_start = wall;
+            }
+            Action::Finish => self.finish(now, wall),
+            Action::Pause if self.running => self.pause(now),
+            Action::Resume if !self.running => self.resume(now),
+            Action::Pause | Action::Resume => {}
+            Action::Reset => self.reset(),
+            Action::Split { name, parent } => self.push_split(name.clone(), *parent, now, wall),
+            Action::StopActive => self.stop_active(now, wall),
+            Action::Stop { index } => self.stop(*index, now, wall),
+            Action::Ascend => {
+                if let Some(idx) = self.active {
+                    self.active = self.splits[idx].parent;
+                }
+            }
+            Action::Reparent { index, parent } => self.reparent(*index, *parent),
+            Action::Promote { index } => self.promote(*index),
+            Action::Demote { index } => self.demote(*index),
+            Action::Merge { index } => self.merge(*index),
+            Action::Divide { index, at, name } => self.divide(*index, *at, name.clone()),
+            Action::Rename { index, name } => {
+                if let Some(split) = self.splits.get_mut(*index) {
+                    split.name = name.clone();
+                }
+            }
+            Action::SetGoal { goal } => {
+                if self.main_goal.is_some() {
+                    self.main_goal = Some(goal.clone());
+                }
+            }
+            Action::SetProject { project } => self.project = project.clone(),
+            Action::CloseOpen => self.close_open_splits(now, wall),
+        }
+    }
+
+    /// The split at `idx` and its descendants. Splits are kept in tree
+    /// order, so these are always contiguous.
+    pub fn subtree(&self, idx: usize) -> Range<usize> {
+        let level = self.splits[idx].level;
+        let end = self.splits[idx + 1..]
+            .iter()
+            .position(|s| s.level <= level)
+            .map_or(self.splits.len(), |n| idx + 1 + n);
+        idx..end
+    }
+
+    /// Time spent in the direct subgoals of the split at `idx`, with running
+    /// ones counted up to `now`; `None` when it has none.
+    pub fn rollup(&self, idx: usize, now: Duration) -> Option<Duration> {
+        let mut children = self
+            .splits
+            .iter()
+            .filter(|s| s.parent == Some(idx))
+            .peekable();
+        children.peek()?;
+        Some(children.map(|s| s.length(now)).sum())
+    }
+
+    /// Whether the split at `idx` can be moved under `parent`: both exist and
+    /// `parent` isn't the split itself or one of its descendants.
+    pub fn can_reparent(&self, idx: usize, parent: Option<usize>) -> bool {
+        idx < self.splits.len()
+            && parent.is_none_or(|p| p < self.splits.len() && !self.subtree(idx).contains(&p))
+    }
+
+    /// Where the split at `idx` ends up after being moved under `parent`.
+    pub fn reparented_index(&self, idx: usize, parent: Option<usize>) -> usize {
+        let len = self.subtree(idx).len();
+        match parent {
+            None => self.splits.len() - len,
+            Some(p) => {
+                let tree = self.subtree(p);
+                // the block is taken out first, which shifts the end back if
+                // it came from before it
+                if p > idx || tree.contains(&idx) {
+                    tree.end - len
+                } else {
+                    tree.end
+                }
+            }
+        }
+    }
+
+    /// The nearest earlier split with the same parent.
+    pub fn previous_sibling(&self, idx: usize) -> Option<usize> {
+        let parent = self.splits.get(idx)?.parent;
+        (0..idx).rev().find(|&j| self.splits[j].parent == parent)
+    }
+
+    /// Move a subtree to the end of `parent`'s children (or the top level).
+    fn reparent(&mut self, idx: usize, parent: Option<usize>) {
+        if self.can_reparent(idx, parent) {
+            let dest = self.reparented_index(idx, parent);
+            self.move_subtree(idx, parent, dest);
+        }
+    }
+
+    /// Where the split at `idx` ends up when promoted, if it has a parent.
+    pub fn promoted_index(&self, idx: usize) -> Option<usize> {
+        let parent = self.splits.get(idx)?.parent?;
+        Some(self.subtree(parent).end - self.subtree(idx).len())
+    }
+
+    /// Make the split at `idx` a sibling of its parent, just after it.
+    fn promote(&mut self, idx: usize) {
+        if let Some(dest) = self.promoted_index(idx) {
+            let grandparent = self.splits[self.splits[idx].parent.unwrap()].parent;
+            self.move_subtree(idx, grandparent, dest);
+        }
+    }
+
+    /// Make the split at `idx` the last child of its previous sibling. It
+    /// stays where it is in the list.
+    fn demote(&mut self, idx: usize) {
+        if let Some(sibling) = self.previous_sibling(idx) {
+            self.move_subtree(idx, Some(sibling), idx);
+        }
+    }
+
+    /// Fold the split at `idx` into its previous sibling. The merged split
+    /// starts where the first one did and ends where the later one does;
+    /// the time between them doesn't count, so it lasts as long as both
+    /// together. The second one's children move under it.
+    fn merge(&mut self, idx: usize) {
+        let Some(prev) = self.previous_sibling(idx) else {
+            return;
+        };
+        let second = self.splits.remove(idx);
+        let first = &mut self.splits[prev];
+        first.name = format!("{} + {}", first.name, second.name);
+        if let Some(end) = first.end_offset {
+            if second.start_offset > end {
+                first.gaps.push(end..second.start_offset);
+            }
+            match second.end_offset {
+                Some(second_end) if second_end < end => {}
+                _ => {
+                    first.end_offset = second.end_offset;
+                    first.end_dt = second.end_dt;
+                }
+            }
+        }
+        first.gaps.extend(second.gaps);
+        first.gaps.sort_by_key(|gap| gap.start);
+
+        let renumber = |i: usize| match i.cmp(&idx) {
+            Ordering::Less => i,
+            Ordering::Equal => prev,
+            Ordering::Greater => i - 1,
+        };
+        for split in &mut self.splits {
+            split.parent = split.parent.map(renumber);
+        }
+        self.active = self.active.map(renumber);
+    }
+
+    /// Cut the finished split at `idx` in two, `at` of its time in. Its
+    /// children stay with the first part; the second part goes right after
+    /// them.
+    fn divide(&mut self, idx: usize, at: Duration, name: String) {
+        let Some(split) = self.splits.get(idx) else {
+            return;
+        };
+        let (Some(end_offset), Some(end_dt)) = (split.end_offset, split.end_dt) else {
+            return;
+        };
+        if at.is_zero() || at >= split.length(end_offset) {
+            return;
+        }
+        // gaps before the cut push it on
+        let mut cut = split.start_offset + at;
+        for gap in &split.gaps {
+            if gap.start < cut {
+                cut += gap.end - gap.start;
+            }
+        }
+        let cut_dt = split.start_dt
+            + chrono::Duration::from_std(cut - split.start_offset).unwrap_or_default();
+        let (before, after) = split.gaps.iter().cloned().partition(|gap| gap.end <= cut);
+        let second = Split {
+            name,
+            start_offset: cut,
+            end_offset: Some(end_offset),
+            start_dt: cut_dt,
+            end_dt: Some(end_dt),
+            parent: split.parent,
+            level: split.level,
+            project: split.project.clone(),
+            gaps: after,
+        };
+        self.splits[idx].end_offset = Some(cut);
+        self.splits[idx].end_dt = Some(cut_dt);
+        self.splits[idx].gaps = before;
+
+        let pos = self.subtree(idx).end;
+        let renumber = |i: usize| if i >= pos { i + 1 } else { i };
+        for split in &mut self.splits {
+            split.parent = split.parent.map(renumber);
+        }
+        self.active = self.active.map(renumber);
+        self.splits.insert(pos, second);
+    }
+
+    /// Move the subtree at `idx` under `parent`, at position `dest` of the
+    /// list without it, renumbering parent links and levels to match.
+    fn move_subtree(&mut self, idx: usize, parent: Option<usize>, dest: usize) {
+        let block = self.subtree(idx);
+        // old index -> new index
+        let mut order: Vec<usize> = (0..self.splits.len())
+            .filter(|i| !block.contains(i))
+            .collect();
+        order.splice(dest..dest, block.clone());
+        let mut new_index = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+
+        let old_level = self.splits[idx].level;
+        let new_level = parent.map_or(0, |p| self.splits[p].level + 1);
+        self.splits[idx].parent = parent;
+        for i in block {
+            let split = &mut self.splits[i];
+            split.level = split.level + new_level - old_level;
+        }
+        for split in &mut self.splits {
+            split.parent = split.parent.map(|p| new_index[p]);
+        }
+        self.active = self.active.map(|a| new_index[a]);
+
+        let mut slots: Vec<Option<Split>> = self.splits.drain(..).map(Some).collect();
+        self.splits = order
+            .iter()
+            .map(|&old| slots[old].take().unwrap())
+            .collect();
+    }
+
+    fn start(&mut self, goal: String, project: Option<String>, now: Instant) {
+        self.main_goal = Some(goal);
+        self.project = project;
+        self.start_time = now;
+        self.session_start = now;
+        self.elapsed = Duration::ZERO;
+        self.splits.clear();
+        self.active = None;
+        self.running = true;
+    }
+
+    fn pause(&mut self, now: Instant) {
+        self.elapsed += now.saturating_duration_since(self.start_time);
+        self.running = false;
+    }
+
+    fn resume(&mut self, now: Instant) {
+        self.start_time = now;
+        self.running = true;
+    }
+
+    fn reset(&mut self) {
+        self.running = false;
+        self.elapsed = Duration::ZERO;
+        self.splits.clear();
+        self.main_goal = None;
+        self.project = None;
+        self.active = None;
+    }
+
+    /// Time spent paused since the goal was started.
+    pub fn paused(&self) -> Duration {
+        self.clock
+            .now()
+            .saturating_duration_since(self.session_start)
+            .checked_sub(self.total())
+            .unwrap_or_default()
+    }
+
+    /// Start a new split under `parent` and make it the active one.
+    fn push_split(
+        &mut self,
+        name: String,
+        parent: Option<usize>,
+        now: Instant,
+        wall: DateTime<Local>,
+    ) {
+        // the journal may be hand-edited; ignore parents that don't exist
+        let parent = parent.filter(|&idx| idx < self.splits.len());
+        let level = parent.map_or(0, |idx| self.splits[idx].level + 1);
+        self.splits.push(Split {
+            name,
+            start_offset: self.total_at(now),
+            end_offset: None,
+            start_dt: wall,
+            end_dt: None,
+            parent,
+            level,
+            project: self.project.clone(),
+            gaps: Vec::new(),
+        });
+        self.active = Some(self.splits.len() - 1);
+    }
+
+    /// Stop the active split and move up to its parent.
+    fn stop_active(&mut self, now: Instant, wall: DateTime<Local>) {
+        if let Some(idx) = self.active {
+            self.splits[idx].end_offset = Some(self.total_at(now));
+            self.splits[idx].end_dt = Some(wall);
+            self.active = self.splits[idx].parent;
+        }
+    }
+
+    /// Stop the split at `idx` if it is still open. Stopping the active
+    /// split moves up to its parent, as `stop_active` does.
+    fn stop(&mut self, idx: usize, now: Instant, wall: DateTime<Local>) {
+        if self.active == Some(idx) {
+            self.stop_active(now, wall);
+            return;
+        }
+        let end_off = self.total_at(now);
+        if let Some(split) = self.splits.get_mut(idx).filter(|s| s.end_offset.is_none()) {
+            split.end_offset = Some(end_off);
+            split.end_dt = Some(wall);
+        }
+    }
+
+    /// Stop the clock and every open split, and move the goal to `finished`.
+    /// The project carries over to whatever comes next.
+    fn finish(&mut self, now: Instant, wall: DateTime<Local>) {
+        let Some(name) = self.main_goal.take() else {
+            return;
+        };
+        self.close_open_splits(now, wall);
+        self.finished.push(Goal {
+            name,
+            project: self.project.clone(),
+            start_dt: self.goal_start,
+            end_dt: wall,
+            total: self.total_at(now),
+            splits: std::mem::take(&mut self.splits),
+        });
+        self.running = false;
+        self.elapsed = Duration::ZERO;
+    }
+
+    fn close_open_splits(&mut self, now: Instant, wall: DateTime<Local>) {
+        let end_off = self.total_at(now);
+        for split in self.splits.iter_mut().filter(|s| s.end_offset.is_none()) {
+            split.end_offset = Some(end_off);
+            split.end_dt = Some(wall);
+        }
+        self.active = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    /// A goal on a simulated clock with `steps` applied, each that many
+    /// minutes after it started.
+    fn session(steps: &[(u32, Action)]) -> Stopwatch {
+        let mut sw = Stopwatch::new();
+        sw.clock = Clock::simulated(Local::now());
+        sw.apply(&Action::Start {
+            goal: "Report".to_string(),
+            project: None,
+        });
+        let mut now = 0;
+        for (at, action) in steps {
+            sw.clock.advance(MINUTE * (at - now));
+            now = *at;
+            sw.apply(action);
+        }
+        sw
+    }
+
+    fn split(name: &str, parent: Option<usize>) -> Action {
+        Action::Split {
+            name: name.to_string(),
+            parent,
+        }
+    }
+
+    fn names(sw: &Stopwatch) -> Vec<(&str, Option<usize>, usize)> {
+        sw.splits
+            .iter()
+            .map(|s| (s.name.as_str(), s.parent, s.level))
+            .collect()
+    }
+
+    #[test]
+    fn merge_keeps_the_first_start() {
+        let mut sw = session(&[
+            (0, split("Outline", None)),
+            (10, Action::StopActive),
+            (15, split("More outline", None)),
+            (25, Action::StopActive),
+        ]);
+        let start_dt = sw.splits[0].start_dt;
+        sw.apply(&Action::Merge { index: 1 });
+
+        assert_eq!(names(&sw), [("Outline + More outline", None, 0)]);
+        let merged = &sw.splits[0];
+        assert_eq!(merged.start_offset, Duration::ZERO);
+        assert_eq!(merged.start_dt, start_dt);
+        assert_eq!(merged.end_offset, Some(MINUTE * 25));
+        // the five minutes between them don't count
+        assert_eq!(merged.length(sw.total()), MINUTE * 20);
+    }
+
+    #[test]
+    fn merge_brings_the_children_along() {
+        let mut sw = session(&[
+            (0, split("Draft", None)),
+            (1, split("Introduction", Some(0))),
+            (4, Action::StopActive),
+            (5, Action::StopActive),
+            (6, split("Redraft", None)),
+            (7, split("Conclusion", Some(2))),
+            (9, Action::StopActive),
+            (10, Action::StopActive),
+        ]);
+        sw.apply(&Action::Merge { index: 2 });
+
+        assert_eq!(
+            names(&sw),
+            [
+                ("Draft + Redraft", None, 0),
+                ("Introduction", Some(0), 1),
+                ("Conclusion", Some(0), 1),
+            ]
+        );
+        assert_eq!(sw.splits[0].length(sw.total()), MINUTE * 9);
+        assert_eq!(sw.rollup(0, sw.total()), Some(MINUTE * 5));
+    }
+
+    #[test]
+    fn merge_into_a_running_split() {
+        let mut sw = session(&[
+            (0, split("Tests", None)),
+            (10, Action::StopActive),
+            (12, split("More tests", None)),
+        ]);
+        sw.clock.advance(MINUTE * 8);
+        sw.apply(&Action::Merge { index: 1 });
+
+        assert_eq!(names(&sw), [("Tests + More tests", None, 0)]);
+        assert_eq!(sw.active, Some(0));
+        assert_eq!(sw.splits[0].end_offset, None);
+        assert_eq!(sw.splits[0].length(sw.total()), MINUTE * 18);
+    }
+
+    #[test]
+    fn merge_needs_a_previous_sibling() {
+        let mut sw = session(&[
+            (0, split("Draft", None)),
+            (1, split("Introduction", Some(0))),
+        ]);
+        sw.apply(&Action::Merge { index: 0 });
+        sw.apply(&Action::Merge { index: 1 });
+        assert_eq!(
+            names(&sw),
+            [("Draft", None, 0), ("Introduction", Some(0), 1)]
+        );
+        assert_eq!(sw.active, Some(1));
+    }
+
+    #[test]
+    fn divide_leaves_the_children_with_the_first_part() {
+        let mut sw = session(&[
+            (0, split("Draft", None)),
+            (1, split("Introduction", Some(0))),
+            (4, Action::StopActive),
+            (10, Action::StopActive),
+            (11, split("Review", None)),
+        ]);
+        sw.apply(&Action::Divide {
+            index: 0,
+            at: MINUTE * 6,
+            name: "Redraft".to_string(),
+        });
+
+        assert_eq!(
+            names(&sw),
+            [
+                ("Draft", None, 0),
+                ("Introduction", Some(0), 1),
+                ("Redraft", None, 0),
+                ("Review", None, 0),
+            ]
+        );
+        assert_eq!(sw.splits[0].end_offset, Some(MINUTE * 6));
+        assert_eq!(sw.splits[2].start_offset, MINUTE * 6);
+        assert_eq!(sw.splits[2].end_offset, Some(MINUTE * 10));
+        assert_eq!(sw.active, Some(3));
+    }
+
+    #[test]
+    fn divide_skips_the_gap_of_a_merge() {
+        let mut sw = session(&[
+            (0, split("Outline", None)),
+            (10, Action::StopActive),
+            (15, split("More outline", None)),
+            (25, Action::StopActive),
+        ]);
+        sw.apply(&Action::Merge { index: 1 });
+        sw.apply(&Action::Divide {
+            index: 0,
+            at: MINUTE * 15,
+            name: "Second half".to_string(),
+        });
+
+        let total = sw.total();
+        let (first, second) = (&sw.splits[0], &sw.splits[1]);
+        assert_eq!(first.end_offset, Some(MINUTE * 20));
+        assert_eq!(first.length(total), MINUTE * 15);
+        assert_eq!(second.start_offset, MINUTE * 20);
+        assert_eq!(second.length(total), MINUTE * 5);
+        assert!(second.gaps.is_empty());
+    }
+
+    #[test]
+    fn move_subtree_takes_the_children_along() {
+        let mut sw = session(&[
+            (0, split("Research", None)),
+            (1, Action::StopActive),
+            (2, split("Draft", None)),
+            (3, split("Introduction", Some(1))),
+            (4, split("Hook", Some(2))),
+        ]);
+        sw.apply(&Action::Reparent {
+            index: 1,
+            parent: Some(0),
+        });
+        assert_eq!(
+            names(&sw),
+            [
+                ("Research", None, 0),
+                ("Draft", Some(0), 1),
+                ("Introduction", Some(1), 2),
+                ("Hook", Some(2), 3),
+            ]
+        );
+        assert_eq!(sw.active, Some(3));
+
+        sw.apply(&Action::Promote { index: 2 });
+        assert_eq!(
+            names(&sw),
+            [
+                ("Research", None, 0),
+                ("Draft", Some(0), 1),
+                ("Introduction", Some(0), 1),
+                ("Hook", Some(2), 2),
+            ]
+        );
+
+        sw.apply(&Action::Reparent {
+            index: 1,
+            parent: None,
+        });
+        assert_eq!(
+            names(&sw),
+            [
+                ("Research", None, 0),
+                ("Introduction", Some(0), 1),
+                ("Hook", Some(1), 2),
+                ("Draft", None, 0),
+            ]
+        );
+        assert_eq!(sw.active, Some(2));
+    }
+}
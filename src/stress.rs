@@ -0,0 +1,137 @@
+use std::{
+    hint::black_box,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+
+use crate::journal::{self, Event, Record};
+use crate::options::{Options, StressArgs};
+use crate::stopwatch::Action;
+use crate::ui::{draw_dynamic, redraw, View};
+
+/// How many of the generated splits are left running, for `draw_dynamic`
+/// to update on every tick.
+const OPEN_SPLITS: usize = 8;
+
+/// `sw --stress`: time the timer and the screen code on a session with
+/// `args.splits` splits. The session is read back from its journal and
+/// every split rolled up, as on startup, then drawn into a writer that only
+/// counts bytes. Each tick is a `draw_dynamic` as the event loop does it,
+/// with a full `redraw` every hundred, as a key press would cause. Run it
+/// before and after a change to see what it costs; with `--max-tick` it
+/// fails past that.
+pub fn run(opts: &Options, args: &StressArgs) -> Result<(), String> {
+    let lines: Vec<String> = session(args.splits).iter().map(journal::encode).collect();
+    let at = Instant::now();
+    let records = lines
+        .iter()
+        .map(|line| journal::decode(line))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sw = journal::replay(&records).ok_or("the session is empty")?;
+    let replay = at.elapsed();
+    let at = Instant::now();
+    let total = sw.total();
+    for i in 0..sw.splits.len() {
+        black_box(sw.rollup(i, total));
+    }
+    let rollup = at.elapsed();
+    let view = View {
+        compact: opts.compact,
+        ..View::default()
+    };
+    let mut out = Counter::default();
+    let fail = |e: io::Error| e.to_string();
+
+    let mut full = Duration::ZERO;
+    let mut full_count = 0;
+    let mut dynamic = Duration::ZERO;
+    let started = Instant::now();
+    for tick in 0..args.ticks {
+        if tick % 100 == 0 {
+            let at = Instant::now();
+            redraw(&mut out, opts, &sw, &view).map_err(fail)?;
+            full += at.elapsed();
+            full_count += 1;
+        }
+        let at = Instant::now();
+        draw_dynamic(&mut out, opts, &sw, &view).map_err(fail)?;
+        dynamic += at.elapsed();
+    }
+    let wall = started.elapsed();
+
+    let per = |total: Duration, n: usize| total / n.max(1) as u32;
+    println!("splits      : {}", sw.splits.len());
+    println!("replay      : {:?} ({} records)", replay, records.len());
+    println!("rollup      : {:?} for every split", rollup);
+    println!("ticks       : {}", args.ticks);
+    println!(
+        "redraw      : {:?} each ({} times)",
+        per(full, full_count),
+        full_count
+    );
+    println!("draw_dynamic: {:?} each", per(dynamic, args.ticks));
+    println!(
+        "output      : {} bytes, {} per tick",
+        out.bytes,
+        out.bytes / args.ticks.max(1) as u64
+    );
+    println!(
+        "total       : {:?} ({:.0} ticks/s)",
+        wall,
+        args.ticks as f64 / wall.as_secs_f64().max(f64::EPSILON)
+    );
+    match args.max_tick {
+        Some(max) if per(dynamic, args.ticks) > max => Err(format!(
+            "draw_dynamic took {:?} a tick, over --max-tick {:?}",
+            per(dynamic, args.ticks),
+            max
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The journal of a goal running with `count` splits a minute apart, every
+/// fourth nested under the one before, and the last few still open.
+fn session(count: usize) -> Vec<Record> {
+    let start = Local::now() - chrono::Duration::minutes(count as i64 + 1);
+    let record = |seconds: usize, action| Record {
+        at: start + chrono::Duration::seconds(seconds as i64),
+        event: Event::Action(action),
+    };
+    let mut records = vec![record(
+        0,
+        Action::Start {
+            goal: "Stress test".to_string(),
+            project: None,
+        },
+    )];
+    for i in 0..count {
+        let at = 60 * (i + 1);
+        let parent = if i % 4 == 3 { Some(i - 1) } else { None };
+        let name = format!("Split {} with a longer name to fill the row", i + 1);
+        records.push(record(at, Action::Split { name, parent }));
+        if i + OPEN_SPLITS < count {
+            records.push(record(at + 30, Action::Stop { index: i }));
+        }
+    }
+    records
+}
+
+/// Discards what is written, counting it.
+#[derive(Default)]
+struct Counter {
+    bytes: u64,
+}
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
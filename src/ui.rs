@@ -0,0 +1,1333 @@
+use std::{
+    fmt,
+    io::{self, BufRead, IsTerminal, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use crossterm::cursor::{MoveTo, MoveToColumn, MoveToPreviousLine};
+use crossterm::event::{poll, read, DisableFocusChange, EnableFocusChange, Event};
+use crossterm::style::{
+    Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::terminal::{
+    self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use crossterm::QueueableCommand;
+
+use crate::duration::{DurationFormat, Style};
+use crate::error::Error;
+use crate::locale::{tr, trf, trf_display};
+use crate::options::Options;
+use crate::outlier::{outliers, Outlier};
+use crate::platform;
+use crate::sequence::{Progress, Sequence};
+use crate::stopwatch::{Split, Stopwatch};
+use crate::width;
+
+/// xterm title stack controls, so the user's own title comes back on exit.
+pub const PUSH_TITLE: &str = "\x1b[22;0t";
+pub const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Text written by a closure. What is redrawn on every tick is written
+/// straight to the terminal this way, rather than built up as strings first.
+struct Lazy<F>(F);
+
+fn lazy<F: Fn(&mut fmt::Formatter) -> fmt::Result>(f: F) -> Lazy<F> {
+    Lazy(f)
+}
+
+impl<F: Fn(&mut fmt::Formatter) -> fmt::Result> fmt::Display for Lazy<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.0)(f)
+    }
+}
+
+/// Header time line, with the distance to `--target` when one is set.
+pub fn time_line(total: Duration, rate: Option<f64>, opts: &Options) -> impl fmt::Display + '_ {
+    lazy(move |f| {
+        let fmt = &opts.format;
+        match opts.target {
+            None => write!(f, "{}", trf_display("Time  : {}", &[&fmt.display(total)]))?,
+            Some(t) if total <= t => write!(
+                f,
+                "{}",
+                trf_display(
+                    "Time  : {}  ({} left of {})",
+                    &[
+                        &fmt.display(total),
+                        &fmt.display(t - total),
+                        &fmt.display(t)
+                    ],
+                )
+            )?,
+            Some(t) => write!(
+                f,
+                "{}",
+                trf_display(
+                    "Time  : {}  ({} over {})",
+                    &[
+                        &fmt.display(total),
+                        &fmt.display(total - t),
+                        &fmt.display(t)
+                    ],
+                )
+            )?,
+        }
+        if let Some(rate) = rate {
+            write!(f, "  {}", opts.rates.amount(total, rate))?;
+        }
+        Ok(())
+    })
+}
+
+/// The distance to `--target` and the amount earned, on a line of their own
+/// under the time in a narrow terminal.
+fn time_extras(total: Duration, rate: Option<f64>, opts: &Options) -> impl fmt::Display + '_ {
+    lazy(move |f| {
+        let fmt = &opts.format;
+        match opts.target {
+            None => {}
+            Some(t) if total <= t => write!(
+                f,
+                "{}",
+                trf_display(
+                    "({} left of {})",
+                    &[&fmt.display(t - total), &fmt.display(t)]
+                )
+            )?,
+            Some(t) => write!(
+                f,
+                "{}",
+                trf_display("({} over {})", &[&fmt.display(total - t), &fmt.display(t)])
+            )?,
+        }
+        if let Some(rate) = rate {
+            if opts.target.is_some() {
+                f.write_str("  ")?;
+            }
+            write!(f, "{}", opts.rates.amount(total, rate))?;
+        }
+        Ok(())
+    })
+}
+
+const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// The time line with the spinner after it. The frame follows the elapsed
+/// time, so it stands still while paused. When the layout is `stacked` the
+/// extras go on the next line instead.
+fn time_line_with_spinner<'a>(
+    sw: &'a Stopwatch,
+    opts: &'a Options,
+    stacked: bool,
+) -> impl fmt::Display + 'a {
+    let total = sw.total();
+    lazy(move |f| {
+        if stacked {
+            let fmt = &opts.format;
+            write!(f, "{}", trf_display("Time  : {}", &[&fmt.display(total)]))?;
+        } else {
+            write!(f, "{}", time_line(total, live_rate(sw, opts), opts))?;
+        }
+        if opts.spinner && sw.main_goal.is_some() {
+            let frame = (total.as_millis() / 100) as usize % SPINNER.len();
+            write!(f, "  {}", SPINNER[frame])?;
+        }
+        Ok(())
+    })
+}
+
+/// Terminal title text: whole seconds only, so it changes at most once a second.
+pub fn title_text(
+    total: Duration,
+    running: bool,
+    main_goal: Option<&str>,
+    fmt: &DurationFormat,
+) -> String {
+    let time = fmt.format(Duration::from_secs(total.as_secs()));
+    let time = match fmt.style {
+        Style::Clock => time.trim_end_matches(".000"),
+        _ => &time,
+    };
+    match (main_goal, running) {
+        (Some(goal), true) => format!("{} {}", time, goal),
+        (Some(goal), false) => trf("{} {} (paused)", &[&time, &goal]),
+        (None, _) => trf("{} stopwatch", &[&time]),
+    }
+}
+
+pub fn clear_screen<W: Write>(out: &mut W) -> io::Result<()> {
+    out.queue(Clear(ClearType::All))?;
+    out.queue(MoveTo(0, 0))?;
+    Ok(())
+}
+
+/// Presentation state that isn't part of the timing data.
+#[derive(Default)]
+pub struct View {
+    /// Split highlighted in the list, moved with the arrow keys.
+    pub selected: Option<usize>,
+    /// One-off feedback shown under the controls until the next key press.
+    pub message: Option<String>,
+    /// Error banner, shown in place of the message until the next key press.
+    pub error: Option<String>,
+    /// Keys typed so far towards a longer key sequence.
+    pub pending: Option<String>,
+    /// Show just the duration and name of each split.
+    pub compact: bool,
+    /// Hide splits that have ended.
+    pub open_only: bool,
+    /// List only the finished splits, longest first.
+    pub sorted: bool,
+    /// Show each top-level subgoal's share of the time as a bar instead of
+    /// the list.
+    pub breakdown: bool,
+    /// How far `--sequence` has got.
+    pub progress: Progress,
+    /// A split being named. Keys go to the name while the clock runs on.
+    pub naming: Option<Naming>,
+    /// A goal waiting out `--lead-in`.
+    pub countdown: Option<Countdown>,
+    /// The split timing each `--watch` path that is there now.
+    pub watched: Vec<(PathBuf, usize)>,
+    /// How many of the `--alert` marks the goal's time has passed.
+    pub passed: usize,
+    /// Whether the goal was paused at `--max-duration`; it can be resumed
+    /// past it.
+    pub capped: bool,
+    /// Show `MASK` in place of goal, project and split names, for sharing
+    /// the screen. Only the display changes.
+    pub private: bool,
+    /// Columns and rows to draw for in place of the terminal's own size.
+    pub size: Option<(u16, u16)>,
+}
+
+/// Stands in for each name while the names are hidden.
+pub const MASK: &str = "•••";
+
+/// A goal that starts at `at`, which is `scheduled` on the wall clock when
+/// it came from `--start-at`. `shown` is the last whole second drawn.
+pub struct Countdown {
+    pub goal: String,
+    pub project: Option<String>,
+    pub at: Instant,
+    pub scheduled: Option<DateTime<Local>>,
+    pub shown: Option<u128>,
+}
+
+/// The name typed so far for the split at `index`.
+pub struct Naming {
+    pub index: usize,
+    pub text: String,
+}
+
+impl View {
+    /// `name` as it is shown: masked while the names are hidden.
+    pub fn name<'a>(&self, name: &'a str) -> &'a str {
+        if self.private {
+            MASK
+        } else {
+            name
+        }
+    }
+
+    pub fn show_error(&mut self, e: Error) {
+        log::warn!("{}", e);
+        self.error = Some(e.to_string());
+    }
+
+    /// Clear the message and error banner. Returns true if either was shown.
+    pub fn dismiss(&mut self) -> bool {
+        let shown = self.message.is_some() || self.error.is_some();
+        self.message = None;
+        self.error = None;
+        shown
+    }
+
+    /// Indices of the splits in the list, in order; none while the
+    /// breakdown is shown.
+    pub fn shown(&self, sw: &Stopwatch) -> Vec<usize> {
+        if self.breakdown {
+            return Vec::new();
+        }
+        if self.sorted {
+            let mut done: Vec<(Duration, usize)> = sw
+                .splits
+                .iter()
+                .enumerate()
+                .filter_map(|(i, s)| Some((s.length(s.end_offset?), i)))
+                .collect();
+            done.sort_by_key(|&(dur, _)| std::cmp::Reverse(dur));
+            return done.into_iter().map(|(_, i)| i).collect();
+        }
+        (0..sw.splits.len())
+            .filter(|&i| !self.open_only || sw.splits[i].end_offset.is_none())
+            .collect()
+    }
+
+    pub fn select_prev(&mut self, shown: &[usize]) {
+        let pos = self
+            .selected
+            .and_then(|i| shown.iter().position(|&s| s == i));
+        self.selected = match pos {
+            _ if shown.is_empty() => None,
+            Some(p) if p > 0 => Some(shown[p - 1]),
+            Some(_) => Some(shown[0]),
+            None => shown.last().copied(),
+        };
+    }
+
+    pub fn select_next(&mut self, shown: &[usize]) {
+        let pos = self
+            .selected
+            .and_then(|i| shown.iter().position(|&s| s == i));
+        self.selected = match pos {
+            _ if shown.is_empty() => None,
+            Some(p) => Some(shown[(p + 1).min(shown.len() - 1)]),
+            None => Some(shown[0]),
+        };
+    }
+}
+
+/// Full redraw of the screen (or the status line in inline mode). Drawing
+/// only queues commands; each frame is written out with a single flush at
+/// the end, so it reaches the terminal in one piece.
+pub fn redraw<W: Write>(
+    out: &mut W,
+    opts: &Options,
+    sw: &Stopwatch,
+    view: &View,
+) -> io::Result<()> {
+    if opts.inline {
+        draw_inline(out, opts, sw, view)
+    } else {
+        draw_static(out, opts, sw, view)
+    }
+}
+
+/// One row of the split list. `live` is the current total for splits that
+/// are still running; without it open splits show placeholders.
+pub fn split_line(i: usize, split: &Split, live: Option<Duration>, fmt: &DurationFormat) -> String {
+    split_row(i, split, &split.name, live, fmt, None).to_string()
+}
+
+/// `split_line` with `label` for the name, cut down to `name_max` columns
+/// if given.
+fn split_row<'a>(
+    i: usize,
+    split: &'a Split,
+    label: &'a str,
+    live: Option<Duration>,
+    fmt: &'a DurationFormat,
+    name_max: Option<usize>,
+) -> impl fmt::Display + 'a {
+    lazy(move |f| {
+        write!(f, "{:2}) {} -> ", i + 1, fmt.display(split.start_offset))?;
+        match split.end_offset.or(live) {
+            Some(end_off) => {
+                let dur = split.length(end_off);
+                write!(f, "{} = {}", fmt.display(end_off), fmt.display(dur))?;
+            }
+            None => {
+                let pending = fmt.placeholder();
+                write!(f, "{} = {}", pending, pending)?;
+            }
+        }
+        write!(f, " {}", name(label, name_max))
+    })
+}
+
+fn name(name: &str, max: Option<usize>) -> width::Ellipsized<'_> {
+    width::ellipsize(name, max.unwrap_or(usize::MAX))
+}
+
+/// `split_line`, or just the duration and name in the compact view, with
+/// the time in its subgoals after a parent's name, which is `label`.
+fn row_text<'a>(
+    sw: &'a Stopwatch,
+    (i, label): (usize, &'a str),
+    live: Option<Duration>,
+    compact: bool,
+    fmt: &'a DurationFormat,
+    name_max: Option<usize>,
+) -> impl fmt::Display + 'a {
+    let split = &sw.splits[i];
+    let rollup = sw.rollup(i, sw.total());
+    lazy(move |f| {
+        if compact {
+            write!(f, "{:2}) ", i + 1)?;
+            match split.end_offset.or(live) {
+                Some(end_off) => write!(f, "{}", fmt.display(split.length(end_off)))?,
+                None => f.write_str(fmt.placeholder())?,
+            }
+            write!(f, " {}", name(label, name_max))?;
+        } else {
+            write!(f, "{}", split_row(i, split, label, live, fmt, name_max))?;
+        }
+        if let Some(rollup) = rollup {
+            write!(f, " [Σ {}]", fmt.display(rollup))?;
+        }
+        Ok(())
+    })
+}
+
+/// Split colors by nesting level, repeating for deeper levels.
+const LEVEL_COLORS: &[Color] = &[
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Blue,
+];
+
+/// Marks the active split in the left margin.
+const ACTIVE_MARKER: &str = "▶";
+
+/// After the longest and shortest of a group of finished siblings.
+fn outlier_mark(outlier: Outlier) -> (&'static str, Color) {
+    match outlier {
+        Outlier::Longest => (" ▲", Color::Red),
+        Outlier::Shortest => (" ▼", Color::Green),
+    }
+}
+
+/// Split `i` on `row`, indented by level after a margin for the active
+/// split's marker and followed by its outlier mark, if any. A row wider
+/// than the terminal would wrap into the next one, so the name is cut short
+/// to fit, and a narrow terminal gets the compact rows.
+fn print_split_row<W: Write>(
+    out: &mut W,
+    sw: &Stopwatch,
+    (i, row, outlier): (usize, u16, Option<Outlier>),
+    live: Option<Duration>,
+    view: &View,
+    (opts, layout): (&Options, &Layout),
+) -> io::Result<()> {
+    let split = &sw.splits[i];
+    let active = sw.active == Some(i);
+    out.queue(MoveTo(0, row))?;
+    out.queue(Print(if active { ACTIVE_MARKER } else { " " }))?;
+    // the sorted list doesn't follow the tree, so nesting would mislead
+    let indent = if view.sorted { 0 } else { split.level * 2 };
+    out.queue(MoveTo(2 + indent as u16, row))?;
+    if opts.color {
+        out.queue(SetForegroundColor(
+            LEVEL_COLORS[split.level % LEVEL_COLORS.len()],
+        ))?;
+    }
+    if active {
+        out.queue(SetAttribute(Attribute::Bold))?;
+    }
+    if view.selected == Some(i) {
+        out.queue(SetAttribute(Attribute::Reverse))?;
+    }
+    let mark = outlier.map(outlier_mark);
+    let room = layout
+        .cols
+        .saturating_sub(2 + indent + mark.map_or(0, |(text, _)| width::of_str(text)));
+    let compact = view.compact || layout.narrow;
+    let label = view.name(&split.name);
+    let full = width::of(&row_text(sw, (i, label), live, compact, &opts.format, None));
+    let name_max = (full > room).then(|| width::of_str(label).saturating_sub(full - room));
+    let line = row_text(sw, (i, label), live, compact, &opts.format, name_max);
+    // only the times are left when even they don't fit
+    out.queue(Print(width::fit(line, room)))?;
+    // SGR 0 ends the attributes as well as the color, without the string
+    // `SetAttribute` builds each time
+    out.queue(ResetColor)?;
+    if let Some((text, color)) = mark {
+        if opts.color {
+            out.queue(SetForegroundColor(color))?;
+        }
+        out.queue(Print(text))?;
+        out.queue(ResetColor)?;
+    }
+    Ok(())
+}
+
+pub fn draw_static<W: Write>(
+    out: &mut W,
+    opts: &Options,
+    sw: &Stopwatch,
+    view: &View,
+) -> io::Result<()> {
+    let layout = Layout::of(opts, sw, screen_size(view));
+    clear_screen(out)?;
+    if layout.too_small {
+        out.queue(Print(width::fit(tr("Terminal too small"), layout.cols)))?;
+        return out.flush();
+    }
+    let shown = view.shown(sw);
+    out.queue(Print(tr("=== Stopwatch ===")))?;
+    if !sw.finished.is_empty() {
+        out.queue(Print(trf(" ({} goals done)", &[&sw.finished.len()])))?;
+    }
+    draw_header(out, opts, sw, view, &layout)?;
+    out.queue(MoveTo(0, layout.top))?;
+    let rows = if view.breakdown {
+        draw_breakdown(out, opts, sw, view, &layout)?
+    } else {
+        draw_list(out, opts, sw, view, (&shown, &layout))?
+    };
+
+    let controls_line_row = layout.top + 1 + rows as u16 + 1;
+    // the controls wrap in a narrow terminal and push the rest down
+    let controls = |text| {
+        width::fit(
+            text,
+            if layout.narrow {
+                layout.cols
+            } else {
+                usize::MAX
+            },
+        )
+    };
+    out.queue(MoveTo(0, controls_line_row + 1))?;
+    out.queue(Print(controls(tr("Controls: s=start/stop r=reset c=continue g=subgoal n=nested h=stop u=up d=redraw t=save-log q=quit"))))?;
+    out.queue(MoveTo(0, controls_line_row + 2))?;
+    out.queue(Print(controls(tr(
+        "          up/down=select 1-9=stop-split m=move <>=level J=merge x=cut p=project G=goal f=next-goal v=view o=open-only S=sort b=breakdown P=private y=copy-time Y=copy-split w=copy-summary a=quick-split e=name",
+    ))))?;
+    if let Some(countdown) = &view.countdown {
+        out.queue(MoveTo(0, controls_line_row + 3))?;
+        let left = countdown
+            .at
+            .saturating_duration_since(sw.clock.now())
+            .as_millis()
+            .div_ceil(1000);
+        let text = match countdown.scheduled {
+            Some(wall) => {
+                let left = format!("{}:{:02}:{:02}", left / 3600, left / 60 % 60, left % 60);
+                let wall = wall.format("%H:%M:%S");
+                trf(
+                    "Starting {} at {}, in {} (Esc to cancel)",
+                    &[&view.name(&countdown.goal), &wall, &left],
+                )
+            }
+            None => trf(
+                "Starting {} in {} (Esc to cancel)",
+                &[&view.name(&countdown.goal), &left],
+            ),
+        };
+        out.queue(Print(text))?;
+    } else if let Some(naming) = &view.naming {
+        out.queue(MoveTo(0, controls_line_row + 3))?;
+        out.queue(Print(trf(
+            "Name split {}: {}_",
+            &[&(naming.index + 1), &view.name(&naming.text)],
+        )))?;
+    } else if let Some(keys) = &view.pending {
+        out.queue(MoveTo(0, controls_line_row + 3))?;
+        out.queue(Print(trf("Keys: {} ...", &[keys])))?;
+    }
+    if let Some(err) = &view.error {
+        out.queue(MoveTo(0, controls_line_row + 4))?;
+        out.queue(SetForegroundColor(Color::White))?;
+        out.queue(SetBackgroundColor(Color::DarkRed))?;
+        out.queue(SetAttribute(Attribute::Bold))?;
+        out.queue(Print(format!(" {} ", trf("Error: {}", &[err]))))?;
+        out.queue(SetAttribute(Attribute::Reset))?;
+        out.queue(ResetColor)?;
+    } else if let Some(msg) = &view.message {
+        out.queue(MoveTo(0, controls_line_row + 4))?;
+        out.queue(Print(msg))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// The goal and time lines, and the time's extras under them when stacked.
+/// Each is kept clear of the last columns, where the spaces `draw_dynamic`
+/// writes after them go.
+fn draw_header<W: Write>(
+    out: &mut W,
+    opts: &Options,
+    sw: &Stopwatch,
+    view: &View,
+    layout: &Layout,
+) -> io::Result<()> {
+    let room = layout.cols.saturating_sub(3);
+    out.queue(MoveTo(0, 1))?;
+    let goal = goal_label(sw, view);
+    out.queue(Print(width::fit(trf_display("Goal  : {}", &[&goal]), room)))?;
+    out.queue(Print("   "))?;
+    out.queue(MoveTo(0, 2))?;
+    let time = time_line_with_spinner(sw, opts, layout.stacked);
+    out.queue(Print(width::fit(time, room)))?;
+    out.queue(Print("   "))?;
+    if layout.stacked {
+        out.queue(MoveTo(2, 3))?;
+        let extras = time_extras(sw.total(), live_rate(sw, opts), opts);
+        out.queue(Print(width::fit(extras, room.saturating_sub(2))))?;
+        out.queue(Print("   "))?;
+    }
+    if layout.lap {
+        out.queue(MoveTo(0, 3 + u16::from(layout.stacked)))?;
+        let lap = opts
+            .format
+            .display(sw.total().saturating_sub(last_split(sw)));
+        out.queue(Print(width::fit(trf_display("Lap   : {}", &[&lap]), room)))?;
+        out.queue(Print("   "))?;
+    }
+    if let Some(seq) = &opts.sequence {
+        out.queue(MoveTo(0, layout.top - 1))?;
+        let phase = phase_line(sw, seq, view, &opts.format);
+        out.queue(Print(width::fit(phase, room)))?;
+        out.queue(Print("   "))?;
+    }
+    Ok(())
+}
+
+/// When the latest split started or stopped, whichever split that was.
+fn last_split(sw: &Stopwatch) -> Duration {
+    sw.splits
+        .iter()
+        .flat_map(|s| [Some(s.start_offset), s.end_offset])
+        .flatten()
+        .max()
+        .unwrap_or_default()
+}
+
+/// Where `--sequence` has got: the phase to come, or the one running and
+/// how long it has left.
+fn phase_line<'a>(
+    sw: &'a Stopwatch,
+    seq: &'a Sequence,
+    view: &'a View,
+    fmt: &'a DurationFormat,
+) -> impl fmt::Display + 'a {
+    let count = seq.phases.len();
+    lazy(move |f| match view.progress {
+        Progress::Ready => {
+            let first = &seq.phases[0];
+            let length = fmt.display(first.length);
+            let args: [&dyn fmt::Display; 4] = [&view.name(&first.name), &1, &count, &length];
+            write!(f, "{}", trf_display("Next  : {} ({}/{}, {})", &args))
+        }
+        Progress::At { phase, split, .. } => {
+            let current = &seq.phases[phase];
+            let label = view.name(&current.name);
+            let ran = sw
+                .splits
+                .get(split)
+                .map_or(Duration::ZERO, |s| s.length(sw.total()));
+            // only routine steps run past their length
+            match current.length.checked_sub(ran) {
+                Some(left) => {
+                    let left = fmt.display(left);
+                    let args: [&dyn fmt::Display; 4] = [&label, &(phase + 1), &count, &left];
+                    write!(f, "{}", trf_display("Phase : {} ({}/{}), {} left", &args))
+                }
+                None => {
+                    let over = fmt.display(ran - current.length);
+                    let args: [&dyn fmt::Display; 4] = [&label, &(phase + 1), &count, &over];
+                    write!(f, "{}", trf_display("Phase : {} ({}/{}), {} over", &args))
+                }
+            }
+        }
+        Progress::Done => write!(f, "{}", trf_display("Phase : all {} done", &[&count])),
+    })
+}
+
+/// Columns below which the split rows drop their start and end times and
+/// the header stacks.
+const NARROW_COLS: usize = 60;
+/// Below this there is no sensible layout at all.
+const MIN_COLS: usize = 24;
+const MIN_ROWS: usize = 8;
+
+/// Where things go on the terminal at its current size.
+struct Layout {
+    cols: usize,
+    narrow: bool,
+    /// Whether the target and amount have a line of their own. The time
+    /// since the last split, once there is one, and the phase of
+    /// `--sequence` have one under them either way.
+    stacked: bool,
+    lap: bool,
+    /// The row of the list header; the splits start on the next one.
+    top: u16,
+    too_small: bool,
+}
+
+impl Layout {
+    fn of(opts: &Options, sw: &Stopwatch, (cols, rows): (usize, usize)) -> Layout {
+        let narrow = cols < NARROW_COLS;
+        let stacked = narrow && (opts.target.is_some() || live_rate(sw, opts).is_some());
+        let lap = !sw.splits.is_empty();
+        Layout {
+            cols,
+            narrow,
+            stacked,
+            lap,
+            top: 3 + u16::from(stacked) + u16::from(lap) + u16::from(opts.sequence.is_some()),
+            too_small: cols < MIN_COLS || rows < MIN_ROWS,
+        }
+    }
+}
+
+/// The split list under its header. Returns the rows used.
+fn draw_list<W: Write>(
+    out: &mut W,
+    opts: &Options,
+    sw: &Stopwatch,
+    view: &View,
+    (shown, layout): (&[usize], &Layout),
+) -> io::Result<usize> {
+    let header = if view.sorted {
+        trf(
+            "Longest finished subgoals ({} of {}):",
+            &[&shown.len(), &sw.splits.len()],
+        )
+    } else if view.open_only {
+        trf(
+            "Open subgoals ({} of {}):",
+            &[&shown.len(), &sw.splits.len()],
+        )
+    } else {
+        trf("Subgoals ({}):", &[&sw.splits.len()])
+    };
+    out.queue(Print(width::fit(header, layout.cols)))?;
+    let finished: Vec<(Option<usize>, Option<Duration>)> = sw
+        .splits
+        .iter()
+        .map(|s| (s.parent, s.end_offset.map(|end| s.length(end))))
+        .collect();
+    let outliers = outliers(&finished);
+    for (row, &i) in shown.iter().enumerate() {
+        let at = (i, layout.top + 1 + row as u16, outliers[i]);
+        print_split_row(out, sw, at, None, view, (opts, layout))?;
+    }
+    Ok(shown.len())
+}
+
+const BAR_WIDTH: usize = 30;
+const BAR_EIGHTHS: &[char] = &['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// `share` of `width` cells filled, to an eighth of a cell.
+fn bar(share: f64, width: usize) -> String {
+    let eighths = (share.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(&partial) = (eighths % 8)
+        .checked_sub(1)
+        .and_then(|i| BAR_EIGHTHS.get(i))
+    {
+        bar.push(partial);
+    }
+    let pad = width - bar.chars().count();
+    bar.push_str(&" ".repeat(pad));
+    bar
+}
+
+/// Each top-level subgoal's share of the goal's time as a bar, with the
+/// time outside any subgoal last. Returns the rows used.
+fn draw_breakdown<W: Write>(
+    out: &mut W,
+    opts: &Options,
+    sw: &Stopwatch,
+    view: &View,
+    layout: &Layout,
+) -> io::Result<usize> {
+    let total = sw.total();
+    let mut parts: Vec<(&str, Duration)> = sw
+        .splits
+        .iter()
+        .filter(|s| s.parent.is_none())
+        .map(|s| (view.name(&s.name), s.length(total)))
+        .collect();
+    let covered: Duration = parts.iter().map(|&(_, dur)| dur).sum();
+    if let Some(rest) = total.checked_sub(covered).filter(|d| !d.is_zero()) {
+        parts.push((tr("(no subgoal)"), rest));
+    }
+    out.queue(Print(tr("Time by subgoal:")))?;
+    let name_cap = if layout.narrow { 12 } else { 24 };
+    let width = parts
+        .iter()
+        .map(|(name, _)| width::of_str(name))
+        .max()
+        .unwrap_or(0)
+        .min(name_cap);
+    // the bar takes what the name, share and time leave
+    let time_width = width::of(&opts.format.display(total));
+    let others = 2 + width + 1 + 8 + time_width;
+    let bar_width = layout.cols.saturating_sub(others).clamp(4, BAR_WIDTH);
+    for (row, &(name, dur)) in parts.iter().enumerate() {
+        let share = if total.is_zero() {
+            0.0
+        } else {
+            dur.as_secs_f64() / total.as_secs_f64()
+        };
+        let name = width::ellipsize(name, width);
+        let pad = width - width::of(&name);
+        out.queue(MoveTo(2, layout.top + 1 + row as u16))?;
+        out.queue(Print(format!("{}{:pad$} ", name, "", pad = pad)))?;
+        if opts.color {
+            out.queue(SetForegroundColor(LEVEL_COLORS[row % LEVEL_COLORS.len()]))?;
+        }
+        out.queue(Print(bar(share, bar_width)))?;
+        if opts.color {
+            out.queue(ResetColor)?;
+        }
+        out.queue(Print(format!(
+            " {:5.1}% {}",
+            share * 100.0,
+            opts.format.format(dur)
+        )))?;
+    }
+    Ok(parts.len())
+}
+
+pub fn draw_dynamic<W: Write>(
+    out: &mut W,
+    opts: &Options,
+    sw: &Stopwatch,
+    view: &View,
+) -> io::Result<()> {
+    if opts.inline {
+        return draw_inline(out, opts, sw, view);
+    }
+    let total = sw.total();
+    let layout = Layout::of(opts, sw, screen_size(view));
+    if layout.too_small {
+        return Ok(());
+    }
+
+    // redraw goal and time
+    draw_header(out, opts, sw, view, &layout)?;
+
+    // redraw running subgoals, on the rows `View::shown` puts them without
+    // building the list; the sorted list and the breakdown have none
+    if view.sorted || view.breakdown {
+        out.flush()?;
+        return Ok(());
+    }
+    let rows =
+        (0..sw.splits.len()).filter(|&i| !view.open_only || sw.splits[i].end_offset.is_none());
+    for (row, i) in rows.enumerate() {
+        let split = &sw.splits[i];
+        if split.end_offset.is_none() {
+            // only finished splits are outliers
+            let at = (i, layout.top + 1 + row as u16, None);
+            print_split_row(out, sw, at, Some(total), view, (opts, &layout))?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Single status line for `--inline`, redrawn in place on the current row.
+pub fn draw_inline<W: Write>(
+    out: &mut W,
+    opts: &Options,
+    sw: &Stopwatch,
+    view: &View,
+) -> io::Result<()> {
+    let fmt = &opts.format;
+    let total = sw.total();
+    let state = if sw.running { tr("run") } else { "---" };
+    let line = lazy(|f| {
+        write!(
+            f,
+            "[{}] {} {}",
+            state,
+            fmt.display(total),
+            goal_label(sw, view)
+        )?;
+        if let Some(target) = opts.target {
+            match target.checked_sub(total) {
+                Some(left) => write!(f, " [-{}]", fmt.display(left))?,
+                None => write!(f, " [+{}]", fmt.display(total - target))?,
+            }
+        }
+        if let Some(rate) = live_rate(sw, opts) {
+            write!(f, " {}", opts.rates.amount(total, rate))?;
+        }
+        if let Some(idx) = sw.active {
+            let split = &sw.splits[idx];
+            let rel = split.length(total);
+            write!(f, " > {} {}", view.name(&split.name), fmt.display(rel))?;
+        }
+        write!(f, " {}", trf_display("({} splits)", &[&sw.splits.len()]))?;
+        if let Some(keys) = &view.pending {
+            write!(f, " [{} ...]", keys)?;
+        }
+        if let Some(err) = &view.error {
+            write!(f, " | {}", trf_display("Error: {}", &[err]))?;
+        } else if let Some(msg) = &view.message {
+            write!(f, " | {}", msg)?;
+        }
+        Ok(())
+    });
+    out.queue(MoveToColumn(0))?;
+    out.queue(Clear(ClearType::CurrentLine))?;
+    // a line that wraps can't be redrawn in place; the last column is left
+    // free, as writing there wraps on some terminals
+    let (cols, _) = screen_size(view);
+    out.queue(Print(width::fit(line, cols.saturating_sub(1))))?;
+    out.flush()?;
+    Ok(())
+}
+
+/// The columns and rows to draw for: `View::size`, else the terminal's, or
+/// no limit when it can't be told.
+fn screen_size(view: &View) -> (usize, usize) {
+    match view.size.map_or_else(terminal::size, Ok) {
+        Ok((cols, rows)) if cols > 0 && rows > 0 => (cols as usize, rows as usize),
+        _ => (usize::MAX, usize::MAX),
+    }
+}
+
+/// Hourly rate for the running goal, if billing applies to it.
+fn live_rate(sw: &Stopwatch, opts: &Options) -> Option<f64> {
+    let goal = sw.main_goal.as_deref()?;
+    opts.rates.goal_rate(goal, sw.project.as_deref())
+}
+
+/// The main goal, followed by the project when there is one.
+fn goal_label<'a>(sw: &'a Stopwatch, view: &'a View) -> impl fmt::Display + 'a {
+    lazy(move |f| {
+        f.write_str(
+            sw.main_goal
+                .as_deref()
+                .map_or(tr("(none)"), |g| view.name(g)),
+        )?;
+        if let Some(project) = &sw.project {
+            write!(f, " [{}]", view.name(project))?;
+        }
+        Ok(())
+    })
+}
+
+/// Plain-text description of the session, used for copying to the clipboard.
+pub fn session_summary(sw: &Stopwatch, fmt: &DurationFormat) -> String {
+    let total = sw.total();
+    let mut text = format!(
+        "{} - {}\n",
+        sw.main_goal.as_deref().unwrap_or(tr("(none)")),
+        fmt.format(total)
+    );
+    for (i, split) in sw.splits.iter().enumerate() {
+        text.push_str(&"  ".repeat(split.level));
+        text.push_str(&split_line(i, split, Some(total), fmt));
+        text.push('\n');
+    }
+    text
+}
+
+/// The input thread and `prompt` both read the terminal, and whichever
+/// holds this is the one reading. Otherwise the keys typed at a prompt could
+/// go to the input thread instead: on Windows each one goes to only one of
+/// them.
+static READER: Mutex<()> = Mutex::new(());
+/// Set while a prompt waits for `READER`, so the input thread lets go.
+static PROMPTING: AtomicBool = AtomicBool::new(false);
+/// Answers to the prompts from `--input-script`, taken in place of the
+/// terminal's until it runs out.
+static ANSWERS: Mutex<Option<mpsc::Receiver<String>>> = Mutex::new(None);
+
+pub fn answer_prompts_from(answers: mpsc::Receiver<String>) {
+    *ANSWERS.lock().unwrap_or_else(PoisonError::into_inner) = Some(answers);
+}
+
+/// Wait up to `timeout` for a terminal event, for the input thread. Nothing
+/// is read while a prompt is.
+pub fn next_event(timeout: Duration) -> io::Result<Option<Event>> {
+    if PROMPTING.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(10));
+        return Ok(None);
+    }
+    let _reading = READER.lock().unwrap_or_else(PoisonError::into_inner);
+    if poll(timeout)? {
+        read().map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Read a line in cooked mode. In inline mode the prompt reuses the status
+/// line and the cursor is moved back up afterwards so the display stays on
+/// one row.
+pub fn prompt<W: Write>(out: &mut W, opts: &Options, text: &str) -> io::Result<String> {
+    if let Some(answers) = ANSWERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+    {
+        if let Ok(answer) = answers.recv() {
+            log::debug!("scripted answer '{}' to '{}'", answer, text.trim());
+            return Ok(answer);
+        }
+    }
+    PROMPTING.store(true, Ordering::Relaxed);
+    let _reading = READER.lock().unwrap_or_else(PoisonError::into_inner);
+    PROMPTING.store(false, Ordering::Relaxed);
+    disable_raw_mode()?;
+    if opts.inline {
+        out.queue(MoveToColumn(0))?;
+        out.queue(Clear(ClearType::CurrentLine))?;
+        out.queue(Print(text))?;
+    } else {
+        // a bare \n only moves down a line in some consoles
+        out.queue(Print("\r\n"))?;
+        out.queue(Print(text))?;
+    }
+    out.flush()?;
+    let mut input = String::new();
+    if io::stdin().is_terminal() {
+        io::stdin().read_line(&mut input)?;
+    } else {
+        // stdin is `--split-on-line`'s
+        io::BufReader::new(platform::tty()?).read_line(&mut input)?;
+    }
+    if opts.inline {
+        out.queue(MoveToPreviousLine(1))?;
+        out.flush()?;
+    }
+    enable_raw_mode()?;
+    Ok(input.trim().to_string())
+}
+
+/// Switch to the alternate screen and raw mode so the shell's scrollback is
+/// left untouched while the stopwatch is running. Inline mode stays on the
+/// main screen. In low-power mode the terminal is asked to report focus
+/// changes, so redraws can stop while nobody is looking.
+pub fn setup_terminal<W: Write>(out: &mut W, opts: &Options) -> io::Result<()> {
+    if !opts.inline {
+        out.queue(EnterAlternateScreen)?;
+    }
+    if opts.low_power {
+        out.queue(EnableFocusChange)?;
+    }
+    out.flush()?;
+    enable_raw_mode()
+}
+
+pub fn restore_terminal<W: Write>(out: &mut W, opts: &Options) -> io::Result<()> {
+    disable_raw_mode()?;
+    if opts.low_power {
+        out.queue(DisableFocusChange)?;
+    }
+    if opts.inline {
+        out.queue(Print("\r\n"))?;
+    } else {
+        out.queue(LeaveAlternateScreen)?;
+    }
+    out.flush()
+}
+
+/// Printed to stdout after quitting so the result stays in the shell history.
+pub fn exit_summary(sw: &Stopwatch, fmt: &DurationFormat) -> Option<String> {
+    let goal = sw.main_goal.as_deref()?;
+    let total = sw.total();
+    let mut text = format!("{}\n", trf("Goal   : {}", &[&goal]));
+    if let Some(project) = &sw.project {
+        text.push_str(&format!("{}\n", trf("Project: {}", &[project])));
+    }
+    text.push_str(&format!("{}\n", trf("Active : {}", &[&fmt.format(total)])));
+    text.push_str(&format!(
+        "{}\n",
+        trf("Paused : {}", &[&fmt.format(sw.paused())])
+    ));
+    text.push_str(&format!("{}\n", trf("Splits : {}", &[&sw.splits.len()])));
+
+    let mut longest: Vec<(Duration, &str)> = sw
+        .splits
+        .iter()
+        .map(|s| (s.length(total), s.name.as_str()))
+        .collect();
+    longest.sort_by_key(|&(dur, _)| std::cmp::Reverse(dur));
+    if !longest.is_empty() {
+        text.push_str(&format!("{}\n", tr("Longest:")));
+    }
+    for (i, (dur, name)) in longest.iter().take(5).enumerate() {
+        text.push_str(&format!("  {}. {} {}\n", i + 1, fmt.format(*dur), name));
+    }
+    Some(text)
+}
+
+/// Each phase of a routine against the time its split took, for the exit
+/// summary. Phases that never started are left out.
+pub fn plan_summary(sw: &Stopwatch, seq: &Sequence, fmt: &DurationFormat) -> Option<String> {
+    seq.goal.as_ref()?;
+    let total = sw.total();
+    let mut text = String::new();
+    for phase in &seq.phases {
+        let Some(split) = sw
+            .splits
+            .iter()
+            .find(|s| s.parent.is_none() && s.name == phase.name)
+        else {
+            continue;
+        };
+        let ran = split.length(total);
+        let diff = match ran.checked_sub(phase.length) {
+            Some(over) => format!("+{}", fmt.format(over)),
+            None => format!("-{}", fmt.format(phase.length - ran)),
+        };
+        text.push_str(&format!(
+            "  {} / {} ({}) {}\n",
+            fmt.format(ran),
+            fmt.format(phase.length),
+            diff,
+            phase.name
+        ));
+    }
+    if text.is_empty() {
+        return None;
+    }
+    Some(format!("{}\n{}", tr("Plan   :"), text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stopwatch::{Action, Clock};
+
+    const WIDE: (u16, u16) = (100, 16);
+
+    /// A goal on the `--simulate` clock with `steps` applied, each that many
+    /// seconds after it started.
+    fn session(steps: &[(u64, Action)], until: u64) -> Stopwatch {
+        let mut sw = Stopwatch::new();
+        sw.clock = Clock::simulated(crate::simulation_start());
+        let mut now = 0;
+        for (at, action) in steps {
+            sw.clock.advance(Duration::from_secs(at - now));
+            now = *at;
+            sw.apply(action);
+        }
+        sw.clock.advance(Duration::from_secs(until - now));
+        sw
+    }
+
+    fn start() -> (u64, Action) {
+        let goal = "Write the report".to_string();
+        let project = None;
+        (0, Action::Start { goal, project })
+    }
+
+    fn split(at: u64, name: &str, parent: Option<usize>) -> (u64, Action) {
+        let name = name.to_string();
+        (at, Action::Split { name, parent })
+    }
+
+    /// What the terminal shows after `frame`, a line of text for each row,
+    /// colors and attributes left out.
+    fn screen(frame: &[u8], (cols, rows): (u16, u16)) -> String {
+        let (cols, rows) = (cols as usize, rows as usize);
+        let mut grid = vec![vec![' '; cols]; rows];
+        let (mut row, mut col) = (0, 0);
+        let text = String::from_utf8(frame.to_vec()).unwrap();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        let end = chars.by_ref().find(|&c| {
+                            params.push(c);
+                            ('@'..='~').contains(&c)
+                        });
+                        params.pop();
+                        let args: Vec<usize> = params
+                            .trim_start_matches('?')
+                            .split(';')
+                            .map(|n| n.parse().unwrap_or(0))
+                            .collect();
+                        match end {
+                            Some('H') => {
+                                row = args[0].max(1) - 1;
+                                col = args.get(1).copied().unwrap_or(1).max(1) - 1;
+                            }
+                            Some('G') => col = args[0].max(1) - 1,
+                            Some('J') => grid = vec![vec![' '; cols]; rows],
+                            Some('K') => {
+                                if let Some(line) = grid.get_mut(row) {
+                                    line.fill(' ');
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    // the title, up to BEL
+                    Some(']') => for _ in chars.by_ref().take_while(|&c| c != '\x07') {},
+                    _ => {}
+                },
+                '\r' => col = 0,
+                '\n' => row += 1,
+                _ => {
+                    // wrap as a terminal does
+                    if col == cols {
+                        (row, col) = (row + 1, 0);
+                    }
+                    if let Some(cell) = grid.get_mut(row).and_then(|line| line.get_mut(col)) {
+                        *cell = c;
+                    }
+                    col += 1;
+                }
+            }
+        }
+        let lines: Vec<String> = grid
+            .iter()
+            .map(|line| line.iter().collect::<String>().trim_end().to_string())
+            .collect();
+        lines.join("\n").trim_end().to_string()
+    }
+
+    fn draw(sw: &Stopwatch, view: &View) -> String {
+        let size = view.size.unwrap();
+        let mut frame = Vec::new();
+        draw_static(&mut frame, &Options::default(), sw, view).unwrap();
+        screen(&frame, size)
+    }
+
+    fn draw_line(sw: &Stopwatch, view: &View) -> String {
+        let size = view.size.unwrap();
+        let mut frame = Vec::new();
+        draw_inline(&mut frame, &Options::default(), sw, view).unwrap();
+        screen(&frame, size)
+    }
+
+    fn view(size: (u16, u16)) -> View {
+        View {
+            size: Some(size),
+            ..View::default()
+        }
+    }
+
+    /// The two lines of keys at the bottom, as they wrap at `WIDE`.
+    const CONTROLS: &str = "\
+Controls: s=start/stop r=reset c=continue g=subgoal n=nested h=stop u=up d=redraw t=save-log q=quit
+          up/down=select 1-9=stop-split m=move <>=level J=merge x=cut p=project G=goal f=next-goal v
+=view o=open-only S=sort b=breakdown P=private y=copy-time Y=copy-split w=copy-summary a=quick-split
+ e=name";
+
+    fn nested() -> Stopwatch {
+        session(
+            &[
+                start(),
+                split(60, "Draft", None),
+                split(180, "Introduction", Some(0)),
+                (480, Action::StopActive),
+                split(480, "Body", Some(0)),
+            ],
+            600,
+        )
+    }
+
+    #[test]
+    fn empty() {
+        let sw = session(&[], 0);
+        let expected = "\
+=== Stopwatch ===
+Goal  : (none)
+Time  : 00:00:00.000
+Subgoals (0):
+
+
+";
+        assert_eq!(draw(&sw, &view(WIDE)), format!("{}{}", expected, CONTROLS));
+        assert_eq!(
+            draw_line(&sw, &view(WIDE)),
+            "[---] 00:00:00.000 (none) (0 splits)"
+        );
+    }
+
+    #[test]
+    fn running() {
+        let sw = session(&[start()], 754);
+        let expected = "\
+=== Stopwatch ===
+Goal  : Write the report
+Time  : 00:12:34.000  ⠋
+Subgoals (0):
+
+
+";
+        assert_eq!(draw(&sw, &view(WIDE)), format!("{}{}", expected, CONTROLS));
+        assert_eq!(
+            draw_line(&sw, &view(WIDE)),
+            "[run] 00:12:34.000 Write the report (0 splits)"
+        );
+    }
+
+    #[test]
+    fn nested_splits() {
+        let sw = nested();
+        let expected = "\
+=== Stopwatch ===
+Goal  : Write the report
+Time  : 00:10:00.000  ⠋
+Lap   : 00:02:00.000
+Subgoals (3):
+   1) 00:01:00.000 -> --:--:--.--- = --:--:--.--- Draft [Σ 00:07:00.000]
+     2) 00:03:00.000 -> 00:08:00.000 = 00:05:00.000 Introduction
+▶    3) 00:08:00.000 -> --:--:--.--- = --:--:--.--- Body
+
+
+";
+        assert_eq!(draw(&sw, &view(WIDE)), format!("{}{}", expected, CONTROLS));
+        assert_eq!(
+            draw_line(&sw, &view(WIDE)),
+            "[run] 00:10:00.000 Write the report > Body 00:02:00.000 (3 splits)"
+        );
+    }
+
+    #[test]
+    fn paused() {
+        let sw = session(
+            &[start(), split(60, "Draft", None), (300, Action::Pause)],
+            480,
+        );
+        let expected = "\
+=== Stopwatch ===
+Goal  : Write the report
+Time  : 00:05:00.000  ⠋
+Lap   : 00:04:00.000
+Subgoals (1):
+▶  1) 00:01:00.000 -> --:--:--.--- = --:--:--.--- Draft
+
+
+";
+        assert_eq!(draw(&sw, &view(WIDE)), format!("{}{}", expected, CONTROLS));
+        assert_eq!(
+            draw_line(&sw, &view(WIDE)),
+            "[---] 00:05:00.000 Write the report > Draft 00:04:00.000 (1 splits)"
+        );
+    }
+
+    #[test]
+    fn narrow_terminal() {
+        let sw = nested();
+        assert_eq!(
+            draw(&sw, &view((40, 16))),
+            "\
+=== Stopwatch ===
+Goal  : Write the report
+Time  : 00:10:00.000  ⠋
+Lap   : 00:02:00.000
+Subgoals (3):
+   1) --:--:--.--- Dra… [Σ 00:07:00.000]
+     2) 00:05:00.000 Introduction
+▶    3) --:--:--.--- Body
+
+
+Controls: s=start/stop r=reset c=contin…
+          up/down=select 1-9=stop-split…"
+        );
+        // the last column is left free
+        assert_eq!(
+            draw_line(&sw, &view((40, 16))),
+            "[run] 00:10:00.000 Write the report > …"
+        );
+        assert_eq!(draw(&sw, &view((20, 6))), "Terminal too small");
+    }
+}
@@ -0,0 +1,163 @@
+//! A JavaScript interface to the timer, for a web page to drive the same
+//! engine as `sw`. It is only built for WebAssembly, as a WASI module, since
+//! the timer reads the clock through std:
+//!
+//! ```text
+//! cargo build --lib --release --no-default-features --target wasm32-wasip1
+//! ```
+//!
+//! and loaded with a WASI shim in the browser. Strings cross as UTF-8 in the
+//! module's memory: the page writes its text into a buffer from `sw_alloc`,
+//! and reads replies from `sw_output` after a call says how long they are.
+//!
+//! ```text
+//! const ptr = sw_alloc(bytes.length);
+//! new Uint8Array(memory.buffer, ptr, bytes.length).set(bytes);
+//! const err = sw_start(ptr, bytes.length);
+//! sw_free(ptr, bytes.length);
+//! sw_pause();
+//! const len = sw_state();
+//! const state = JSON.parse(decode(memory.buffer, sw_output(), len));
+//! ```
+//!
+//! Calls that can fail return 0, or the length of the error in `sw_output`.
+
+use std::cell::RefCell;
+
+use crate::ffi::state;
+use crate::journal;
+use crate::stopwatch::{Action, Stopwatch};
+
+thread_local! {
+    static TIMER: RefCell<Stopwatch> = RefCell::new(Stopwatch::new());
+    static OUTPUT: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Room for `len` bytes of input.
+#[no_mangle]
+pub extern "C" fn sw_alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// Give back a buffer from `sw_alloc`.
+///
+/// # Safety
+///
+/// `ptr` and `len` must be what `sw_alloc` was called with and returned.
+#[no_mangle]
+pub unsafe extern "C" fn sw_free(ptr: *mut u8, len: usize) {
+    drop(Vec::from_raw_parts(ptr, 0, len));
+}
+
+/// Where the text of the last reply starts.
+#[no_mangle]
+pub extern "C" fn sw_output() -> *const u8 {
+    OUTPUT.with(|out| out.borrow().as_ptr())
+}
+
+/// Start timing the goal named by the `len` bytes at `ptr`, dropping any
+/// splits of the goal before.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` initialized bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sw_start(ptr: *const u8, len: usize) -> usize {
+    let result = text(ptr, len).map(|goal| {
+        act(Action::Start {
+            goal: goal.to_string(),
+            project: None,
+        })
+    });
+    done(result)
+}
+
+/// Start a split named by the `len` bytes at `ptr` under the split at index
+/// `parent`, or at the top level when `parent` is negative. It becomes the
+/// active split.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` initialized bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sw_split(ptr: *const u8, len: usize, parent: i64) -> usize {
+    let result = text(ptr, len).and_then(|name| {
+        let parent = usize::try_from(parent).ok();
+        let splits = TIMER.with(|sw| sw.borrow().splits.len());
+        if parent.is_some_and(|p| p >= splits) {
+            return Err(format!("no split {}", parent.unwrap_or_default()));
+        }
+        act(Action::Split {
+            name: name.to_string(),
+            parent,
+        });
+        Ok(())
+    });
+    done(result)
+}
+
+#[no_mangle]
+pub extern "C" fn sw_pause() {
+    act(Action::Pause);
+}
+
+#[no_mangle]
+pub extern "C" fn sw_resume() {
+    act(Action::Resume);
+}
+
+/// Stop the active split; its parent, if any, becomes the active one.
+#[no_mangle]
+pub extern "C" fn sw_stop() {
+    act(Action::StopActive);
+}
+
+/// Apply any action given as a journal record, e.g.
+/// `{"t":"2024-05-01T09:00:00.000Z","event":"split","name":"Tests"}`; `t` is
+/// when it happened.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` initialized bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sw_apply(ptr: *const u8, len: usize) -> usize {
+    let result =
+        text(ptr, len).and_then(|text| TIMER.with(|sw| journal::apply(&mut sw.borrow_mut(), text)));
+    done(result)
+}
+
+/// Put the timer's state in `sw_output` as JSON, the same as
+/// `sw_timer_serialize` gives C callers, returning its length.
+#[no_mangle]
+pub extern "C" fn sw_state() -> usize {
+    let text = TIMER.with(|sw| state(&sw.borrow()));
+    reply(text)
+}
+
+/// The `len` bytes at `ptr` as a `&str`, or why they can't be.
+unsafe fn text<'a>(ptr: *const u8, len: usize) -> Result<&'a str, String> {
+    let bytes = std::slice::from_raw_parts(ptr, len);
+    std::str::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+fn act(action: Action) {
+    TIMER.with(|sw| sw.borrow_mut().apply(&action));
+}
+
+/// 0 for success, or the length of the error put in `sw_output`.
+fn done(result: Result<(), String>) -> usize {
+    match result {
+        Ok(()) => 0,
+        Err(e) => reply(e),
+    }
+}
+
+fn reply(text: String) -> usize {
+    OUTPUT.with(|out| {
+        *out.borrow_mut() = text;
+        out.borrow().len()
+    })
+}
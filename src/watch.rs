@@ -0,0 +1,97 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+use crate::Message;
+
+/// How often the paths are looked at. Short, since lock files often don't
+/// last long; one there only between two looks is missed.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Enough of a path's metadata to tell the file there from another made in
+/// its place between two looks, which `exists` alone can't.
+#[derive(PartialEq)]
+struct Identity {
+    created: Option<SystemTime>,
+    #[cfg(unix)]
+    inode: u64,
+}
+
+impl Identity {
+    fn of(path: &Path) -> Option<Identity> {
+        let meta = fs::metadata(path).ok()?;
+        Some(Identity {
+            created: meta.created().ok(),
+            #[cfg(unix)]
+            inode: std::os::unix::fs::MetadataExt::ino(&meta),
+        })
+    }
+}
+
+/// Watches paths and reports each one appearing or going away, so the
+/// stopwatch can time whatever holds them, e.g. a process and its lock
+/// file. Paths already there when it starts count as seen. It polls, so a
+/// path there only briefly can be missed. It stops when dropped.
+pub struct Watcher {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    pub fn start(paths: &[PathBuf], tx: mpsc::Sender<Message>) -> Watcher {
+        let paths = paths.to_vec();
+        let mut seen: Vec<Option<Identity>> = paths.iter().map(|p| Identity::of(p)).collect();
+        log::info!("watching {} paths", paths.len());
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                while !shutdown.load(Ordering::Relaxed) {
+                    thread::sleep(POLL_INTERVAL);
+                    for (path, seen) in paths.iter().zip(&mut seen) {
+                        let now = Identity::of(path);
+                        if now == *seen {
+                            continue;
+                        }
+                        // replaced: gone, then back
+                        let changes = match (seen.is_some(), now.is_some()) {
+                            (true, true) => &[false, true][..],
+                            (_, exists) => &[exists][..],
+                        };
+                        *seen = now;
+                        for &exists in changes {
+                            log::info!(
+                                "{} {}",
+                                path.display(),
+                                if exists { "appeared" } else { "went away" }
+                            );
+                            if tx.send(Message::Watched(path.clone(), exists)).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            })
+        };
+        Watcher {
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
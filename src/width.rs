@@ -0,0 +1,293 @@
+use std::fmt::{self, Display, Write};
+
+/// Columns a character takes in a terminal: 2 for East Asian wide and
+/// fullwidth characters and most emoji, 0 for combining marks, joiners and
+/// variation selectors, 1 otherwise. The ranges cover the blocks names are
+/// written in rather than the whole Unicode table.
+pub fn char_width(c: char) -> usize {
+    let c = c as u32;
+    if c < 0x300 {
+        return usize::from(c >= 0x20 && c != 0x7f);
+    }
+    if in_ranges(c, ZERO) {
+        0
+    } else if in_ranges(c, WIDE) {
+        2
+    } else {
+        1
+    }
+}
+
+fn in_ranges(c: u32, ranges: &[(u32, u32)]) -> bool {
+    ranges
+        .binary_search_by(|&(lo, hi)| {
+            if hi < c {
+                std::cmp::Ordering::Less
+            } else if lo > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+pub fn of_str(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// The width of `d` as displayed, without building the string.
+pub fn of(d: &impl Display) -> usize {
+    let mut counter = Columns {
+        used: 0,
+        max: usize::MAX,
+    };
+    // only fails once past `max`
+    let _ = write!(counter, "{}", d);
+    counter.used
+}
+
+/// `s` cut down to `max` columns, ending in `…` when anything was cut.
+pub fn ellipsize(s: &str, max: usize) -> Ellipsized<'_> {
+    Ellipsized { s, max }
+}
+
+pub struct Ellipsized<'a> {
+    s: &'a str,
+    max: usize,
+}
+
+impl Display for Ellipsized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if of_str(self.s) <= self.max {
+            return f.write_str(self.s);
+        }
+        if self.max == 0 {
+            return Ok(());
+        }
+        let mut used = 0;
+        for c in self.s.chars() {
+            let w = char_width(c);
+            if used + w > self.max - 1 {
+                break;
+            }
+            used += w;
+            f.write_char(c)?;
+        }
+        f.write_char('…')
+    }
+}
+
+/// `d` cut down to `max` columns like `ellipsize`, for a whole line.
+pub fn fit<D: Display>(d: D, max: usize) -> Fit<D> {
+    Fit { d, max }
+}
+
+pub struct Fit<D> {
+    d: D,
+    max: usize,
+}
+
+impl<D: Display> Display for Fit<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if of(&self.d) <= self.max {
+            return write!(f, "{}", self.d);
+        }
+        if self.max == 0 {
+            return Ok(());
+        }
+        let mut cut = Cut {
+            inner: f,
+            columns: Columns {
+                used: 0,
+                max: self.max - 1,
+            },
+        };
+        // stops with an error where the room runs out
+        let _ = write!(cut, "{}", self.d);
+        f.write_char('…')
+    }
+}
+
+/// Counts columns, failing once there are more than `max`.
+struct Columns {
+    used: usize,
+    max: usize,
+}
+
+impl Columns {
+    fn take(&mut self, c: char) -> fmt::Result {
+        let w = char_width(c);
+        if self.used + w > self.max {
+            return Err(fmt::Error);
+        }
+        self.used += w;
+        Ok(())
+    }
+}
+
+impl Write for Columns {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars().try_for_each(|c| self.take(c))
+    }
+}
+
+/// Passes text on until `columns` is full.
+struct Cut<'a, 'b> {
+    inner: &'a mut fmt::Formatter<'b>,
+    columns: Columns,
+}
+
+impl Write for Cut<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.columns.take(c)?;
+            self.inner.write_char(c)?;
+        }
+        Ok(())
+    }
+}
+
+/// Combining marks, zero-width spaces and joiners, variation selectors,
+/// emoji skin tone modifiers and tag characters.
+const ZERO: &[(u32, u32)] = &[
+    (0x0300, 0x036f),
+    (0x0483, 0x0489),
+    (0x0591, 0x05bd),
+    (0x05bf, 0x05bf),
+    (0x05c1, 0x05c2),
+    (0x05c4, 0x05c5),
+    (0x05c7, 0x05c7),
+    (0x0610, 0x061a),
+    (0x064b, 0x065f),
+    (0x0670, 0x0670),
+    (0x06d6, 0x06dc),
+    (0x06df, 0x06e4),
+    (0x06e7, 0x06e8),
+    (0x06ea, 0x06ed),
+    (0x0900, 0x0902),
+    (0x093a, 0x093a),
+    (0x093c, 0x093c),
+    (0x0941, 0x0948),
+    (0x094d, 0x094d),
+    (0x0951, 0x0957),
+    (0x0962, 0x0963),
+    (0x0e31, 0x0e31),
+    (0x0e34, 0x0e3a),
+    (0x0e47, 0x0e4e),
+    (0x1160, 0x11ff),
+    (0x1ab0, 0x1aff),
+    (0x1dc0, 0x1dff),
+    (0x200b, 0x200f),
+    (0x202a, 0x202e),
+    (0x2060, 0x2064),
+    (0x20d0, 0x20ff),
+    (0x302a, 0x302d),
+    (0x3099, 0x309a),
+    (0xfe00, 0xfe0f),
+    (0xfe20, 0xfe2f),
+    (0xfeff, 0xfeff),
+    (0x1f3fb, 0x1f3ff),
+    (0xe0000, 0xe007f),
+    (0xe0100, 0xe01ef),
+];
+
+/// East Asian wide and fullwidth blocks, and emoji shown two columns wide.
+const WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115f),
+    (0x231a, 0x231b),
+    (0x2329, 0x232a),
+    (0x23e9, 0x23ec),
+    (0x23f0, 0x23f0),
+    (0x23f3, 0x23f3),
+    (0x25fd, 0x25fe),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267f, 0x267f),
+    (0x2693, 0x2693),
+    (0x26a1, 0x26a1),
+    (0x26aa, 0x26ab),
+    (0x26bd, 0x26be),
+    (0x26c4, 0x26c5),
+    (0x26ce, 0x26ce),
+    (0x26d4, 0x26d4),
+    (0x26ea, 0x26ea),
+    (0x26f2, 0x26f3),
+    (0x26f5, 0x26f5),
+    (0x26fa, 0x26fa),
+    (0x26fd, 0x26fd),
+    (0x2705, 0x2705),
+    (0x270a, 0x270b),
+    (0x2728, 0x2728),
+    (0x274c, 0x274c),
+    (0x274e, 0x274e),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27b0, 0x27b0),
+    (0x27bf, 0x27bf),
+    (0x2b1b, 0x2b1c),
+    (0x2b50, 0x2b50),
+    (0x2b55, 0x2b55),
+    (0x2e80, 0x3029),
+    (0x302e, 0x303e),
+    (0x3041, 0x3096),
+    (0x309b, 0x33ff),
+    (0x3400, 0x4dbf),
+    (0x4e00, 0x9fff),
+    (0xa000, 0xa4cf),
+    (0xa960, 0xa97f),
+    (0xac00, 0xd7a3),
+    (0xf900, 0xfaff),
+    (0xfe10, 0xfe19),
+    (0xfe30, 0xfe6f),
+    (0xff00, 0xff60),
+    (0xffe0, 0xffe6),
+    (0x16fe0, 0x16fe4),
+    (0x17000, 0x18cff),
+    (0x1b000, 0x1b2ff),
+    (0x1f004, 0x1f004),
+    (0x1f0cf, 0x1f0cf),
+    (0x1f18e, 0x1f18e),
+    (0x1f191, 0x1f19a),
+    (0x1f200, 0x1f251),
+    (0x1f300, 0x1f320),
+    (0x1f32d, 0x1f335),
+    (0x1f337, 0x1f37c),
+    (0x1f37e, 0x1f393),
+    (0x1f3a0, 0x1f3ca),
+    (0x1f3cf, 0x1f3d3),
+    (0x1f3e0, 0x1f3f0),
+    (0x1f3f4, 0x1f3f4),
+    (0x1f3f8, 0x1f3fa),
+    (0x1f400, 0x1f43e),
+    (0x1f440, 0x1f440),
+    (0x1f442, 0x1f4fc),
+    (0x1f4ff, 0x1f53d),
+    (0x1f54b, 0x1f54e),
+    (0x1f550, 0x1f567),
+    (0x1f57a, 0x1f57a),
+    (0x1f595, 0x1f596),
+    (0x1f5a4, 0x1f5a4),
+    (0x1f5fb, 0x1f64f),
+    (0x1f680, 0x1f6c5),
+    (0x1f6cc, 0x1f6cc),
+    (0x1f6d0, 0x1f6d2),
+    (0x1f6d5, 0x1f6d7),
+    (0x1f6dc, 0x1f6df),
+    (0x1f6eb, 0x1f6ec),
+    (0x1f6f4, 0x1f6fc),
+    (0x1f7e0, 0x1f7eb),
+    (0x1f7f0, 0x1f7f0),
+    (0x1f90c, 0x1f93a),
+    (0x1f93c, 0x1f945),
+    (0x1f947, 0x1f9ff),
+    (0x1fa70, 0x1fa7c),
+    (0x1fa80, 0x1fa89),
+    (0x1fa8f, 0x1fac6),
+    (0x1face, 0x1fadc),
+    (0x1fadf, 0x1fae9),
+    (0x1faf0, 0x1faf8),
+    (0x20000, 0x2fffd),
+    (0x30000, 0x3fffd),
+];
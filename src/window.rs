@@ -0,0 +1,171 @@
+use std::{
+    io,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::Message;
+
+/// How often the focused window is looked up.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What to do when another window gets the focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Follow {
+    /// Start a new split named after the window.
+    Split,
+    /// Show the window's title and run the focus hooks, leaving the splits
+    /// alone.
+    Note,
+}
+
+impl Follow {
+    pub fn from_name(name: &str) -> Option<Follow> {
+        match name {
+            "split" => Some(Follow::Split),
+            "note" => Some(Follow::Note),
+            _ => None,
+        }
+    }
+}
+
+/// Watches which window has the focus and reports the title of each new
+/// one. The title is read with the desktop's own tools: `osascript` on
+/// macOS, `hyprctl` on Hyprland and `xdotool` or `xprop` under X11.
+/// Other Wayland compositors don't tell other programs, so only their X11
+/// windows are seen. It stops when dropped.
+pub struct Watcher {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    pub fn start(tx: mpsc::Sender<Message>) -> io::Result<Watcher> {
+        // fail now if there is no way to ask rather than silently later
+        let mut last = focused().map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => io::Error::new(
+                e.kind(),
+                "nothing to ask with; install xdotool or xprop, or hyprctl on Hyprland",
+            ),
+            _ => e,
+        })?;
+        log::info!("following the focused window, now '{}'", last);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                let step = Duration::from_millis(100);
+                let mut waited = Duration::ZERO;
+                while !shutdown.load(Ordering::Relaxed) {
+                    // short sleeps so the shutdown flag is noticed
+                    thread::sleep(step);
+                    waited += step;
+                    if waited < POLL_INTERVAL {
+                        continue;
+                    }
+                    waited = Duration::ZERO;
+                    let title = match focused() {
+                        Ok(title) => title,
+                        Err(e) => {
+                            log::debug!("focused window: {}", e);
+                            continue;
+                        }
+                    };
+                    // nothing focused, e.g. between closing a window and the
+                    // next one taking over
+                    if title.is_empty() || title == last {
+                        continue;
+                    }
+                    log::info!("focus moved to '{}'", title);
+                    if tx.send(Message::Focus(title.clone())).is_err() {
+                        break;
+                    }
+                    last = title;
+                }
+            })
+        };
+        Ok(Watcher {
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// The focused window's application and title.
+#[cfg(target_os = "macos")]
+fn focused() -> io::Result<String> {
+    const SCRIPT: &str = r#"tell application "System Events"
+    set front to first application process whose frontmost is true
+    set title to name of front
+    try
+        set title to title & " - " & (name of front window of front)
+    end try
+end tell
+return title"#;
+    run(Command::new("osascript").args(["-e", SCRIPT]))
+}
+
+/// The focused window's title.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn focused() -> io::Result<String> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        // "Window 55d8e0 -> title:" and then its properties
+        let text = run(Command::new("hyprctl").arg("activewindow"))?;
+        let first = text.lines().next().unwrap_or("");
+        return Ok(first
+            .split_once(" -> ")
+            .map(|(_, title)| title.trim_end_matches(':').to_string())
+            .unwrap_or_default());
+    }
+    match run(Command::new("xdotool").args(["getactivewindow", "getwindowname"])) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        title => return title,
+    }
+    // `_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007`
+    let active = run(Command::new("xprop").args(["-root", "_NET_ACTIVE_WINDOW"]))?;
+    let id = active.rsplit(' ').next().unwrap_or("");
+    if id.is_empty() || id == "0x0" {
+        return Ok(String::new());
+    }
+    // `_NET_WM_NAME(UTF8_STRING) = "title"`
+    let name = run(Command::new("xprop").args(["-id", id, "_NET_WM_NAME"]))?;
+    Ok(name
+        .split_once(" = ")
+        .map(|(_, title)| title.trim_matches('"').replace("\\\"", "\""))
+        .unwrap_or_default())
+}
+
+#[cfg(windows)]
+fn focused() -> io::Result<String> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the focused window can't be followed on Windows",
+    ))
+}
+
+/// What `command` printed, trimmed.
+fn run(command: &mut Command) -> io::Result<String> {
+    let output = command
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(stderr.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
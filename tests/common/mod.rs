@@ -0,0 +1,154 @@
+//! Running `sw` end to end on a pseudo-terminal of its own, in a directory
+//! of its own, with nothing from the user's config or environment.
+#![allow(dead_code)]
+
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    os::{
+        fd::{FromRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    path::PathBuf,
+    process::{Command, ExitStatus, Stdio},
+    ptr,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to leave the program to react to each input.
+const PAUSE: Duration = Duration::from_millis(400);
+/// How long a run may take before it is killed.
+const DEADLINE: Duration = Duration::from_secs(20);
+
+pub struct Sandbox {
+    pub dir: PathBuf,
+}
+
+pub struct Run {
+    pub status: ExitStatus,
+    /// Everything written to the terminal.
+    pub screen: String,
+}
+
+impl Run {
+    /// What is left on the terminal once `sw` has quit: the summary.
+    pub fn summary(&self) -> &str {
+        let start = self.screen.rfind("\x1b[?1049l").map_or(0, |i| i + 8);
+        &self.screen[start..]
+    }
+}
+
+impl Sandbox {
+    pub fn new(name: &str) -> Sandbox {
+        let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Sandbox { dir }
+    }
+
+    pub fn read(&self, file: &str) -> String {
+        let path = self.dir.join(file);
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+
+    pub fn write(&self, file: &str, text: &str) {
+        fs::write(self.dir.join(file), text).unwrap();
+    }
+
+    /// Run `sw` with `args` on an 80x24 terminal, typing each of `inputs` in
+    /// turn, and wait for it to quit.
+    pub fn run(&self, args: &[&str], inputs: &[&str]) -> Run {
+        let (master, slave) = open_pty(80, 24);
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_sw"));
+        cmd.args(args)
+            .current_dir(&self.dir)
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env("HOME", &self.dir)
+            .env("XDG_CONFIG_HOME", self.dir.join("config"))
+            .env("XDG_STATE_HOME", self.dir.join("state"))
+            .env("XDG_RUNTIME_DIR", &self.dir)
+            .env("LC_ALL", "C")
+            .env("TERM", "xterm-256color")
+            .env("NO_COLOR", "1")
+            .stdin(Stdio::from(slave.try_clone().unwrap()))
+            .stdout(Stdio::from(slave.try_clone().unwrap()))
+            .stderr(Stdio::from(slave));
+        // a session of its own with the pty as its controlling terminal,
+        // which crossterm opens as /dev/tty
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let mut child = cmd.spawn().unwrap();
+        // the parent's copies of the slave are gone with `cmd`
+        drop(cmd);
+
+        let (tx, rx) = mpsc::channel();
+        let mut reader = File::from(master.try_clone().unwrap());
+        thread::spawn(move || {
+            let mut buf = [0; 4096];
+            // EIO once the program has quit and the slave is closed
+            while let Ok(n @ 1..) = reader.read(&mut buf) {
+                if tx.send(buf[..n].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut writer = File::from(master);
+        thread::sleep(PAUSE);
+        for input in inputs {
+            writer.write_all(input.as_bytes()).unwrap();
+            thread::sleep(PAUSE);
+        }
+
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                break status;
+            }
+            if started.elapsed() > DEADLINE {
+                child.kill().unwrap();
+                panic!("sw did not quit; inputs were {:?}", inputs);
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        drop(writer);
+        let mut screen = Vec::new();
+        while let Ok(chunk) = rx.recv_timeout(Duration::from_secs(1)) {
+            screen.extend(chunk);
+        }
+        Run {
+            status,
+            screen: String::from_utf8_lossy(&screen).into_owned(),
+        }
+    }
+}
+
+fn open_pty(cols: u16, rows: u16) -> (OwnedFd, OwnedFd) {
+    let (mut master, mut slave) = (0, 0);
+    let mut size = libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let done = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            // `*mut` on macOS
+            ptr::addr_of_mut!(size),
+        )
+    };
+    assert_eq!(done, 0, "openpty: {}", std::io::Error::last_os_error());
+    unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) }
+}
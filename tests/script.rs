@@ -0,0 +1,140 @@
+//! Sessions played from an `--input-script` on the simulated clock, which
+//! come out the same to the millisecond on every run.
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+const REPORT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/report.sws");
+
+fn play(sandbox: &Sandbox, script: &str) -> common::Run {
+    sandbox.run(
+        &[
+            "--simulate",
+            "--input-script",
+            script,
+            "--journal",
+            "journal.jsonl",
+            "done.org",
+        ],
+        &[],
+    )
+}
+
+#[test]
+fn script_plays_a_session_and_saves_it() {
+    let sandbox = Sandbox::new("script_plays_a_session_and_saves_it");
+    let run = play(&sandbox, REPORT);
+    assert!(run.status.success(), "{}", run.screen);
+    assert_eq!(
+        run.summary(),
+        "Goal   : Write the report\r\n\
+         Active : 00:25:01.000\r\n\
+         Paused : 00:15:00.000\r\n\
+         Splits : 3\r\n\
+         Longest:\r\n  \
+         1. 00:10:00.000 Outline\r\n  \
+         2. 00:10:00.000 Draft\r\n  \
+         3. 00:08:00.000 Introduction\r\n"
+    );
+    assert_eq!(
+        sandbox.read("done.org"),
+        "* Write the report
+  :LOGBOOK:
+  CLOCK: [2000-01-03 09:00]--[2000-01-03 09:40] => 00:25:01.000
+  :END:
+
+** Outline
+  :LOGBOOK:
+  CLOCK: [2000-01-03 09:00]--[2000-01-03 09:10] => 00:10:00.000
+  :END:
+
+** Draft
+  :LOGBOOK:
+  CLOCK: [2000-01-03 09:30]--[2000-01-03 09:40] => 00:10:00.000
+  :END:
+
+*** Introduction
+  :LOGBOOK:
+  CLOCK: [2000-01-03 09:32]--[2000-01-03 09:40] => 00:08:00.000
+  :END:
+
+"
+    );
+}
+
+#[test]
+fn script_runs_the_same_every_time() {
+    let sandbox = Sandbox::new("script_runs_the_same_every_time");
+    let runs: Vec<String> = (0..2)
+        .map(|_| {
+            let _ = std::fs::remove_file(sandbox.dir.join("done.org"));
+            let _ = std::fs::remove_file(sandbox.dir.join("journal.jsonl"));
+            assert!(play(&sandbox, REPORT).status.success());
+            sandbox.read("done.org")
+        })
+        .collect();
+    assert_eq!(runs[0], runs[1]);
+}
+
+#[test]
+fn prompt_answers_come_from_the_script() {
+    let sandbox = Sandbox::new("prompt_answers_come_from_the_script");
+    sandbox.write(
+        "shed.sws",
+        "press s\ntype Tidy the shed\nwait 1s\npress g\ntype Sweep\nwait 10m\npress s t q\n",
+    );
+    let run = play(&sandbox, "shed.sws");
+    assert!(run.status.success(), "{}", run.screen);
+    assert!(
+        run.summary()
+            .starts_with("Goal   : Tidy the shed\r\nActive : 00:10:00.000\r\n"),
+        "{}",
+        run.summary()
+    );
+    assert!(
+        run.summary().contains("Splits : 1\r\n"),
+        "{}",
+        run.summary()
+    );
+    let log = sandbox.read("done.org");
+    assert!(log.starts_with("* Tidy the shed\n"), "{}", log);
+    assert!(
+        log.contains("CLOCK: [2000-01-03 09:00]--[2000-01-03 09:10] => 00:10:00.000"),
+        "{}",
+        log
+    );
+}
+
+#[test]
+fn untitled_goal_is_renamed_from_the_script() {
+    let sandbox = Sandbox::new("untitled_goal_is_renamed_from_the_script");
+    sandbox.write(
+        "rename.sws",
+        "press s s\nwait 5m\npress G\ntype Tidy the shed\nwait 5m\npress s t q\n",
+    );
+    let run = play(&sandbox, "rename.sws");
+    assert!(run.status.success(), "{}", run.screen);
+    assert!(
+        run.summary()
+            .starts_with("Goal   : Tidy the shed\r\nActive : 00:10:00.000\r\n"),
+        "{}",
+        run.summary()
+    );
+    let log = sandbox.read("done.org");
+    assert!(log.starts_with("* Tidy the shed\n"), "{}", log);
+}
+
+#[test]
+fn bad_script_is_reported_by_line() {
+    let sandbox = Sandbox::new("bad_script_is_reported_by_line");
+    sandbox.write("bad.sws", "press s\n# fine so far\nhold s\n");
+    let run = play(&sandbox, "bad.sws");
+    assert_eq!(run.status.code(), Some(2));
+    assert!(
+        run.screen.contains("bad.sws: line 3: unknown step 'hold'"),
+        "{}",
+        run.screen
+    );
+}
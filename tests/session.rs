@@ -0,0 +1,114 @@
+//! Sessions typed at the keyboard, on the `--simulate` clock so the log's
+//! dates are the same on every run.
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+const ARGS: &[&str] = &[
+    "--simulate",
+    "--chord-timeout",
+    "100ms",
+    "--journal",
+    "journal.jsonl",
+    "done.org",
+];
+
+#[test]
+fn subgoals_are_saved_under_the_goal() {
+    let sandbox = Sandbox::new("subgoals_are_saved_under_the_goal");
+    let run = sandbox.run(
+        ARGS,
+        &[
+            "s",
+            "Write the report\r",
+            "g",
+            "Outline\r",
+            "h",
+            "g",
+            "Draft\r",
+            "h",
+            "s",
+            "t",
+            "q",
+        ],
+    );
+    assert!(run.status.success(), "{}", run.screen);
+    let summary = run.summary();
+    assert!(summary.contains("Goal   : Write the report"), "{}", summary);
+    assert!(summary.contains("Splits : 2"), "{}", summary);
+    assert!(summary.contains(" Outline"), "{}", summary);
+    assert!(summary.contains(" Draft"), "{}", summary);
+
+    let log = sandbox.read("done.org");
+    let headings: Vec<&str> = log.lines().filter(|l| l.starts_with('*')).collect();
+    assert_eq!(headings, ["* Write the report", "** Outline", "** Draft"]);
+    let clocks: Vec<&str> = log
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("CLOCK: "))
+        .collect();
+    assert_eq!(clocks.len(), 3, "{}", log);
+    for clock in clocks {
+        assert!(
+            clock.starts_with("[2000-01-03 09:00]--[2000-01-03 09:0"),
+            "{}",
+            log
+        );
+    }
+}
+
+#[test]
+fn nested_subgoals_are_saved_a_level_down() {
+    let sandbox = Sandbox::new("nested_subgoals_are_saved_a_level_down");
+    let run = sandbox.run(
+        ARGS,
+        &[
+            "s",
+            "Write the report\r",
+            "g",
+            "Draft\r",
+            "n",
+            "Introduction\r",
+            "h",
+            "h",
+            "s",
+            "t",
+            "q",
+        ],
+    );
+    assert!(run.status.success(), "{}", run.screen);
+    assert!(run.summary().contains("Splits : 2"), "{}", run.summary());
+
+    let log = sandbox.read("done.org");
+    let headings: Vec<&str> = log.lines().filter(|l| l.starts_with('*')).collect();
+    assert_eq!(
+        headings,
+        ["* Write the report", "** Draft", "*** Introduction"]
+    );
+}
+
+#[test]
+fn goal_from_the_command_line_starts_straight_away() {
+    let sandbox = Sandbox::new("goal_from_the_command_line_starts_straight_away");
+    let args = [&["--goal", "Chores"], ARGS].concat();
+    let run = sandbox.run(&args, &["a", "Errands\r", "h", "s", "t", "q"]);
+    assert!(run.status.success(), "{}", run.screen);
+    assert!(
+        run.summary().contains("Goal   : Chores"),
+        "{}",
+        run.summary()
+    );
+
+    let log = sandbox.read("done.org");
+    assert!(log.starts_with("* Chores\n"), "{}", log);
+    assert!(log.contains("\n** Errands\n"), "{}", log);
+}
+
+#[test]
+fn nothing_is_saved_without_asking() {
+    let sandbox = Sandbox::new("nothing_is_saved_without_asking");
+    let run = sandbox.run(ARGS, &["s", "Write the report\r", "s", "q"]);
+    assert!(run.status.success(), "{}", run.screen);
+    assert!(!sandbox.dir.join("done.org").exists());
+}